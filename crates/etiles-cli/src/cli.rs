@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand, ValueHint};
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -9,6 +10,7 @@ pub struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Convert point cloud files to 3D Tiles
     ConvertPointCloud {
@@ -17,8 +19,12 @@ pub enum Commands {
         #[clap(long, value_hint = ValueHint::AnyPath, value_name = "PATH")]
         input_path: PathBuf,
 
-        /// Path to a TAR archive where the derived 3D Tiles will be stored. Extension must be .tar.
-        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        /// Path to a TAR archive where the derived 3D Tiles will be stored. Extension must be .tar,
+        /// or .tar.gz or .tar.zst for a compressed archive. With the extension .3dtiles, a SQLite
+        /// container is written instead. Without an extension, the entries are written as files
+        /// into a directory. A URL s3://{bucket}/{prefix} uploads the entries to an S3-compatible
+        /// bucket configured by the AWS_* environment variables.
+        #[clap(long, value_hint = ValueHint::AnyPath, value_name = "PATH")]
         output_path: PathBuf,
 
         /// Maximum number of points stored per octree node.
@@ -29,8 +35,58 @@ pub enum Commands {
 
         /// EPSG code of the coordinate reference system of the source point cloud
         /// (e.g. 25832 for ETRS89 / UTM zone 32N).
-        #[clap(long, value_name = "EPSG_CODE")]
-        source_crs: u32,
+        #[clap(long, value_name = "EPSG_CODE", required_unless_present = "local")]
+        source_crs: Option<u32>,
+
+        /// Treat the coordinates as engineering or local coordinates without a CRS (e.g. an
+        /// indoor or plant scan), which are stored without reprojection.
+        #[clap(
+            long,
+            conflicts_with_all = ["source_crs", "frame", "origin", "origin_ecef", "color_raster"]
+        )]
+        local: bool,
+
+        /// Geographic placement of the local origin on the globe (longitude and latitude in
+        /// degrees, ellipsoidal height in meters, optional clockwise heading of the y-axis from
+        /// north in degrees). Without a placement, the local coordinates are taken as ECEF.
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "LON,LAT,HEIGHT[,HEADING]",
+            allow_negative_numbers = true,
+            requires = "local"
+        )]
+        placement: Option<Vec<f64>>,
+
+        /// Local frame in which the points are stored, defining the root transform of the
+        /// tileset.
+        #[clap(long, value_enum, default_value_t = FrameArgument::SourceAxes, value_name = "FRAME")]
+        frame: FrameArgument,
+
+        /// Horizontal axes in which the octree is built. Principal axes suit elongated diagonal
+        /// extents such as roads, railways or pipelines.
+        #[clap(long, value_enum, default_value_t = AlignmentArgument::FrameAxes, value_name = "ALIGNMENT")]
+        alignment: AlignmentArgument,
+
+        /// Origin of the local frame (x,y,z in the source CRS). Defaults to the center of the
+        /// point cloud; a fixed origin lets tilesets of adjacent blocks share the same frame.
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "X,Y,Z",
+            allow_negative_numbers = true,
+            conflicts_with = "origin_ecef"
+        )]
+        origin: Option<Vec<f64>>,
+
+        /// Origin of the local frame as ECEF coordinates (x,y,z in EPSG:4978).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "X,Y,Z",
+            allow_negative_numbers = true
+        )]
+        origin_ecef: Option<Vec<f64>>,
 
         /// Disable random shuffling of points before building the octree.
         /// Shuffling is on by default and improves spatial distribution across tiles.
@@ -41,5 +97,384 @@ pub enum Commands {
         /// Only used when --no-shuffle is not set.
         #[clap(long, default_value_t = 1, value_name = "SEED")]
         seed: u64,
+
+        /// Only keep points of these classification codes (e.g. 2,6 for ground and buildings).
+        /// Requires a classification column in the point data.
+        #[clap(long, value_delimiter = ',', value_name = "CLASS")]
+        include_classes: Option<Vec<u8>>,
+
+        /// Drop points of these classification codes (e.g. 7,18 for low and high noise).
+        /// Requires a classification column in the point data.
+        #[clap(long, value_delimiter = ',', value_name = "CLASS")]
+        exclude_classes: Vec<u8>,
+
+        /// Path to a GeoJSON (.geojson, .json) or WKT (.wkt) file with a polygon in the source CRS.
+        /// Only points whose horizontal position lies within the polygon are kept.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        clip_polygon: Option<PathBuf>,
+
+        /// Drop points with a z value below this height (in the source CRS).
+        #[clap(long, value_name = "Z", allow_negative_numbers = true)]
+        z_min: Option<f64>,
+
+        /// Drop points with a z value above this height (in the source CRS).
+        #[clap(long, value_name = "Z", allow_negative_numbers = true)]
+        z_max: Option<f64>,
+
+        /// Enable statistical outlier removal using the mean distance to this number of
+        /// nearest neighbors.
+        #[clap(long, value_name = "K")]
+        outlier_neighbors: Option<usize>,
+
+        /// Points whose mean neighbor distance exceeds the global mean by more than this many
+        /// standard deviations are removed. Only used with --outlier-neighbors.
+        #[clap(long, default_value_t = 2.0, value_name = "RATIO")]
+        outlier_std_ratio: f64,
+
+        /// Enable radius outlier removal with this search radius (in units of the source CRS).
+        #[clap(long, value_name = "RADIUS")]
+        radius_outlier_radius: Option<f64>,
+
+        /// Minimum number of neighbors within the search radius for a point to be kept.
+        /// Only used with --radius-outlier-radius.
+        #[clap(long, default_value_t = 2, value_name = "N")]
+        radius_outlier_min_neighbors: usize,
+
+        /// Downsample the point cloud to at most one point per voxel of this edge length
+        /// (in units of the source CRS).
+        #[clap(long, value_name = "SIZE")]
+        voxel_size: Option<f64>,
+
+        /// Source of the point colors. Modes other than rgb replace existing colors, which is
+        /// useful for point clouds without RGB.
+        #[clap(long, value_enum, default_value_t = ColorModeArgument::Rgb)]
+        color_mode: ColorModeArgument,
+
+        /// Comma-separated hex colors (e.g. #0000ff,#00ff00,#ff0000) of the gradient used by
+        /// the height color mode, from lowest to highest.
+        #[clap(long, value_delimiter = ',', value_name = "HEX")]
+        height_gradient: Vec<String>,
+
+        /// Height mapped to the first gradient color (in the source CRS).
+        /// Defaults to the minimum z value of the point cloud.
+        #[clap(long, value_name = "Z", allow_negative_numbers = true)]
+        height_color_min: Option<f64>,
+
+        /// Height mapped to the last gradient color (in the source CRS).
+        /// Defaults to the maximum z value of the point cloud.
+        #[clap(long, value_name = "Z", allow_negative_numbers = true)]
+        height_color_max: Option<f64>,
+
+        /// Path to a GeoTIFF raster (e.g. an orthophoto) with an EPSG-coded CRS to sample point
+        /// colors from. Can be repeated; the first raster covering a point is used.
+        /// Applied after --color-mode, so points outside all rasters keep their color.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        color_raster: Vec<PathBuf>,
+
+        /// Interpolation used when sampling the color rasters.
+        #[clap(long, value_enum, default_value_t = RasterSamplingArgument::Bilinear)]
+        raster_sampling: RasterSamplingArgument,
+
+        /// Estimate normals from this number of nearest neighbors and encode them as NORMAL
+        /// attribute. Normals present in the point data (normal_x, normal_y, normal_z columns)
+        /// are used instead of estimating new ones.
+        #[clap(long, value_name = "K")]
+        normal_neighbors: Option<usize>,
+
+        /// Orient estimated normals towards this position (x,y,z in the source CRS), e.g. of a
+        /// stationary scanner. By default, normals are oriented upwards.
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "X,Y,Z",
+            allow_negative_numbers = true
+        )]
+        normal_viewpoint: Option<Vec<f64>>,
+
+        /// Path to a JSON file with provenance metadata written into the tileset, e.g.
+        /// {"projectId": "...", "acquisitionDate": "2024-05-01", "sensor": "...", "sourceFiles": [...]}.
+        /// Additional string, number and boolean properties are written as well.
+        /// The source files default to the names of the input files.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        metadata: Option<PathBuf>,
+
+        /// Application-specific version of the tileset, written as asset.tilesetVersion.
+        #[clap(long, value_name = "VERSION")]
+        tileset_version: Option<String>,
+
+        /// Copyright notice written into the asset of every glTF tile and the asset extras of
+        /// the tileset.
+        #[clap(long, value_name = "TEXT")]
+        copyright: Option<String>,
+
+        /// JSON object written as asset extras of the tileset and every glTF tile
+        /// (e.g. '{"license": "CC-BY-4.0"}').
+        #[clap(long, value_name = "JSON")]
+        asset_extras: Option<String>,
+
+        /// Names of 3D Tiles extensions listed in extensionsUsed.
+        #[clap(long, value_delimiter = ',', value_name = "EXTENSION")]
+        extensions_used: Vec<String>,
+
+        /// Names of 3D Tiles extensions listed in extensionsRequired (and extensionsUsed).
+        #[clap(long, value_delimiter = ',', value_name = "EXTENSION")]
+        extensions_required: Vec<String>,
+
+        /// Split the content of each tile into a layer of the given classification codes,
+        /// written as separate files (e.g. ground=2, vegetation=3,4,5). Repeatable; a layer
+        /// without codes (e.g. other) collects all remaining points.
+        #[clap(long, value_name = "NAME[=CLASSES]")]
+        content_layer: Vec<String>,
+
+        /// Generate a declarative 3D Tiles style written as style.json into the archive, which
+        /// viewers can apply without re-encoding the tiles. The height style uses the
+        /// --height-gradient colors.
+        #[clap(long, value_enum, value_name = "STYLE")]
+        style: Option<StyleArgument>,
+
+        /// Modification time of the archive entries as RFC 3339 timestamp
+        /// (e.g. 2024-05-01T00:00:00Z). Defaults to the Unix epoch, so that identical inputs
+        /// yield byte-identical archives.
+        #[clap(long, value_name = "TIME")]
+        archive_time: Option<String>,
+
+        /// Write manifest.json with the size and SHA-256 hash of every archive entry, which the
+        /// verify command checks.
+        #[clap(long)]
+        manifest: bool,
+
+        /// Store each archive entry gzip-compressed with the additional extension .gz, e.g. for
+        /// static hosting with Content-Encoding: gzip.
+        #[clap(long)]
+        precompress_entries: bool,
+
+        /// Maximum number of concurrent uploads when writing to an s3:// URL.
+        #[clap(long, default_value_t = 8, value_name = "N")]
+        upload_concurrency: usize,
+
+        /// Number of retries of a failed upload when writing to an s3:// URL.
+        #[clap(long, default_value_t = 3, value_name = "N")]
+        upload_retries: usize,
     },
+
+    /// Insert or remove point cloud files of an existing 3D Tiles archive without a full rebuild
+    UpdatePointCloud {
+        /// Path to a point cloud file or a directory containing point cloud files to be inserted.
+        /// Supported formats: LAS, LAZ, E57, XYZ, XYZ+Zstandard.
+        #[clap(
+            long,
+            value_hint = ValueHint::AnyPath,
+            value_name = "PATH",
+            required_unless_present = "remove_path"
+        )]
+        input_path: Option<PathBuf>,
+
+        /// Path to a previously inserted point cloud file or directory whose points are removed
+        /// before inserting. Each point removes the closest point of the tileset within the
+        /// removal tolerance.
+        #[clap(long, value_hint = ValueHint::AnyPath, value_name = "PATH")]
+        remove_path: Option<PathBuf>,
+
+        /// Maximum distance in meters between a point to be removed and the matching point of
+        /// the tileset.
+        #[clap(long, default_value_t = 0.001, value_name = "METERS")]
+        removal_tolerance: f64,
+
+        /// Path to an existing TAR archive created by convert-point-cloud, which is updated in
+        /// place. All points must lie within the octree bounds of the existing tileset.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        tileset_path: PathBuf,
+
+        /// Maximum number of points stored per octree node.
+        /// Should match the value used when the tileset was created.
+        #[clap(long, default_value_t = 100000, value_name = "N")]
+        maximum_points_per_octant: u64,
+
+        /// EPSG code of the coordinate reference system of the source point cloud
+        /// (e.g. 25832 for ETRS89 / UTM zone 32N).
+        #[clap(long, value_name = "EPSG_CODE", required_unless_present = "local")]
+        source_crs: Option<u32>,
+
+        /// Treat the coordinates as local coordinates without a CRS, which are stored without
+        /// reprojection. The tileset must have been converted with --local.
+        #[clap(long, conflicts_with = "source_crs")]
+        local: bool,
+
+        /// Geographic placement of the local origin the tileset was converted with
+        /// (LON,LAT,HEIGHT[,HEADING]).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "LON,LAT,HEIGHT[,HEADING]",
+            allow_negative_numbers = true,
+            requires = "local"
+        )]
+        placement: Option<Vec<f64>>,
+
+        /// Disable random shuffling of the inserted points.
+        #[clap(long)]
+        no_shuffle: bool,
+
+        /// Seed for the random shuffle, ensuring reproducible output.
+        /// Only used when --no-shuffle is not set.
+        #[clap(long, default_value_t = 1, value_name = "SEED")]
+        seed: u64,
+
+        /// New application-specific version of the tileset, replacing asset.tilesetVersion.
+        /// All other asset information of the existing tileset is kept.
+        #[clap(long, value_name = "VERSION")]
+        tileset_version: Option<String>,
+    },
+
+    /// Combine multiple 3D Tiles archives under a parent tileset referencing them as external tilesets
+    Merge {
+        /// Paths to the TAR archives to be combined. Each tileset is referenced as
+        /// `{name}/tileset.json`, where the name is the file name of its archive without the .tar,
+        /// .tar.gz or .tar.zst extension.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH", required = true)]
+        input_path: Vec<PathBuf>,
+
+        /// Path to the output. Without --repackage, this is the parent tileset.json and the input
+        /// archives must be extracted into directories named after them next to it.
+        /// With --repackage, this is a TAR archive containing all tilesets.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        output_path: PathBuf,
+
+        /// Repackage the parent tileset and all input archives into a single TAR archive.
+        #[clap(long)]
+        repackage: bool,
+
+        /// Epochs of the input tilesets in the order of the input paths (e.g.
+        /// 2024-03-01,2024-09-01) for comparing multi-epoch scans. Without epochs, the
+        /// acquisition date of each input tileset's metadata is used, if any.
+        #[clap(long, value_delimiter = ',', value_name = "EPOCH")]
+        epoch: Vec<String>,
+    },
+
+    /// Extract the points of a 3D Tiles archive back into a point cloud file
+    Extract {
+        /// Path to a TAR archive or .3dtiles SQLite container created by convert-point-cloud.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        input_path: PathBuf,
+
+        /// Path to the point cloud file to be written.
+        /// Supported formats: LAS, LAZ, XYZ, XYZ+Zstandard.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        output_path: PathBuf,
+
+        /// EPSG code of the coordinate reference system of the written point cloud
+        /// (e.g. 25832 for ETRS89 / UTM zone 32N).
+        #[clap(long, value_name = "EPSG_CODE", required_unless_present = "local")]
+        target_crs: Option<u32>,
+
+        /// Write the points in the local coordinates of a tileset converted with --local,
+        /// without reprojection.
+        #[clap(long, conflicts_with = "target_crs")]
+        local: bool,
+
+        /// Geographic placement of the local origin the tileset was converted with
+        /// (LON,LAT,HEIGHT[,HEADING]).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "LON,LAT,HEIGHT[,HEADING]",
+            allow_negative_numbers = true,
+            requires = "local"
+        )]
+        placement: Option<Vec<f64>>,
+
+        /// Only extract the points of this octree level.
+        #[clap(long, value_name = "LEVEL", conflicts_with = "maximum_level")]
+        level: Option<u32>,
+
+        /// Only extract the points from the root down to this octree level,
+        /// i.e. the point cloud at this level of detail.
+        #[clap(long, value_name = "LEVEL")]
+        maximum_level: Option<u32>,
+
+        /// Only keep points within this horizontal bounding box (in the target CRS or the local
+        /// coordinates).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "MIN_X,MIN_Y,MAX_X,MAX_Y",
+            allow_negative_numbers = true,
+            conflicts_with = "clip_polygon"
+        )]
+        bbox: Option<Vec<f64>>,
+
+        /// Path to a GeoJSON (.geojson, .json) or WKT (.wkt) file with a polygon in the target CRS
+        /// or the local coordinates.
+        /// Only points whose horizontal position lies within the polygon are kept.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        clip_polygon: Option<PathBuf>,
+    },
+
+    /// Verify the integrity of a 3D Tiles archive against its manifest and subtree availability
+    Verify {
+        /// Path to a TAR archive or .3dtiles SQLite container created by convert-point-cloud.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        input_path: PathBuf,
+    },
+
+    /// Serve the entries of a .3dtiles SQLite container over HTTP, e.g. for CesiumJS
+    #[cfg(feature = "sqlite")]
+    Serve {
+        /// Path to a .3dtiles SQLite container created by convert-point-cloud.
+        #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+        input_path: PathBuf,
+
+        /// Address to listen on. The tileset is available at the root path.
+        #[clap(long, default_value = "127.0.0.1:8080", value_name = "ADDRESS")]
+        address: SocketAddr,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorModeArgument {
+    /// RGB colors of the point cloud
+    Rgb,
+    /// Color gradient by height
+    Height,
+    /// Grayscale by intensity
+    Intensity,
+    /// ASPRS classification colors (requires a classification column)
+    Classification,
+    /// Colors by return number (requires a return_number column)
+    ReturnNumber,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FrameArgument {
+    /// Axes of the source CRS at the origin
+    SourceAxes,
+    /// East, north and up axes of the WGS 84 ellipsoid at the origin
+    EastNorthUp,
+    /// ECEF coordinates with the identity as root transform, stored relative to the tile centers
+    /// (the tileset has no heights)
+    Ecef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AlignmentArgument {
+    /// Axes of the tileset frame
+    FrameAxes,
+    /// Principal axes of the horizontal point distribution (not supported with the ecef frame)
+    PrincipalAxes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StyleArgument {
+    /// ASPRS classification colors (requires a classification column)
+    Classification,
+    /// Color gradient by ellipsoidal height over the height range of the tileset
+    Height,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RasterSamplingArgument {
+    /// Color of the pixel containing the point
+    Nearest,
+    /// Bilinear interpolation between the four closest pixels
+    Bilinear,
 }
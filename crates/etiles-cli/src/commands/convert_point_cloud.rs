@@ -1,70 +1,343 @@
+use crate::commands::is_object_store_url;
+#[cfg(feature = "sqlite")]
+use crate::commands::is_sqlite_container_path;
 use crate::error::Error;
+use crate::progress::create_progress_reporter;
 use epoint::transform::merge;
 use eproj::SpatialReferenceIdentifier;
-use etiles::Tileset;
-use etiles::io::EtilesWriter;
+#[cfg(feature = "sqlite")]
+use etiles::io::SqliteContainer;
+use etiles::io::{
+    ArchiveHeaderInfo, AssetInfo, ContentLayer, DirectorySink, EtilesWriter, ObjectStoreSink,
+    OutputSink, TilesetMetadata, TilesetStyle,
+};
+use etiles::{
+    ColorMode, GeoRaster, LocalPlacement, NormalEstimation, PointFilter, ProgressPhase,
+    ProgressReporter, RadiusOutlierRemoval, RasterSampling, StatisticalOutlierRemoval, Tileset,
+    TilesetAlignment, TilesetFrame, colorize_point_cloud, colorize_point_cloud_from_rasters,
+    downsample_voxel_grid, estimate_normals, filter_point_cloud, has_normals,
+    remove_radius_outliers, remove_statistical_outliers,
+};
 use std::fs;
 use std::path::Path;
 use std::time::Instant;
 use tracing::info;
 
+/// Optional processing steps applied to the point cloud before the tileset is built.
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessingOptions {
+    pub point_filter: PointFilter,
+    pub statistical_outlier_removal: Option<StatisticalOutlierRemoval>,
+    pub radius_outlier_removal: Option<RadiusOutlierRemoval>,
+    pub voxel_size: Option<f64>,
+    pub color_mode: ColorMode,
+    pub color_rasters: Vec<GeoRaster>,
+    pub raster_sampling: RasterSampling,
+    pub normal_estimation: Option<NormalEstimation>,
+}
+
+/// Coordinate reference of the point cloud and the frame in which the tileset stores the points.
+#[derive(Debug, Clone, Copy)]
+pub enum TilesetPlacement {
+    /// Points in the source CRS, reprojected into the tileset frame.
+    Georeferenced {
+        source_crs: SpatialReferenceIdentifier,
+        tileset_frame: TilesetFrame,
+    },
+    /// Points in local coordinates without a CRS, placed by the root transform.
+    Local(LocalPlacement),
+}
+
+/// Metadata, asset information, content layers, style, archive headers, manifest and entry
+/// compression written with the tileset, and the upload settings for object stores.
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    pub metadata: TilesetMetadata,
+    pub asset_info: AssetInfo,
+    pub content_layers: Vec<ContentLayer>,
+    pub style: Option<TilesetStyle>,
+    pub archive_header_info: ArchiveHeaderInfo,
+    pub manifest: bool,
+    pub precompressed_entries: bool,
+    pub upload_concurrency: usize,
+    pub upload_retries: usize,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     input_path: impl AsRef<Path>,
     output_directory_path: impl AsRef<Path>,
     maximum_points_per_octant: u64,
-    source_crs: SpatialReferenceIdentifier,
+    tileset_placement: TilesetPlacement,
+    tileset_alignment: TilesetAlignment,
     seed_number: Option<u64>,
+    preprocessing_options: &PreprocessingOptions,
+    mut output_options: OutputOptions,
 ) -> Result<(), Error> {
-    let point_cloud = if input_path.as_ref().is_dir() {
-        read_point_clouds_from_directory(input_path)?
-    } else {
-        info!("Start reading point cloud file");
-        let now = Instant::now();
-        let auto_reader = epoint::io::AutoReader::from_path(input_path)?;
-        let point_cloud = auto_reader.finish()?;
-        info!("Read point cloud in {}s", now.elapsed().as_secs());
-        point_cloud
+    if output_options.metadata.source_files.is_empty() {
+        output_options.metadata.source_files = derive_source_file_names(&input_path)?;
+    }
+    let progress_reporter = create_progress_reporter();
+    let point_cloud = read_point_cloud(input_path, &progress_reporter)?;
+
+    let source_crs = match tileset_placement {
+        TilesetPlacement::Georeferenced { source_crs, .. } => Some(source_crs),
+        TilesetPlacement::Local(_) => None,
     };
+    let point_cloud = preprocess_point_cloud(point_cloud, source_crs, preprocessing_options)?;
 
-    let tileset = Tileset::from_point_cloud(
-        point_cloud,
-        source_crs,
-        maximum_points_per_octant,
-        seed_number,
-    )?;
+    let tileset = match tileset_placement {
+        TilesetPlacement::Georeferenced {
+            source_crs,
+            tileset_frame,
+        } => Tileset::from_point_cloud_with_frame(
+            point_cloud,
+            source_crs,
+            tileset_frame,
+            tileset_alignment,
+            maximum_points_per_octant,
+            seed_number,
+            &progress_reporter,
+        )?,
+        TilesetPlacement::Local(local_placement) => Tileset::from_local_point_cloud(
+            point_cloud,
+            local_placement,
+            tileset_alignment,
+            maximum_points_per_octant,
+            seed_number,
+            &progress_reporter,
+        )?,
+    };
 
-    if let Some(parent) = output_directory_path.as_ref().parent() {
+    if !is_object_store_url(&output_directory_path)
+        && let Some(parent) = output_directory_path.as_ref().parent()
+    {
         fs::create_dir_all(parent)?;
     }
     info!(
         "Start writing tileset to: {}",
         output_directory_path.as_ref().display()
     );
-    let writer = EtilesWriter::from_path(output_directory_path)?;
-    writer.finish(&tileset)?;
+    write_tileset(
+        &tileset,
+        output_directory_path,
+        output_options,
+        progress_reporter,
+    )?;
     info!("Completed");
 
     Ok(())
 }
 
+fn write_tileset(
+    tileset: &Tileset,
+    output_path: impl AsRef<Path>,
+    output_options: OutputOptions,
+    progress_reporter: ProgressReporter,
+) -> Result<(), Error> {
+    if is_object_store_url(&output_path) {
+        let url = output_path.as_ref().to_string_lossy();
+        let sink = ObjectStoreSink::from_s3_url(&url, output_options.upload_retries)?
+            .with_concurrency(output_options.upload_concurrency);
+        configure_writer(
+            EtilesWriter::from_sink(sink),
+            output_options,
+            progress_reporter,
+        )
+        .finish(tileset)?;
+        return Ok(());
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_container_path(&output_path) {
+        let container = SqliteContainer::create(output_path)?;
+        configure_writer(
+            EtilesWriter::from_sink(container),
+            output_options,
+            progress_reporter,
+        )
+        .finish(tileset)?;
+        return Ok(());
+    }
+
+    if output_path.as_ref().extension().is_none() {
+        let sink = DirectorySink::new(output_path)?;
+        configure_writer(
+            EtilesWriter::from_sink(sink),
+            output_options,
+            progress_reporter,
+        )
+        .finish(tileset)?;
+    } else {
+        let archive_header_info = output_options.archive_header_info.clone();
+        configure_writer(
+            EtilesWriter::from_path(output_path)?.with_archive_header_info(archive_header_info),
+            output_options,
+            progress_reporter,
+        )
+        .finish(tileset)?;
+    }
+
+    Ok(())
+}
+
+fn configure_writer<S: OutputSink>(
+    writer: EtilesWriter<S>,
+    output_options: OutputOptions,
+    progress_reporter: ProgressReporter,
+) -> EtilesWriter<S> {
+    let mut writer = writer
+        .with_metadata(output_options.metadata)
+        .with_asset_info(output_options.asset_info)
+        .with_content_layers(output_options.content_layers)
+        .with_manifest(output_options.manifest)
+        .with_precompressed_entries(output_options.precompressed_entries)
+        .with_progress_reporter(progress_reporter);
+    if let Some(style) = output_options.style {
+        writer = writer.with_style(style);
+    }
+    writer
+}
+
+fn preprocess_point_cloud(
+    mut point_cloud: epoint::PointCloud,
+    source_crs: Option<SpatialReferenceIdentifier>,
+    preprocessing_options: &PreprocessingOptions,
+) -> Result<epoint::PointCloud, Error> {
+    if !preprocessing_options.point_filter.is_empty() {
+        info!("Start filtering {} points", point_cloud.size());
+        point_cloud = filter_point_cloud(point_cloud, &preprocessing_options.point_filter)?;
+        info!("Kept {} points after filtering", point_cloud.size());
+    }
+
+    if let Some(statistical_outlier_removal) = &preprocessing_options.statistical_outlier_removal {
+        info!("Start removing statistical outliers");
+        let now = Instant::now();
+        point_cloud = remove_statistical_outliers(point_cloud, statistical_outlier_removal)?;
+        info!(
+            "Removed statistical outliers in {}s",
+            now.elapsed().as_secs()
+        );
+    }
+
+    if let Some(radius_outlier_removal) = &preprocessing_options.radius_outlier_removal {
+        info!("Start removing radius outliers");
+        let now = Instant::now();
+        point_cloud = remove_radius_outliers(point_cloud, radius_outlier_removal)?;
+        info!("Removed radius outliers in {}s", now.elapsed().as_secs());
+    }
+
+    if let Some(voxel_size) = preprocessing_options.voxel_size {
+        info!("Start voxel grid downsampling");
+        let now = Instant::now();
+        point_cloud = downsample_voxel_grid(point_cloud, voxel_size)?;
+        info!("Downsampled point cloud in {}s", now.elapsed().as_secs());
+    }
+
+    if preprocessing_options.color_mode != ColorMode::Rgb {
+        info!("Start colorizing point cloud");
+        point_cloud = colorize_point_cloud(point_cloud, &preprocessing_options.color_mode)?;
+    }
+
+    if let Some(source_crs) = source_crs
+        && !preprocessing_options.color_rasters.is_empty()
+    {
+        info!(
+            "Start colorizing point cloud from {} rasters",
+            preprocessing_options.color_rasters.len()
+        );
+        let now = Instant::now();
+        point_cloud = colorize_point_cloud_from_rasters(
+            point_cloud,
+            source_crs,
+            &preprocessing_options.color_rasters,
+            preprocessing_options.raster_sampling,
+        )?;
+        info!("Colorized point cloud in {}s", now.elapsed().as_secs());
+    }
+
+    if let Some(normal_estimation) = &preprocessing_options.normal_estimation {
+        if has_normals(&point_cloud) {
+            info!("Using normals of the point data instead of estimating them");
+        } else {
+            info!("Start estimating normals");
+            let now = Instant::now();
+            point_cloud = estimate_normals(point_cloud, normal_estimation)?;
+            info!("Estimated normals in {}s", now.elapsed().as_secs());
+        }
+    }
+
+    Ok(point_cloud)
+}
+
+/// Reads a point cloud file, or all point cloud files of a directory combined.
+pub(crate) fn read_point_cloud(
+    input_path: impl AsRef<Path>,
+    progress_reporter: &ProgressReporter,
+) -> Result<epoint::PointCloud, Error> {
+    if input_path.as_ref().is_dir() {
+        read_point_clouds_from_directory(input_path, progress_reporter)
+    } else {
+        info!("Start reading point cloud file");
+        let now = Instant::now();
+        let read_progress = progress_reporter.begin_phase(ProgressPhase::Read, 1)?;
+        let auto_reader = epoint::io::AutoReader::from_path(input_path)?;
+        let point_cloud = auto_reader.finish()?;
+        read_progress.finish()?;
+        info!("Read point cloud in {}s", now.elapsed().as_secs());
+        Ok(point_cloud)
+    }
+}
+
+/// Returns the file names of the point cloud files read by [`read_point_cloud`].
+fn derive_source_file_names(input_path: impl AsRef<Path>) -> Result<Vec<String>, Error> {
+    let paths = if input_path.as_ref().is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(input_path)? {
+            let path = entry?.path();
+            if path.is_file() && epoint::io::PointCloudFormat::from_path(&path).is_some() {
+                paths.push(path);
+            }
+        }
+        paths
+    } else {
+        vec![input_path.as_ref().to_path_buf()]
+    };
+
+    let mut file_names: Vec<String> = paths
+        .iter()
+        .filter_map(|p| p.file_name())
+        .map(|f| f.to_string_lossy().to_string())
+        .collect();
+    file_names.sort();
+    Ok(file_names)
+}
+
 fn read_point_clouds_from_directory(
     input_path: impl AsRef<Path>,
+    progress_reporter: &ProgressReporter,
 ) -> Result<epoint::PointCloud, Error> {
     info!("Start reading point cloud directory");
-    let mut point_cloud_file_count = 0;
     let now = Instant::now();
 
-    let mut point_clouds = Vec::new();
+    let mut paths = Vec::new();
     for entry in fs::read_dir(input_path)? {
-        let entry = entry?;
-        let path = entry.path();
+        let path = entry?.path();
         if path.is_file() && epoint::io::PointCloudFormat::from_path(&path).is_some() {
-            let auto_reader = epoint::io::AutoReader::from_path(&path)?;
-            let point_cloud = auto_reader.finish()?;
-            point_clouds.push(point_cloud);
-            point_cloud_file_count += 1;
+            paths.push(path);
         }
     }
+    let point_cloud_file_count = paths.len();
+
+    let read_progress = progress_reporter.begin_phase(ProgressPhase::Read, paths.len() as u64)?;
+    let mut point_clouds = Vec::new();
+    for path in paths {
+        let auto_reader = epoint::io::AutoReader::from_path(&path)?;
+        let point_cloud = auto_reader.finish()?;
+        point_clouds.push(point_cloud);
+        read_progress.advance(1)?;
+    }
+    read_progress.finish()?;
 
     let combined_point_cloud = merge(point_clouds)?;
     info!(
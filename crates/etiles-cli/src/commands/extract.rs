@@ -0,0 +1,71 @@
+use crate::commands::PointCloudCoordinates;
+#[cfg(feature = "sqlite")]
+use crate::commands::is_sqlite_container_path;
+use crate::error::Error;
+use etiles::io::EtilesReader;
+#[cfg(feature = "sqlite")]
+use etiles::io::SqliteContainer;
+use etiles::{
+    ClipPolygon, LevelSelection, PointFilter, Tileset, extract_local_point_cloud,
+    extract_point_cloud, filter_point_cloud,
+};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use tracing::info;
+
+pub fn run(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    coordinates: PointCloudCoordinates,
+    level_selection: LevelSelection,
+    clip_polygon: Option<ClipPolygon>,
+) -> Result<(), Error> {
+    info!(
+        "Start reading tileset from: {}",
+        input_path.as_ref().display()
+    );
+    let now = Instant::now();
+    let tileset = read_tileset(input_path)?;
+    info!("Read tileset in {}s", now.elapsed().as_secs());
+
+    let mut point_cloud = match coordinates {
+        PointCloudCoordinates::Crs(target_crs) => {
+            extract_point_cloud(&tileset, target_crs, level_selection)?
+        }
+        PointCloudCoordinates::Local(local_placement) => {
+            extract_local_point_cloud(&tileset, local_placement, level_selection)?
+        }
+    };
+    info!("Extracted {} points", point_cloud.size());
+
+    if clip_polygon.is_some() {
+        let point_filter = PointFilter {
+            clip_polygon,
+            ..Default::default()
+        };
+        point_cloud = filter_point_cloud(point_cloud, &point_filter)?;
+        info!("Kept {} points after clipping", point_cloud.size());
+    }
+
+    if let Some(parent) = output_path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    info!(
+        "Start writing point cloud to: {}",
+        output_path.as_ref().display()
+    );
+    epoint::io::AutoWriter::from_path(output_path)?.finish(point_cloud)?;
+    info!("Completed");
+
+    Ok(())
+}
+
+fn read_tileset(input_path: impl AsRef<Path>) -> Result<Tileset, Error> {
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_container_path(&input_path) {
+        return Ok(EtilesReader::new(SqliteContainer::open(input_path)?).finish()?);
+    }
+
+    Ok(EtilesReader::from_path(input_path)?.finish()?)
+}
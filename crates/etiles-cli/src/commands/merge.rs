@@ -0,0 +1,37 @@
+use crate::error::Error;
+use etiles::io::EtilesMerger;
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+pub fn run(
+    input_paths: &[PathBuf],
+    epochs: &[String],
+    output_path: impl AsRef<Path>,
+    repackage: bool,
+) -> Result<(), Error> {
+    let mut merger = EtilesMerger::new();
+    for (index, input_path) in input_paths.iter().enumerate() {
+        info!("Add tileset: {}", input_path.display());
+        match epochs.get(index) {
+            Some(epoch) => merger.add_archive_with_epoch(input_path, epoch)?,
+            None => merger.add_archive(input_path)?,
+        }
+    }
+
+    if let Some(parent) = output_path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    info!("Start writing to: {}", output_path.as_ref().display());
+    let writer = BufWriter::new(File::create(output_path)?);
+    if repackage {
+        merger.write_archive(writer)?;
+    } else {
+        merger.write_tileset_json(writer)?;
+    }
+    info!("Completed");
+
+    Ok(())
+}
@@ -1 +1,34 @@
 pub mod convert_point_cloud;
+pub mod extract;
+pub mod merge;
+#[cfg(feature = "sqlite")]
+pub mod serve;
+pub mod update_point_cloud;
+pub mod verify;
+
+use eproj::SpatialReferenceIdentifier;
+use etiles::LocalPlacement;
+#[cfg(feature = "sqlite")]
+use etiles::io::FILE_EXTENSION_ETILES_SQLITE;
+use std::path::Path;
+
+/// Coordinates of a point cloud that is inserted into, removed from or extracted from an
+/// existing tileset.
+#[derive(Debug, Clone, Copy)]
+pub enum PointCloudCoordinates {
+    /// Points in a CRS, reprojected from or into the tileset frame.
+    Crs(SpatialReferenceIdentifier),
+    /// Points in the local coordinates of a tileset converted without a CRS, placed like it.
+    Local(LocalPlacement),
+}
+
+/// Whether the path refers to a SQLite container rather than a TAR archive.
+#[cfg(feature = "sqlite")]
+fn is_sqlite_container_path(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().and_then(|e| e.to_str()) == Some(FILE_EXTENSION_ETILES_SQLITE)
+}
+
+/// Whether the path is an `s3://` URL of an object store rather than a local path.
+fn is_object_store_url(path: impl AsRef<Path>) -> bool {
+    path.as_ref().to_string_lossy().starts_with("s3://")
+}
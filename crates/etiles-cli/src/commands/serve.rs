@@ -0,0 +1,271 @@
+use crate::error::Error;
+use etiles::io::{SqliteContainer, SqliteContainerEntry};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Number of worker threads, each serving one connection at a time.
+const WORKER_COUNT: usize = 16;
+/// Time within which a request line and its headers must be received completely.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a kept-alive connection may stay idle before the next request starts.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time after which a stalled write of a response is aborted.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum length of the request line and of each header line in bytes.
+const MAXIMUM_LINE_LENGTH: u64 = 8 * 1024;
+/// Maximum number of header lines of a request.
+const MAXIMUM_HEADER_COUNT: usize = 100;
+
+/// Serves the entries of the SQLite container over HTTP until the process is terminated.
+///
+/// Connections are handled by a fixed number of worker threads, each with its own read-only
+/// connection to the container, and are closed if the client stalls or sends oversized
+/// requests. Precompressed entries are sent as stored with their `Content-Encoding` if the
+/// client accepts it, and decompressed otherwise.
+pub fn run(input_path: impl AsRef<Path>, address: SocketAddr) -> Result<(), Error> {
+    // fail early if the container cannot be opened
+    let containers = (0..WORKER_COUNT)
+        .map(|_| SqliteContainer::open(&input_path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let listener = TcpListener::bind(address)?;
+    info!(
+        "Serving {} at http://{}/",
+        input_path.as_ref().display(),
+        listener.local_addr()?
+    );
+
+    let workers = containers
+        .into_iter()
+        .map(|container| -> Result<_, Error> {
+            let listener = listener.try_clone()?;
+            Ok(thread::spawn(move || {
+                serve_connections(&listener, &container)
+            }))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for worker in workers {
+        if worker.join().is_err() {
+            warn!("Worker thread panicked");
+        }
+    }
+
+    Ok(())
+}
+
+/// Accepts and serves connections one after another.
+fn serve_connections(listener: &TcpListener, container: &SqliteContainer) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                warn!("Failed to accept connection: {error}");
+                continue;
+            }
+        };
+
+        if let Err(error) = serve_connection(stream, container) {
+            debug!("Connection closed: {error}");
+        }
+    }
+}
+
+/// Request line and the headers relevant for responding.
+struct Request {
+    method: String,
+    path: String,
+    accepts_gzip: bool,
+    keep_alive: bool,
+}
+
+struct Response {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+/// Stream whose reads fail once the deadline has passed, regardless of how slowly the client
+/// sends its data.
+struct DeadlineStream {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining_time = self.deadline.saturating_duration_since(Instant::now());
+        if remaining_time.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining_time))?;
+        self.stream.read(buf)
+    }
+}
+
+fn serve_connection(stream: TcpStream, container: &SqliteContainer) -> Result<(), Error> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(DeadlineStream {
+        stream: stream.try_clone()?,
+        deadline: Instant::now() + REQUEST_TIMEOUT,
+    });
+    let mut writer = stream;
+
+    while let Some(mut request) = read_request(&mut reader)? {
+        let response = respond(container, &mut request)?;
+        debug!("{} {} {}", request.method, request.path, response.status);
+        write_response(&mut writer, &request, response)?;
+
+        if !request.keep_alive {
+            break;
+        }
+
+        // wait for the start of the next request, then allow it the full request timeout
+        reader.get_mut().deadline = Instant::now() + IDLE_TIMEOUT;
+        if reader.fill_buf()?.is_empty() {
+            break;
+        }
+        reader.get_mut().deadline = Instant::now() + REQUEST_TIMEOUT;
+    }
+
+    Ok(())
+}
+
+/// Reads the request line and headers, or returns `None` once the client closed the connection.
+fn read_request(reader: &mut impl BufRead) -> Result<Option<Request>, Error> {
+    let mut request_line = String::new();
+    if read_limited_line(reader, &mut request_line)? == 0 {
+        return Ok(None);
+    }
+    let mut request_line_parts = request_line.split_whitespace();
+    let method = request_line_parts.next().unwrap_or_default().to_string();
+    let path = request_line_parts.next().unwrap_or("/").to_string();
+    let version = request_line_parts.next().unwrap_or_default();
+
+    let mut request = Request {
+        method,
+        path,
+        accepts_gzip: false,
+        keep_alive: version == "HTTP/1.1",
+    };
+    for header_index in 0.. {
+        let mut header_line = String::new();
+        if read_limited_line(reader, &mut header_line)? == 0 || header_line.trim().is_empty() {
+            break;
+        }
+        if header_index == MAXIMUM_HEADER_COUNT {
+            return Err(invalid_request("too many header lines"));
+        }
+        let Some((name, value)) = header_line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "accept-encoding" => request.accepts_gzip = value.contains("gzip"),
+            "connection" => request.keep_alive = value == "keep-alive",
+            _ => {}
+        }
+    }
+
+    Ok(Some(request))
+}
+
+/// Reads a line of at most [`MAXIMUM_LINE_LENGTH`] bytes and fails if it is longer.
+fn read_limited_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize, Error> {
+    let length = reader.take(MAXIMUM_LINE_LENGTH).read_line(line)?;
+    if length as u64 == MAXIMUM_LINE_LENGTH && !line.ends_with('\n') {
+        return Err(invalid_request("line exceeds the maximum length"));
+    }
+
+    Ok(length)
+}
+
+fn invalid_request(message: &str) -> Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string()).into()
+}
+
+fn respond(container: &SqliteContainer, request: &mut Request) -> Result<Response, Error> {
+    if request.method != "GET" && request.method != "HEAD" {
+        // a request body is never read, so the connection cannot be reused
+        request.keep_alive = false;
+        return Ok(Response {
+            status: "405 Method Not Allowed",
+            headers: vec![("Allow", "GET, HEAD".to_string())],
+            body: Vec::new(),
+        });
+    }
+
+    let Some(entry) = container.read_requested_entry(&request.path)? else {
+        return Ok(Response {
+            status: "404 Not Found",
+            headers: Vec::new(),
+            body: Vec::new(),
+        });
+    };
+
+    let mut headers = vec![
+        ("Content-Type", derive_media_type(&request.path).to_string()),
+        ("Access-Control-Allow-Origin", "*".to_string()),
+    ];
+    let body = match entry {
+        SqliteContainerEntry {
+            content,
+            content_encoding: Some(content_encoding),
+        } if request.accepts_gzip && content_encoding == "gzip" => {
+            headers.push(("Content-Encoding", content_encoding));
+            headers.push(("Vary", "Accept-Encoding".to_string()));
+            content
+        }
+        entry => entry.into_decoded_content()?,
+    };
+
+    Ok(Response {
+        status: "200 OK",
+        headers,
+        body,
+    })
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    request: &Request,
+    response: Response,
+) -> Result<(), Error> {
+    write!(writer, "HTTP/1.1 {}\r\n", response.status)?;
+    for (name, value) in &response.headers {
+        write!(writer, "{name}: {value}\r\n")?;
+    }
+    let connection = if request.keep_alive {
+        "keep-alive"
+    } else {
+        "close"
+    };
+    write!(
+        writer,
+        "Content-Length: {}\r\nConnection: {connection}\r\n\r\n",
+        response.body.len()
+    )?;
+    if request.method != "HEAD" {
+        writer.write_all(&response.body)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Derives the media type of an entry from the extension of the request path. The root path
+/// addresses the tileset document.
+fn derive_media_type(request_path: &str) -> &'static str {
+    let path = request_path.split(['?', '#']).next().unwrap_or_default();
+    if path.trim_start_matches('/').is_empty() {
+        return "application/json";
+    }
+
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => "application/json",
+        Some("glb") => "model/gltf-binary",
+        _ => "application/octet-stream",
+    }
+}
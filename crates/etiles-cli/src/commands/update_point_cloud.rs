@@ -0,0 +1,76 @@
+use crate::commands::PointCloudCoordinates;
+use crate::commands::convert_point_cloud::read_point_cloud;
+use crate::error::Error;
+use crate::progress::create_progress_reporter;
+use etiles::io::EtilesUpdater;
+use std::path::Path;
+use std::time::Instant;
+use tracing::info;
+
+/// Removes the points of the point cloud at the removal path within the tolerance, if given,
+/// and then inserts the points of the point cloud at the input path, if given.
+pub fn run(
+    input_path: Option<impl AsRef<Path>>,
+    removal: Option<(impl AsRef<Path>, f64)>,
+    tileset_path: impl AsRef<Path>,
+    maximum_points_per_octant: u64,
+    coordinates: PointCloudCoordinates,
+    seed_number: Option<u64>,
+    tileset_version: Option<String>,
+) -> Result<(), Error> {
+    let progress_reporter = create_progress_reporter();
+
+    info!(
+        "Start reading tileset from: {}",
+        tileset_path.as_ref().display()
+    );
+    let now = Instant::now();
+    let mut updater = EtilesUpdater::from_path(&tileset_path)?;
+    updater.set_progress_reporter(progress_reporter.clone());
+    info!("Read tileset in {}s", now.elapsed().as_secs());
+    if tileset_version.is_some() {
+        let mut asset_info = updater.asset_info().clone();
+        asset_info.tileset_version = tileset_version;
+        updater.set_asset_info(asset_info);
+    }
+
+    if let Some((removal_path, tolerance)) = removal {
+        let point_cloud = read_point_cloud(removal_path, &progress_reporter)?;
+        info!("Start removing {} points", point_cloud.size());
+        match coordinates {
+            PointCloudCoordinates::Crs(source_crs) => {
+                updater.remove_point_cloud(point_cloud, source_crs, tolerance)?
+            }
+            PointCloudCoordinates::Local(local_placement) => {
+                updater.remove_local_point_cloud(point_cloud, local_placement, tolerance)?
+            }
+        }
+    }
+    if let Some(input_path) = input_path {
+        let point_cloud = read_point_cloud(input_path, &progress_reporter)?;
+        info!("Start inserting {} points", point_cloud.size());
+        match coordinates {
+            PointCloudCoordinates::Crs(source_crs) => updater.insert_point_cloud(
+                point_cloud,
+                source_crs,
+                maximum_points_per_octant,
+                seed_number,
+            )?,
+            PointCloudCoordinates::Local(local_placement) => updater.insert_local_point_cloud(
+                point_cloud,
+                local_placement,
+                maximum_points_per_octant,
+                seed_number,
+            )?,
+        }
+    }
+
+    info!(
+        "Start writing tileset to: {}",
+        tileset_path.as_ref().display()
+    );
+    updater.finish()?;
+    info!("Completed");
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+#[cfg(feature = "sqlite")]
+use crate::commands::is_sqlite_container_path;
+use crate::error::Error;
+#[cfg(feature = "sqlite")]
+use etiles::io::SqliteContainer;
+use etiles::io::{EtilesVerifier, VerificationReport};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Verifies the archive and logs all findings. Returns whether the archive is valid.
+pub fn run(input_path: impl AsRef<Path>) -> Result<bool, Error> {
+    info!("Start verifying: {}", input_path.as_ref().display());
+    let now = Instant::now();
+    let report = verify_archive(&input_path)?;
+    info!(
+        "Verified {} entries in {}s",
+        report.entry_count,
+        now.elapsed().as_secs()
+    );
+
+    log_report(&report);
+    Ok(report.is_valid())
+}
+
+fn verify_archive(input_path: impl AsRef<Path>) -> Result<VerificationReport, Error> {
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_container_path(&input_path) {
+        return Ok(EtilesVerifier::new(SqliteContainer::open(input_path)?).finish()?);
+    }
+
+    Ok(EtilesVerifier::from_path(input_path)?.finish()?)
+}
+
+fn log_report(report: &VerificationReport) {
+    if !report.has_manifest {
+        warn!("Archive has no manifest, so only the subtree availability is verified");
+    }
+
+    let findings: [(&str, &Vec<PathBuf>); 5] = [
+        ("Corrupted entry", &report.corrupted_entries),
+        (
+            "Missing entry listed in manifest",
+            &report.missing_manifest_entries,
+        ),
+        (
+            "Extra entry not listed in manifest",
+            &report.extra_manifest_entries,
+        ),
+        (
+            "Missing entry declared available",
+            &report.missing_available_entries,
+        ),
+        (
+            "Extra entry not declared available",
+            &report.extra_available_entries,
+        ),
+    ];
+    for (description, paths) in findings {
+        for path in paths {
+            warn!("{description}: {}", path.display());
+        }
+    }
+
+    if report.is_valid() {
+        info!("Archive is valid");
+    }
+}
@@ -1,11 +1,27 @@
 mod cli;
 mod commands;
 mod error;
+mod progress;
 
-use crate::cli::{Cli, Commands};
+use crate::cli::{
+    AlignmentArgument, Cli, ColorModeArgument, Commands, FrameArgument, RasterSamplingArgument,
+    StyleArgument,
+};
+use crate::commands::PointCloudCoordinates;
+use crate::commands::convert_point_cloud::{OutputOptions, PreprocessingOptions, TilesetPlacement};
 use anyhow::Result;
 use clap::Parser;
 use eproj::SpatialReferenceIdentifier;
+use etiles::io::{
+    ArchiveCompression, ArchiveHeaderInfo, AssetInfo, ContentLayer, FILE_EXTENSION_ETILES_SQLITE,
+    TilesetMetadata, TilesetStyle,
+};
+use etiles::{
+    ClipPolygon, ColorGradient, ColorMode, GeoRaster, LevelSelection, LocalPlacement,
+    NormalEstimation, NormalOrientation, PointFilter, RadiusOutlierRemoval, RasterSampling,
+    StatisticalOutlierRemoval, TilesetAlignment, TilesetFrame, TilesetOrigin,
+};
+use nalgebra::{Point2, Point3};
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
@@ -17,8 +33,45 @@ fn main() -> Result<()> {
             output_path,
             maximum_points_per_octant,
             source_crs,
+            local,
+            placement,
+            frame,
+            alignment,
+            origin,
+            origin_ecef,
             no_shuffle,
             seed,
+            include_classes,
+            exclude_classes,
+            clip_polygon,
+            z_min,
+            z_max,
+            outlier_neighbors,
+            outlier_std_ratio,
+            radius_outlier_radius,
+            radius_outlier_min_neighbors,
+            voxel_size,
+            color_mode,
+            height_gradient,
+            height_color_min,
+            height_color_max,
+            color_raster,
+            raster_sampling,
+            normal_neighbors,
+            normal_viewpoint,
+            metadata,
+            tileset_version,
+            copyright,
+            asset_extras,
+            extensions_used,
+            extensions_required,
+            content_layer,
+            style,
+            archive_time,
+            manifest,
+            precompress_entries,
+            upload_concurrency,
+            upload_retries,
         } => {
             if !input_path.exists() {
                 anyhow::bail!("input path does not exist: {}", input_path.display());
@@ -27,25 +80,380 @@ fn main() -> Result<()> {
             {
                 anyhow::bail!("unrecognized point cloud format: {}", input_path.display());
             }
-            if output_path.extension().and_then(|e| e.to_str()) != Some("tar") {
+            if !output_path.to_string_lossy().starts_with("s3://")
+                && output_path.extension().is_some()
+                && ArchiveCompression::from_path(output_path).is_err()
+                && (!cfg!(feature = "sqlite")
+                    || output_path.extension().and_then(|e| e.to_str())
+                        != Some(FILE_EXTENSION_ETILES_SQLITE))
+            {
                 anyhow::bail!(
-                    "output path must have a .tar extension: {}",
+                    "output path must have a .tar, .tar.gz, .tar.zst or .3dtiles extension, no \
+                     extension for a directory, or be an s3:// URL: {}",
                     output_path.display()
                 );
             }
 
-            let source_crs = SpatialReferenceIdentifier::from_code(*source_crs)?;
+            if let (Some(z_min), Some(z_max)) = (z_min, z_max)
+                && z_min > z_max
+            {
+                anyhow::bail!("z-min ({z_min}) must not be greater than z-max ({z_max})");
+            }
+
+            let height_gradient = if height_gradient.is_empty() {
+                ColorGradient::default()
+            } else {
+                ColorGradient::from_hex_codes(height_gradient)?
+            };
+            let color_mode = match color_mode {
+                ColorModeArgument::Rgb => ColorMode::Rgb,
+                ColorModeArgument::Height => {
+                    let gradient = height_gradient.clone();
+                    let range = match (height_color_min, height_color_max) {
+                        (Some(min), Some(max)) => Some((*min, *max)),
+                        (None, None) => None,
+                        _ => anyhow::bail!(
+                            "height-color-min and height-color-max must be provided together"
+                        ),
+                    };
+                    ColorMode::Height { gradient, range }
+                }
+                ColorModeArgument::Intensity => ColorMode::Intensity,
+                ColorModeArgument::Classification => ColorMode::Classification,
+                ColorModeArgument::ReturnNumber => ColorMode::ReturnNumber,
+            };
+
+            let normal_orientation = match normal_viewpoint.as_deref() {
+                None => NormalOrientation::Up,
+                Some([x, y, z]) => NormalOrientation::TowardsPosition(Point3::new(*x, *y, *z)),
+                Some(_) => anyhow::bail!("normal-viewpoint must have three coordinates"),
+            };
+
+            let tileset_origin = match (origin.as_deref(), origin_ecef.as_deref()) {
+                (None, None) => TilesetOrigin::Center,
+                (Some([x, y, z]), None) => TilesetOrigin::Source(Point3::new(*x, *y, *z)),
+                (None, Some([x, y, z])) => TilesetOrigin::Ecef(Point3::new(*x, *y, *z)),
+                _ => anyhow::bail!("origin must have three coordinates"),
+            };
+            let tileset_frame = match frame {
+                FrameArgument::SourceAxes => TilesetFrame::SourceAxes(tileset_origin),
+                FrameArgument::EastNorthUp => TilesetFrame::EastNorthUp(tileset_origin),
+                FrameArgument::Ecef if tileset_origin != TilesetOrigin::Center => {
+                    anyhow::bail!("ecef frame has no origin")
+                }
+                FrameArgument::Ecef => TilesetFrame::Ecef,
+            };
+            if !*local
+                && tileset_frame == TilesetFrame::Ecef
+                && *alignment == AlignmentArgument::PrincipalAxes
+            {
+                anyhow::bail!("principal-axes alignment is not supported in the ecef frame");
+            }
+
+            let tileset_placement = if *local {
+                TilesetPlacement::Local(derive_local_placement(placement.as_deref())?)
+            } else {
+                let source_crs = source_crs
+                    .ok_or_else(|| anyhow::anyhow!("source-crs is required without local"))?;
+                TilesetPlacement::Georeferenced {
+                    source_crs: SpatialReferenceIdentifier::from_code(source_crs)?,
+                    tileset_frame,
+                }
+            };
             let seed_number = if *no_shuffle { None } else { Some(*seed) };
+            let point_filter = PointFilter {
+                classification_include: include_classes
+                    .as_ref()
+                    .map(|c| c.iter().copied().collect()),
+                classification_exclude: exclude_classes.iter().copied().collect(),
+                clip_polygon: clip_polygon
+                    .as_ref()
+                    .map(ClipPolygon::from_path)
+                    .transpose()?,
+                z_min: *z_min,
+                z_max: *z_max,
+            };
+            let preprocessing_options = PreprocessingOptions {
+                point_filter,
+                statistical_outlier_removal: outlier_neighbors.map(|k| StatisticalOutlierRemoval {
+                    neighbor_count: k,
+                    standard_deviation_multiplier: *outlier_std_ratio,
+                }),
+                radius_outlier_removal: radius_outlier_radius.map(|r| RadiusOutlierRemoval {
+                    radius: r,
+                    minimum_neighbor_count: *radius_outlier_min_neighbors,
+                }),
+                voxel_size: *voxel_size,
+                color_mode,
+                color_rasters: color_raster
+                    .iter()
+                    .map(GeoRaster::from_path)
+                    .collect::<Result<Vec<_>, _>>()?,
+                raster_sampling: match raster_sampling {
+                    RasterSamplingArgument::Nearest => RasterSampling::Nearest,
+                    RasterSamplingArgument::Bilinear => RasterSampling::Bilinear,
+                },
+                normal_estimation: normal_neighbors.map(|k| NormalEstimation {
+                    neighbor_count: k,
+                    orientation: normal_orientation,
+                }),
+            };
+
+            let content_layers = content_layer
+                .iter()
+                .map(|l| match l.split_once('=') {
+                    Some((name, classes)) => Ok(ContentLayer::new(
+                        name,
+                        classes
+                            .split(',')
+                            .map(|c| c.trim().parse::<u8>())
+                            .collect::<Result<Vec<u8>, _>>()
+                            .map_err(|e| anyhow::anyhow!("invalid content layer `{l}`: {e}"))?,
+                    )),
+                    None => Ok(ContentLayer::new(l.as_str(), Vec::new())),
+                })
+                .collect::<Result<Vec<ContentLayer>>>()?;
+
+            let metadata = metadata
+                .as_ref()
+                .map(TilesetMetadata::from_path)
+                .transpose()?
+                .unwrap_or_default();
+            let asset_info = AssetInfo {
+                tileset_version: tileset_version.clone(),
+                copyright: copyright.clone(),
+                extras: asset_extras
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()
+                    .map_err(|e| anyhow::anyhow!("asset-extras must be a JSON object: {e}"))?
+                    .unwrap_or_default(),
+                extensions_used: extensions_used.clone(),
+                extensions_required: extensions_required.clone(),
+            };
+
+            let archive_header_info = ArchiveHeaderInfo {
+                modification_time: archive_time
+                    .as_deref()
+                    .map(chrono::DateTime::parse_from_rfc3339)
+                    .transpose()
+                    .map_err(|e| {
+                        anyhow::anyhow!("archive-time must be an RFC 3339 timestamp: {e}")
+                    })?
+                    .map(|t| t.to_utc())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
 
             commands::convert_point_cloud::run(
                 input_path,
                 output_path,
                 *maximum_points_per_octant,
-                source_crs,
+                tileset_placement,
+                match alignment {
+                    AlignmentArgument::FrameAxes => TilesetAlignment::FrameAxes,
+                    AlignmentArgument::PrincipalAxes => TilesetAlignment::PrincipalAxes,
+                },
                 seed_number,
+                &preprocessing_options,
+                OutputOptions {
+                    metadata,
+                    asset_info,
+                    content_layers,
+                    style: style.map(|s| match s {
+                        StyleArgument::Classification => TilesetStyle::Classification,
+                        StyleArgument::Height => TilesetStyle::Height {
+                            gradient: height_gradient,
+                        },
+                    }),
+                    archive_header_info,
+                    manifest: *manifest,
+                    precompressed_entries: *precompress_entries,
+                    upload_concurrency: *upload_concurrency,
+                    upload_retries: *upload_retries,
+                },
             )?;
         }
+        Commands::UpdatePointCloud {
+            input_path,
+            remove_path,
+            removal_tolerance,
+            tileset_path,
+            maximum_points_per_octant,
+            source_crs,
+            local,
+            placement,
+            no_shuffle,
+            seed,
+            tileset_version,
+        } => {
+            for path in input_path.iter().chain(remove_path) {
+                if !path.exists() {
+                    anyhow::bail!("input path does not exist: {}", path.display());
+                }
+                if path.is_file() && epoint::io::PointCloudFormat::from_path(path).is_none() {
+                    anyhow::bail!("unrecognized point cloud format: {}", path.display());
+                }
+            }
+            if !tileset_path.is_file() {
+                anyhow::bail!("tileset path does not exist: {}", tileset_path.display());
+            }
+
+            let coordinates =
+                derive_point_cloud_coordinates(*source_crs, *local, placement.as_deref())?;
+            let seed_number = if *no_shuffle { None } else { Some(*seed) };
+
+            commands::update_point_cloud::run(
+                input_path.as_ref(),
+                remove_path.as_ref().map(|p| (p, *removal_tolerance)),
+                tileset_path,
+                *maximum_points_per_octant,
+                coordinates,
+                seed_number,
+                tileset_version.clone(),
+            )?;
+        }
+        Commands::Merge {
+            input_path,
+            output_path,
+            repackage,
+            epoch,
+        } => {
+            if let Some(missing_path) = input_path.iter().find(|p| !p.is_file()) {
+                anyhow::bail!("input path does not exist: {}", missing_path.display());
+            }
+            if *repackage && output_path.extension().and_then(|e| e.to_str()) != Some("tar") {
+                anyhow::bail!("output path must have the extension .tar when repackaging");
+            }
+            if !epoch.is_empty() && epoch.len() != input_path.len() {
+                anyhow::bail!(
+                    "number of epochs ({}) must match the number of input paths ({})",
+                    epoch.len(),
+                    input_path.len()
+                );
+            }
+
+            commands::merge::run(input_path, epoch, output_path, *repackage)?;
+        }
+        Commands::Extract {
+            input_path,
+            output_path,
+            target_crs,
+            local,
+            placement,
+            level,
+            maximum_level,
+            bbox,
+            clip_polygon,
+        } => {
+            if !input_path.is_file() {
+                anyhow::bail!("input path does not exist: {}", input_path.display());
+            }
+            if !matches!(
+                epoint::io::PointCloudFormat::from_path(output_path),
+                Some(
+                    epoint::io::PointCloudFormat::Las
+                        | epoint::io::PointCloudFormat::Laz
+                        | epoint::io::PointCloudFormat::Xyz
+                        | epoint::io::PointCloudFormat::XyzZst
+                )
+            ) {
+                anyhow::bail!(
+                    "output path must have a LAS, LAZ or XYZ extension: {}",
+                    output_path.display()
+                );
+            }
+
+            let level_selection = match (level, maximum_level) {
+                (Some(level), _) => LevelSelection::Level(*level),
+                (None, Some(maximum_level)) => LevelSelection::UpToLevel(*maximum_level),
+                (None, None) => LevelSelection::All,
+            };
+            let clip_polygon = match bbox.as_deref() {
+                None => clip_polygon
+                    .as_ref()
+                    .map(ClipPolygon::from_path)
+                    .transpose()?,
+                Some([min_x, min_y, max_x, max_y]) => Some(ClipPolygon::from_bounds(
+                    Point2::new(*min_x, *min_y),
+                    Point2::new(*max_x, *max_y),
+                )),
+                Some(_) => anyhow::bail!("bbox must have four coordinates"),
+            };
+
+            let coordinates =
+                derive_point_cloud_coordinates(*target_crs, *local, placement.as_deref())?;
+            commands::extract::run(
+                input_path,
+                output_path,
+                coordinates,
+                level_selection,
+                clip_polygon,
+            )?;
+        }
+        Commands::Verify { input_path } => {
+            if !input_path.is_file() {
+                anyhow::bail!("input path does not exist: {}", input_path.display());
+            }
+
+            if !commands::verify::run(input_path)? {
+                anyhow::bail!("archive verification failed: {}", input_path.display());
+            }
+        }
+        #[cfg(feature = "sqlite")]
+        Commands::Serve {
+            input_path,
+            address,
+        } => {
+            if !input_path.is_file() {
+                anyhow::bail!("input path does not exist: {}", input_path.display());
+            }
+            if input_path.extension().and_then(|e| e.to_str()) != Some(FILE_EXTENSION_ETILES_SQLITE)
+            {
+                anyhow::bail!(
+                    "input path must be a .{FILE_EXTENSION_ETILES_SQLITE} container: {}",
+                    input_path.display()
+                );
+            }
+
+            commands::serve::run(input_path, *address)?;
+        }
     };
 
     Ok(())
 }
+
+/// Parses the geographic placement of local coordinates, which are taken as ECEF without one.
+fn derive_local_placement(placement: Option<&[f64]>) -> Result<LocalPlacement> {
+    match placement {
+        None => Ok(LocalPlacement::Identity),
+        Some([longitude, latitude, height, heading @ ..]) if heading.len() <= 1 => {
+            Ok(LocalPlacement::Geographic {
+                longitude: *longitude,
+                latitude: *latitude,
+                height: *height,
+                heading: heading.first().copied().unwrap_or_default(),
+            })
+        }
+        Some(_) => anyhow::bail!("placement must have three or four values"),
+    }
+}
+
+/// Derives the coordinates of an inserted, removed or extracted point cloud from its CRS or its
+/// local placement.
+fn derive_point_cloud_coordinates(
+    crs: Option<u32>,
+    local: bool,
+    placement: Option<&[f64]>,
+) -> Result<PointCloudCoordinates> {
+    if local {
+        return Ok(PointCloudCoordinates::Local(derive_local_placement(
+            placement,
+        )?));
+    }
+
+    let crs = crs.ok_or_else(|| anyhow::anyhow!("a CRS is required without local"))?;
+    Ok(PointCloudCoordinates::Crs(
+        SpatialReferenceIdentifier::from_code(crs)?,
+    ))
+}
@@ -0,0 +1,58 @@
+use etiles::{Progress, ProgressObserver, ProgressPhase, ProgressReporter};
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use std::sync::Mutex;
+
+/// Creates a progress reporter drawing a progress bar to stderr, which is hidden if stderr is
+/// not a terminal.
+pub fn create_progress_reporter() -> ProgressReporter {
+    ProgressReporter::new().with_observer(ProgressBarObserver::new())
+}
+
+/// Shows the progress of the current phase, restarting the bar whenever the phase changes.
+struct ProgressBarObserver {
+    progress_bar: ProgressBar,
+    phase: Mutex<Option<ProgressPhase>>,
+}
+
+impl ProgressBarObserver {
+    fn new() -> Self {
+        let progress_bar = ProgressBar::new(0);
+        progress_bar.set_style(
+            ProgressStyle::with_template(
+                "{prefix:>9} [{bar:40}] {human_pos}/{human_len} {percent:>3}% {msg}",
+            )
+            .expect("valid progress bar template")
+            .progress_chars("=> "),
+        );
+
+        Self {
+            progress_bar,
+            phase: Mutex::new(None),
+        }
+    }
+}
+
+impl ProgressObserver for ProgressBarObserver {
+    fn on_progress(&self, progress: &Progress) {
+        let mut phase = self.phase.lock().unwrap_or_else(|e| e.into_inner());
+        if *phase != Some(progress.phase) {
+            *phase = Some(progress.phase);
+            self.progress_bar.reset();
+            self.progress_bar.set_length(progress.total);
+            self.progress_bar.set_prefix(progress.phase.as_str());
+            self.progress_bar.set_message("");
+        }
+
+        self.progress_bar.set_position(progress.completed);
+        if let Some(remaining) = progress.remaining {
+            self.progress_bar
+                .set_message(format!("{} remaining", HumanDuration(remaining)));
+        }
+    }
+}
+
+impl Drop for ProgressBarObserver {
+    fn drop(&mut self) {
+        self.progress_bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,342 @@
+use crate::COLUMN_NAME_RETURN_NUMBER_STR;
+use crate::column::get_all_u8_values;
+use crate::error::Error;
+use crate::error::Error::{
+    EmptyColorGradient, EmptyPointCloud, InvalidHeightColorRange, InvalidHexColor,
+};
+use crate::filter::get_all_classifications;
+use epoint::{PointCloud, PointDataColumnType};
+use palette::Srgb;
+use rayon::prelude::*;
+use std::str::FromStr;
+
+/// Source of the point colors encoded into the tiles.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ColorMode {
+    /// RGB colors of the point cloud, or a constant grey if the point cloud has no colors.
+    #[default]
+    Rgb,
+    /// Colors sampled from a gradient by the z value (in source CRS).
+    /// Without a range, the z range of the point cloud is used.
+    Height {
+        gradient: ColorGradient,
+        range: Option<(f64, f64)>,
+    },
+    /// Grayscale by the intensity, scaled to the intensity range of the point cloud.
+    Intensity,
+    /// ASPRS standard classification colors.
+    Classification,
+    /// Colors by the return number of the pulse.
+    ReturnNumber,
+}
+
+/// Linear color gradient with evenly spaced color stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorGradient {
+    stops: Vec<Srgb<f32>>,
+}
+
+impl ColorGradient {
+    pub fn new(stops: Vec<Srgb<f32>>) -> Result<Self, Error> {
+        if stops.is_empty() {
+            return Err(EmptyColorGradient());
+        }
+
+        Ok(Self { stops })
+    }
+
+    /// Creates a gradient from hex codes such as `#0000ff` or `00f`.
+    pub fn from_hex_codes(hex_codes: &[impl AsRef<str>]) -> Result<Self, Error> {
+        let stops = hex_codes
+            .iter()
+            .map(|c| {
+                Srgb::<u8>::from_str(c.as_ref().trim())
+                    .map(|c| c.into_format())
+                    .map_err(|_| InvalidHexColor(c.as_ref().to_string()))
+            })
+            .collect::<Result<Vec<Srgb<f32>>, Error>>()?;
+
+        Self::new(stops)
+    }
+
+    pub fn stops(&self) -> &[Srgb<f32>] {
+        &self.stops
+    }
+
+    /// Samples the gradient at `t`, which is clamped to `[0, 1]`.
+    pub fn sample(&self, t: f64) -> Srgb<f32> {
+        if self.stops.len() == 1 {
+            return self.stops[0];
+        }
+
+        let scaled = t.clamp(0.0, 1.0) as f32 * (self.stops.len() - 1) as f32;
+        let lower_index = (scaled.floor() as usize).min(self.stops.len() - 2);
+        let fraction = scaled - lower_index as f32;
+        let lower = self.stops[lower_index];
+        let upper = self.stops[lower_index + 1];
+
+        Srgb::new(
+            lower.red + (upper.red - lower.red) * fraction,
+            lower.green + (upper.green - lower.green) * fraction,
+            lower.blue + (upper.blue - lower.blue) * fraction,
+        )
+    }
+}
+
+impl Default for ColorGradient {
+    fn default() -> Self {
+        Self {
+            stops: vec![
+                Srgb::new(0.0, 0.0, 1.0),
+                Srgb::new(0.0, 1.0, 1.0),
+                Srgb::new(0.0, 1.0, 0.0),
+                Srgb::new(1.0, 1.0, 0.0),
+                Srgb::new(1.0, 0.0, 0.0),
+            ],
+        }
+    }
+}
+
+/// Replaces the colors of the point cloud according to the color mode.
+///
+/// Fails if the point cloud is empty or a given height range is not increasing.
+pub fn colorize_point_cloud(
+    mut point_cloud: PointCloud,
+    color_mode: &ColorMode,
+) -> Result<PointCloud, Error> {
+    let colors: Vec<Srgb<f32>> = match color_mode {
+        ColorMode::Rgb => return Ok(point_cloud),
+        _ if point_cloud.size() == 0 => return Err(EmptyPointCloud()),
+        ColorMode::Height { gradient, range } => {
+            derive_height_colors(&point_cloud, gradient, *range)?
+        }
+        ColorMode::Intensity => derive_intensity_colors(&point_cloud)?,
+        ColorMode::Classification => get_all_classifications(&point_cloud)?
+            .into_par_iter()
+            .map(get_classification_color)
+            .collect(),
+        ColorMode::ReturnNumber => get_all_u8_values(&point_cloud, COLUMN_NAME_RETURN_NUMBER_STR)?
+            .into_par_iter()
+            .map(get_return_number_color)
+            .collect(),
+    };
+
+    set_all_colors(&mut point_cloud, colors)?;
+    Ok(point_cloud)
+}
+
+/// Writes the colors into the color columns of the point cloud, replacing existing ones.
+pub fn set_all_colors(point_cloud: &mut PointCloud, colors: Vec<Srgb<f32>>) -> Result<(), Error> {
+    let (red, (green, blue)): (Vec<u16>, (Vec<u16>, Vec<u16>)) = colors
+        .into_par_iter()
+        .map(|c| {
+            let c: Srgb<u16> = c.into_format();
+            (c.red, (c.green, c.blue))
+        })
+        .unzip();
+
+    let point_data = &mut point_cloud.point_data;
+    point_data.add_u16_column(PointDataColumnType::ColorRed.as_str(), red)?;
+    point_data.add_u16_column(PointDataColumnType::ColorGreen.as_str(), green)?;
+    point_data.add_u16_column(PointDataColumnType::ColorBlue.as_str(), blue)?;
+    Ok(())
+}
+
+fn derive_height_colors(
+    point_cloud: &PointCloud,
+    gradient: &ColorGradient,
+    range: Option<(f64, f64)>,
+) -> Result<Vec<Srgb<f32>>, Error> {
+    if let Some((z_min, z_max)) = range
+        && (z_max <= z_min || z_min.is_nan() || z_max.is_nan())
+    {
+        return Err(InvalidHeightColorRange(z_min, z_max));
+    }
+    let (z_min, z_max) = range.unwrap_or_else(|| {
+        (
+            point_cloud.point_data.get_local_min().z,
+            point_cloud.point_data.get_local_max().z,
+        )
+    });
+    let z_extent = z_max - z_min;
+
+    let colors = point_cloud
+        .point_data
+        .get_z_values()
+        .into_no_null_iter()
+        .collect::<Vec<f64>>()
+        .into_par_iter()
+        .map(|z| {
+            let t = if z_extent > 0.0 {
+                (z - z_min) / z_extent
+            } else {
+                0.5
+            };
+            gradient.sample(t)
+        })
+        .collect();
+
+    Ok(colors)
+}
+
+fn derive_intensity_colors(point_cloud: &PointCloud) -> Result<Vec<Srgb<f32>>, Error> {
+    let intensity_min = point_cloud
+        .point_data
+        .get_intensity_min()?
+        .unwrap_or_default();
+    let intensity_max = point_cloud
+        .point_data
+        .get_intensity_max()?
+        .unwrap_or_default();
+    let intensity_extent = intensity_max - intensity_min;
+
+    let colors = point_cloud
+        .point_data
+        .get_intensity_values()?
+        .into_iter()
+        .map(|i| {
+            let value = match i {
+                Some(i) if intensity_extent > 0.0 => (i - intensity_min) / intensity_extent,
+                _ => 0.5,
+            };
+            Srgb::new(value, value, value)
+        })
+        .collect();
+
+    Ok(colors)
+}
+
+/// Colors of the ASPRS standard point classes as commonly used by point cloud viewers.
+pub fn get_classification_color(classification: u8) -> Srgb<f32> {
+    let color: Srgb<u8> = match classification {
+        0 => Srgb::new(190, 190, 190),  // created, never classified
+        1 => Srgb::new(170, 170, 170),  // unclassified
+        2 => Srgb::new(170, 85, 0),     // ground
+        3 => Srgb::new(0, 170, 170),    // low vegetation
+        4 => Srgb::new(85, 255, 85),    // medium vegetation
+        5 => Srgb::new(0, 136, 0),      // high vegetation
+        6 => Srgb::new(255, 85, 85),    // building
+        7 => Srgb::new(170, 0, 170),    // low point (noise)
+        8 => Srgb::new(255, 255, 85),   // model key-point
+        9 => Srgb::new(85, 85, 255),    // water
+        10 => Srgb::new(255, 170, 0),   // rail
+        11 => Srgb::new(85, 85, 85),    // road surface
+        12 => Srgb::new(255, 255, 255), // overlap
+        13 => Srgb::new(255, 255, 0),   // wire guard (shield)
+        14 => Srgb::new(255, 200, 0),   // wire conductor (phase)
+        15 => Srgb::new(170, 170, 255), // transmission tower
+        16 => Srgb::new(255, 130, 255), // wire-structure connector
+        17 => Srgb::new(200, 200, 200), // bridge deck
+        18 => Srgb::new(255, 0, 255),   // high noise
+        _ => Srgb::new(128, 128, 128),  // reserved or user definable
+    };
+
+    color.into_format()
+}
+
+/// Colors for the first, second, third and all further returns.
+pub fn get_return_number_color(return_number: u8) -> Srgb<f32> {
+    let color: Srgb<u8> = match return_number {
+        0 | 1 => Srgb::new(230, 75, 55),
+        2 => Srgb::new(75, 190, 80),
+        3 => Srgb::new(55, 120, 230),
+        _ => Srgb::new(240, 200, 50),
+    };
+
+    color.into_format()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{derive_classified_point_cloud, derive_point_cloud};
+    use nalgebra::Point3;
+
+    fn derive_colors(point_cloud: &PointCloud) -> Vec<Srgb<f32>> {
+        point_cloud
+            .point_data
+            .get_all_colors()
+            .expect("colors")
+            .into_iter()
+            .map(|c| c.into_format())
+            .collect()
+    }
+
+    fn assert_color_eq(color: Srgb<f32>, expected_color: Srgb<f32>) {
+        let difference = (color.red - expected_color.red)
+            .abs()
+            .max((color.green - expected_color.green).abs())
+            .max((color.blue - expected_color.blue).abs());
+        assert!(
+            difference < 1e-4,
+            "{color:?} differs from {expected_color:?}"
+        );
+    }
+
+    #[test]
+    fn test_gradient_sample_at_stops_and_midpoints() {
+        let gradient =
+            ColorGradient::from_hex_codes(&["#0000ff", "#00ff00", "#ff0000"]).expect("gradient");
+
+        assert_color_eq(gradient.sample(0.0), Srgb::new(0.0, 0.0, 1.0));
+        assert_color_eq(gradient.sample(0.5), Srgb::new(0.0, 1.0, 0.0));
+        assert_color_eq(gradient.sample(1.0), Srgb::new(1.0, 0.0, 0.0));
+        assert_color_eq(gradient.sample(0.25), Srgb::new(0.0, 0.5, 0.5));
+        assert_color_eq(gradient.sample(0.75), Srgb::new(0.5, 0.5, 0.0));
+        // outside of the gradient, the end stops are used
+        assert_color_eq(gradient.sample(-1.0), Srgb::new(0.0, 0.0, 1.0));
+        assert_color_eq(gradient.sample(2.0), Srgb::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_constant_height_takes_gradient_center() {
+        let point_cloud =
+            derive_point_cloud((0..10).map(|i| Point3::new(i as f64, 0.0, 5.0)).collect());
+        let gradient = ColorGradient::default();
+
+        let point_cloud = colorize_point_cloud(
+            point_cloud,
+            &ColorMode::Height {
+                gradient: gradient.clone(),
+                range: None,
+            },
+        )
+        .expect("colorized point cloud");
+
+        for color in derive_colors(&point_cloud) {
+            assert_color_eq(color, gradient.sample(0.5));
+        }
+    }
+
+    #[test]
+    fn test_height_range_must_be_increasing() {
+        for (z_min, z_max) in [(10.0, 5.0), (5.0, 5.0), (f64::NAN, 5.0)] {
+            let point_cloud = derive_point_cloud(vec![Point3::new(0.0, 0.0, 7.0)]);
+
+            let result = colorize_point_cloud(
+                point_cloud,
+                &ColorMode::Height {
+                    gradient: ColorGradient::default(),
+                    range: Some((z_min, z_max)),
+                },
+            );
+
+            assert!(matches!(result, Err(InvalidHeightColorRange(..))));
+        }
+    }
+
+    #[test]
+    fn test_unknown_classifications_take_fallback_color() {
+        let fallback_color: Srgb<f32> = Srgb::new(128u8, 128, 128).into_format();
+        let point_cloud =
+            derive_classified_point_cloud(vec![Point3::new(0.0, 0.0, 0.0); 3], vec![2, 19, 200]);
+
+        let point_cloud = colorize_point_cloud(point_cloud, &ColorMode::Classification)
+            .expect("colorized point cloud");
+
+        let colors = derive_colors(&point_cloud);
+        assert_color_eq(colors[0], get_classification_color(2));
+        assert_color_eq(colors[1], fallback_color);
+        assert_color_eq(colors[2], fallback_color);
+    }
+}
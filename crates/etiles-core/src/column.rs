@@ -0,0 +1,47 @@
+use crate::error::Error;
+use crate::error::Error::NoColumn;
+use epoint::PointCloud;
+use polars::prelude::DataType;
+
+/// Returns the values of an optional integer column, which may be stored with any integer type.
+pub(crate) fn get_all_u8_values(
+    point_cloud: &PointCloud,
+    column_name: &'static str,
+) -> Result<Vec<u8>, Error> {
+    let column = point_cloud
+        .point_data
+        .data_frame
+        .column(column_name)
+        .map_err(|_| NoColumn(column_name))?
+        .cast(&DataType::UInt32)?;
+
+    let values: Vec<u8> = column
+        .u32()?
+        .into_iter()
+        .map(|c| c.unwrap_or_default() as u8)
+        .collect();
+
+    Ok(values)
+}
+
+/// Returns the values of an optional numeric column as `f64`, which may be stored with any
+/// numeric type.
+pub(crate) fn get_all_f64_values(
+    point_cloud: &PointCloud,
+    column_name: &'static str,
+) -> Result<Vec<f64>, Error> {
+    let column = point_cloud
+        .point_data
+        .data_frame
+        .column(column_name)
+        .map_err(|_| NoColumn(column_name))?
+        .cast(&DataType::Float64)?;
+
+    let values: Vec<f64> = column
+        .f64()?
+        .into_iter()
+        .map(|v| v.unwrap_or_default())
+        .collect();
+
+    Ok(values)
+}
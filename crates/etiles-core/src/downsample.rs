@@ -0,0 +1,135 @@
+use crate::error::Error;
+use crate::error::Error::NonPositiveParameter;
+use crate::filter::filter_by_mask;
+use epoint::PointCloud;
+use nalgebra::{Point3, Vector3};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Reduces the point cloud to at most one point per cubic voxel with an edge length of `voxel_size`.
+///
+/// Of all points within a voxel, the point closest to the voxel center is kept, so that all other
+/// point attributes are preserved.
+pub fn downsample_voxel_grid(
+    point_cloud: PointCloud,
+    voxel_size: f64,
+) -> Result<PointCloud, Error> {
+    if voxel_size <= 0.0 {
+        return Err(NonPositiveParameter("voxel_size"));
+    }
+
+    let all_points = point_cloud.point_data.get_all_points();
+    let origin = point_cloud.point_data.get_local_min();
+    let half_voxel = Vector3::new(voxel_size, voxel_size, voxel_size) / 2.0;
+
+    let voxel_assignments: Vec<((i64, i64, i64), f64)> = all_points
+        .par_iter()
+        .map(|p| {
+            let voxel_index = (
+                ((p.x - origin.x) / voxel_size).floor() as i64,
+                ((p.y - origin.y) / voxel_size).floor() as i64,
+                ((p.z - origin.z) / voxel_size).floor() as i64,
+            );
+            let voxel_center = origin
+                + Vector3::new(
+                    voxel_index.0 as f64,
+                    voxel_index.1 as f64,
+                    voxel_index.2 as f64,
+                ) * voxel_size
+                + half_voxel;
+            (voxel_index, distance_squared(p, &voxel_center))
+        })
+        .collect();
+
+    let mut closest_points: HashMap<(i64, i64, i64), (usize, f64)> = HashMap::new();
+    for (current_point_index, (current_voxel_index, current_distance)) in
+        voxel_assignments.into_iter().enumerate()
+    {
+        closest_points
+            .entry(current_voxel_index)
+            .and_modify(|(closest_index, closest_distance)| {
+                if current_distance < *closest_distance {
+                    *closest_index = current_point_index;
+                    *closest_distance = current_distance;
+                }
+            })
+            .or_insert((current_point_index, current_distance));
+    }
+
+    let mut mask = vec![false; all_points.len()];
+    for (current_point_index, _) in closest_points.values() {
+        mask[*current_point_index] = true;
+    }
+    info!(
+        "Downsampled {} points to {} points with a voxel size of {}",
+        all_points.len(),
+        closest_points.len(),
+        voxel_size
+    );
+
+    filter_by_mask(point_cloud, mask)
+}
+
+fn distance_squared(a: &Point3<f64>, b: &Point3<f64>) -> f64 {
+    (a - b).norm_squared()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::derive_point_cloud;
+
+    #[test]
+    fn test_voxel_grid_keeps_point_closest_to_voxel_center() {
+        // voxels start at the minimum (0, 0, 0), so that their centers lie at 0.5 + i
+        let points = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.45, 0.5, 0.55),
+            Point3::new(0.9, 0.9, 0.9),
+            Point3::new(1.1, 0.2, 0.3),
+            Point3::new(1.5, 0.4, 0.6),
+            Point3::new(1.95, 0.95, 0.05),
+            Point3::new(0.2, 0.7, 2.6),
+        ];
+
+        let point_cloud =
+            downsample_voxel_grid(derive_point_cloud(points), 1.0).expect("point cloud");
+
+        assert_eq!(
+            point_cloud.point_data.get_all_points(),
+            vec![
+                Point3::new(0.45, 0.5, 0.55),
+                Point3::new(1.5, 0.4, 0.6),
+                Point3::new(0.2, 0.7, 2.6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_voxel_grid_count_and_centroid_of_regular_grid() {
+        // 8×8×8 points with a spacing of 0.25 fall into 2×2×2 voxels of size 1, whose centers at
+        // 0.5 and 1.5 are grid points
+        let points: Vec<Point3<f64>> = (0..512)
+            .map(|i| Point3::new((i % 8) as f64, ((i / 8) % 8) as f64, (i / 64) as f64) * 0.25)
+            .collect();
+
+        let point_cloud =
+            downsample_voxel_grid(derive_point_cloud(points), 1.0).expect("point cloud");
+
+        let points = point_cloud.point_data.get_all_points();
+        assert_eq!(points.len(), 8);
+        let centroid = points
+            .iter()
+            .fold(Vector3::zeros(), |sum, p| sum + p.coords)
+            / 8.0;
+        assert!((centroid - Vector3::repeat(1.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_voxel_grid_rejects_non_positive_voxel_size() {
+        let result = downsample_voxel_grid(derive_point_cloud(vec![Point3::origin()]), 0.0);
+
+        assert!(matches!(result, Err(NonPositiveParameter("voxel_size"))));
+    }
+}
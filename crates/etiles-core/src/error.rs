@@ -8,4 +8,51 @@ pub enum Error {
     EprojError(#[from] eproj::Error),
     #[error(transparent)]
     EpointError(#[from] epoint::Error),
+    #[error(transparent)]
+    PolarsError(#[from] polars::error::PolarsError),
+    #[error(transparent)]
+    GeoJsonError(#[from] geojson::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("point data contains no column `{0}`")]
+    NoColumn(&'static str),
+    #[error("no points remaining after filtering")]
+    NoRemainingPoints(),
+    #[error("invalid WKT: {0}")]
+    InvalidWkt(String),
+    #[error("geometry of type `{0}` is not supported as clip polygon")]
+    UnsupportedGeometryType(&'static str),
+    #[error("color gradient must contain at least one color")]
+    EmptyColorGradient(),
+    #[error("invalid hex color `{0}`")]
+    InvalidHexColor(String),
+    #[error("point cloud contains no points")]
+    EmptyPointCloud(),
+    #[error("height color range from {0} to {1} is not increasing")]
+    InvalidHeightColorRange(f64, f64),
+    #[error(transparent)]
+    TiffError(#[from] tiff::TiffError),
+    #[error("invalid GeoTIFF: {0}")]
+    InvalidGeoTiff(String),
+    #[error("{0} points lie outside the fixed octree bounds of the tileset")]
+    PointsOutsideBounds(usize),
+    #[error("enclosing cube with edge length {0} is not a power of two cube of the octree")]
+    InvalidEnclosingCube(f64),
+    #[error("{0} points match no point of the tileset")]
+    PointsNotFound(usize),
+    #[error("parameter `{0}` must be positive")]
+    NonPositiveParameter(&'static str),
+    #[error("parameter `{0}` must be at least {1}")]
+    ParameterTooSmall(&'static str, usize),
+    #[error("file extension `{0}` is invalid")]
+    InvalidFileExtension(String),
+    #[error("tileset contains no points")]
+    EmptyTileset(),
+    #[error("{1} vertices lack the attribute `{0}` declared by the vertex schema")]
+    MissingVertexAttribute(&'static str, usize),
+    #[error("principal axes alignment is not supported in the ECEF frame, whose z-axis is not up")]
+    UnsupportedFrameAlignment(),
+    #[error("operation was cancelled")]
+    Cancelled(),
 }
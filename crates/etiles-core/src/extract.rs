@@ -0,0 +1,346 @@
+use crate::error::Error;
+use crate::error::Error::NoRemainingPoints;
+use crate::frame::{LocalPlacement, derive_normal_rotation};
+use crate::reproject::reproject_point_cloud;
+use crate::tileset::{Tileset, Vertex};
+use crate::{
+    COLUMN_NAME_CLASSIFICATION_STR, COLUMN_NAME_NORMAL_X_STR, COLUMN_NAME_NORMAL_Y_STR,
+    COLUMN_NAME_NORMAL_Z_STR,
+};
+use chrono::{DateTime, Utc};
+use ecoord::TransformTree;
+use ecoord::octree::OctantIndex;
+use epoint::{PointCloud, PointCloudInfo, PointDataColumns};
+use eproj::SpatialReferenceIdentifier;
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+use palette::Srgb;
+use polars::prelude::{NamedFrom, Series};
+
+/// Octree levels whose content is extracted from a tileset.
+///
+/// As tiles are refined additively, the content down to a level represents the point cloud at
+/// the corresponding level of detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LevelSelection {
+    /// Content of all levels, i.e. the full point cloud.
+    #[default]
+    All,
+    /// Content of only this level.
+    Level(u32),
+    /// Content of the root down to and including this level.
+    UpToLevel(u32),
+}
+
+impl LevelSelection {
+    pub fn contains(&self, level: u32) -> bool {
+        match self {
+            Self::All => true,
+            Self::Level(l) => level == *l,
+            Self::UpToLevel(l) => level <= *l,
+        }
+    }
+}
+
+/// Assembles a point cloud from the content of the selected octants, transformed from the local
+/// frame of the root transform into the target CRS.
+///
+/// Normals are rotated from the local frame into the axes of the target CRS at the center of the
+/// extracted points. Normals, times and classifications are only kept if every extracted vertex
+/// has one.
+pub fn extract_point_cloud(
+    tileset: &Tileset,
+    target_srs: SpatialReferenceIdentifier,
+    level_selection: LevelSelection,
+) -> Result<PointCloud, Error> {
+    let vertices = select_vertices(tileset, level_selection)?;
+
+    let points: Vec<Point3<f64>> = vertices
+        .iter()
+        .map(|v| tileset.root_transform * v.position)
+        .collect();
+    let point_cloud = derive_point_cloud(&vertices, points)?;
+    let mut point_cloud = reproject_point_cloud(
+        point_cloud,
+        SpatialReferenceIdentifier::Epsg4978,
+        target_srs,
+    )?;
+
+    // the inverse rotates from the local frame into the axes of the target CRS
+    let normal_rotation = vertices
+        .iter()
+        .all(|v| v.normal.is_some())
+        .then(|| {
+            derive_normal_rotation(
+                target_srs,
+                point_cloud.point_data.get_local_center(),
+                &tileset.root_transform,
+            )
+        })
+        .transpose()?
+        .map(|r| r.inverse());
+    add_vertex_attributes(&mut point_cloud, &vertices, normal_rotation)?;
+
+    Ok(point_cloud)
+}
+
+/// Assembles a point cloud like [`extract_point_cloud`], but in the local coordinates of a tileset
+/// built by [`Tileset::from_local_point_cloud`] with the same placement, without reprojection.
+pub fn extract_local_point_cloud(
+    tileset: &Tileset,
+    local_placement: LocalPlacement,
+    level_selection: LevelSelection,
+) -> Result<PointCloud, Error> {
+    let vertices = select_vertices(tileset, level_selection)?;
+
+    let local_transform = local_placement.derive_transform().inverse() * tileset.root_transform;
+    let points: Vec<Point3<f64>> = vertices
+        .iter()
+        .map(|v| local_transform * v.position)
+        .collect();
+    let mut point_cloud = derive_point_cloud(&vertices, points)?;
+    add_vertex_attributes(&mut point_cloud, &vertices, Some(local_transform.rotation))?;
+
+    Ok(point_cloud)
+}
+
+/// Returns the vertices of the selected octants, ordered by octant.
+fn select_vertices(
+    tileset: &Tileset,
+    level_selection: LevelSelection,
+) -> Result<Vec<&Vertex>, Error> {
+    let mut octant_indices: Vec<&OctantIndex> = tileset
+        .tiled_content
+        .cells()
+        .keys()
+        .filter(|i| level_selection.contains(i.level))
+        .collect();
+    octant_indices.sort_by_key(|i| (i.level, i.x, i.y, i.z));
+
+    let vertices: Vec<&Vertex> = octant_indices
+        .into_iter()
+        .flat_map(|i| tileset.tiled_content.cells()[i].iter())
+        .collect();
+    if vertices.is_empty() {
+        return Err(NoRemainingPoints());
+    }
+
+    Ok(vertices)
+}
+
+/// Creates the point cloud with the points, colors and, if every vertex has one, times.
+fn derive_point_cloud(vertices: &[&Vertex], points: Vec<Point3<f64>>) -> Result<PointCloud, Error> {
+    let colors: Vec<Srgb<u16>> = vertices.iter().map(|v| v.color.into_format()).collect();
+    // times are only kept if every vertex has one
+    let timestamps: Option<Vec<DateTime<Utc>>> = vertices
+        .iter()
+        .map(|v| {
+            v.time.and_then(|t| {
+                let nanos = ((t.fract() * 1e9).round() as u32).min(999_999_999);
+                DateTime::from_timestamp(t.floor() as i64, nanos)
+            })
+        })
+        .collect();
+    let point_data =
+        PointDataColumns::new(points, None, None, timestamps, None, None, Some(colors))?;
+
+    Ok(PointCloud::new(
+        point_data,
+        PointCloudInfo::new(None),
+        TransformTree::default(),
+    )?)
+}
+
+/// Adds the normals, rotated by `normal_rotation` if every vertex has one, and the
+/// classifications if every vertex has one.
+fn add_vertex_attributes(
+    point_cloud: &mut PointCloud,
+    vertices: &[&Vertex],
+    normal_rotation: Option<UnitQuaternion<f64>>,
+) -> Result<(), Error> {
+    if let Some(normal_rotation) = normal_rotation
+        && let Some(normals) = vertices
+            .iter()
+            .map(|v| {
+                v.normal
+                    .map(|n| (normal_rotation * n.cast::<f64>()).cast::<f32>())
+            })
+            .collect::<Option<Vec<Vector3<f32>>>>()
+    {
+        let point_data = &mut point_cloud.point_data;
+        point_data.add_f32_column(
+            COLUMN_NAME_NORMAL_X_STR,
+            normals.iter().map(|n| n.x).collect(),
+        )?;
+        point_data.add_f32_column(
+            COLUMN_NAME_NORMAL_Y_STR,
+            normals.iter().map(|n| n.y).collect(),
+        )?;
+        point_data.add_f32_column(
+            COLUMN_NAME_NORMAL_Z_STR,
+            normals.iter().map(|n| n.z).collect(),
+        )?;
+    }
+
+    // classifications are only kept if every vertex has one
+    if let Some(classifications) = vertices
+        .iter()
+        .map(|v| v.classification)
+        .collect::<Option<Vec<u8>>>()
+    {
+        point_cloud.point_data.data_frame.with_column(Series::new(
+            COLUMN_NAME_CLASSIFICATION_STR.into(),
+            classifications,
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::{get_all_f64_values, get_all_u8_values};
+    use crate::progress::ProgressReporter;
+
+    const LOCAL_PLACEMENT: LocalPlacement = LocalPlacement::Geographic {
+        longitude: 11.575,
+        latitude: 48.137,
+        height: 520.0,
+        heading: 30.0,
+    };
+
+    /// Vertices on a grid of 10 x 10 x 10 points with an upward normal.
+    fn derive_vertices() -> Vec<Vertex> {
+        (0..1_000)
+            .map(|i| Vertex {
+                position: Point3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64),
+                color: Srgb::new(0.2, 0.4, 0.6),
+                normal: Some(Vector3::z()),
+                classification: Some(2),
+                time: Some(1.7e9 + i as f64),
+            })
+            .collect()
+    }
+
+    /// Builds the tileset of the vertices, given in the local frame of the placement.
+    fn derive_tileset(vertices: Vec<Vertex>) -> Tileset {
+        Tileset::from_local_vertices(
+            vertices,
+            LOCAL_PLACEMENT.derive_transform(),
+            None,
+            100,
+            Some(42),
+            &ProgressReporter::default(),
+        )
+        .expect("tileset")
+    }
+
+    fn count_vertices(tileset: &Tileset, level_selection: LevelSelection) -> usize {
+        tileset
+            .tiled_content
+            .cells()
+            .iter()
+            .filter(|(i, _)| level_selection.contains(i.level))
+            .map(|(_, c)| c.len())
+            .sum()
+    }
+
+    #[test]
+    fn test_level_selection_extracts_selected_levels() {
+        let tileset = derive_tileset(derive_vertices());
+        let deepest_level = tileset
+            .tiled_content
+            .cells()
+            .keys()
+            .map(|i| i.level)
+            .max()
+            .expect("octants");
+        assert!(deepest_level >= 2);
+
+        for level_selection in [
+            LevelSelection::Level(0),
+            LevelSelection::Level(1),
+            LevelSelection::UpToLevel(1),
+            LevelSelection::UpToLevel(deepest_level),
+        ] {
+            let point_cloud = extract_local_point_cloud(&tileset, LOCAL_PLACEMENT, level_selection)
+                .expect("point cloud");
+
+            assert_eq!(
+                point_cloud.size(),
+                count_vertices(&tileset, level_selection),
+                "{level_selection:?}"
+            );
+        }
+        assert_eq!(
+            count_vertices(&tileset, LevelSelection::UpToLevel(deepest_level)),
+            1_000
+        );
+        let result = extract_local_point_cloud(
+            &tileset,
+            LOCAL_PLACEMENT,
+            LevelSelection::Level(deepest_level + 1),
+        );
+        assert!(matches!(result, Err(NoRemainingPoints())));
+    }
+
+    #[test]
+    fn test_attributes_are_only_kept_if_every_vertex_has_them() {
+        let point_cloud = extract_local_point_cloud(
+            &derive_tileset(derive_vertices()),
+            LOCAL_PLACEMENT,
+            LevelSelection::All,
+        )
+        .expect("point cloud");
+        assert!(point_cloud.point_data.contains_timestamp_sec_column());
+        assert_eq!(
+            get_all_u8_values(&point_cloud, COLUMN_NAME_CLASSIFICATION_STR).expect("classes"),
+            vec![2; 1_000]
+        );
+        assert!(get_all_f64_values(&point_cloud, COLUMN_NAME_NORMAL_Z_STR).is_ok());
+
+        let mut vertices = derive_vertices();
+        vertices[0].normal = None;
+        vertices[1].classification = None;
+        vertices[2].time = None;
+        let point_cloud = extract_local_point_cloud(
+            &derive_tileset(vertices),
+            LOCAL_PLACEMENT,
+            LevelSelection::All,
+        )
+        .expect("point cloud");
+        assert!(!point_cloud.point_data.contains_timestamp_sec_column());
+        assert!(get_all_u8_values(&point_cloud, COLUMN_NAME_CLASSIFICATION_STR).is_err());
+        assert!(get_all_f64_values(&point_cloud, COLUMN_NAME_NORMAL_Z_STR).is_err());
+    }
+
+    #[test]
+    fn test_normals_are_rotated_into_target_crs() {
+        let tileset = derive_tileset(derive_vertices());
+        // the axes of ECEF at any point are the ECEF axes
+        let expected_normal = tileset.root_transform.rotation * Vector3::z();
+
+        let point_cloud = extract_point_cloud(
+            &tileset,
+            SpatialReferenceIdentifier::Epsg4978,
+            LevelSelection::All,
+        )
+        .expect("point cloud");
+
+        let [x, y, z] = [
+            COLUMN_NAME_NORMAL_X_STR,
+            COLUMN_NAME_NORMAL_Y_STR,
+            COLUMN_NAME_NORMAL_Z_STR,
+        ]
+        .map(|c| get_all_f64_values(&point_cloud, c).expect("normal column"));
+        assert_eq!(x.len(), 1_000);
+        for ((x, y), z) in x.into_iter().zip(y).zip(z) {
+            let normal = Vector3::new(x, y, z);
+            assert!(
+                (normal - expected_normal).norm() < 1e-6,
+                "{normal} differs from {expected_normal}"
+            );
+        }
+        assert!((expected_normal - Vector3::z()).norm() > 0.1);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::COLUMN_NAME_CLASSIFICATION_STR;
+use crate::column::get_all_u8_values;
+use crate::error::Error;
+use crate::error::Error::NoRemainingPoints;
+use crate::polygon::ClipPolygon;
+use epoint::PointCloud;
+use nalgebra::Point2;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Criteria for dropping points before the octree is built.
+///
+/// All criteria are combined, i.e. a point must satisfy every configured criterion to be kept.
+#[derive(Debug, Clone, Default)]
+pub struct PointFilter {
+    /// Only keep points of these classes (if set).
+    pub classification_include: Option<HashSet<u8>>,
+    /// Drop points of these classes.
+    pub classification_exclude: HashSet<u8>,
+    /// Only keep points whose horizontal position lies within the polygon (in source CRS).
+    pub clip_polygon: Option<ClipPolygon>,
+    /// Minimum z value (in source CRS).
+    pub z_min: Option<f64>,
+    /// Maximum z value (in source CRS).
+    pub z_max: Option<f64>,
+}
+
+impl PointFilter {
+    pub fn is_empty(&self) -> bool {
+        self.classification_include.is_none()
+            && self.classification_exclude.is_empty()
+            && self.clip_polygon.is_none()
+            && self.z_min.is_none()
+            && self.z_max.is_none()
+    }
+
+    fn filters_classification(&self) -> bool {
+        self.classification_include.is_some() || !self.classification_exclude.is_empty()
+    }
+
+    fn is_classification_kept(&self, classification: u8) -> bool {
+        if let Some(classification_include) = &self.classification_include
+            && !classification_include.contains(&classification)
+        {
+            return false;
+        }
+
+        !self.classification_exclude.contains(&classification)
+    }
+}
+
+/// Drops all points of the point cloud not satisfying the filter.
+///
+/// The filter is evaluated in the source CRS and must therefore be applied before reprojecting.
+pub fn filter_point_cloud(
+    point_cloud: PointCloud,
+    filter: &PointFilter,
+) -> Result<PointCloud, Error> {
+    if filter.is_empty() {
+        return Ok(point_cloud);
+    }
+
+    let classifications: Option<Vec<u8>> = if filter.filters_classification() {
+        Some(get_all_classifications(&point_cloud)?)
+    } else {
+        None
+    };
+
+    let all_points = point_cloud.point_data.get_all_points();
+    let mask: Vec<bool> = all_points
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if filter.z_min.is_some_and(|z_min| p.z < z_min) {
+                return false;
+            }
+            if filter.z_max.is_some_and(|z_max| p.z > z_max) {
+                return false;
+            }
+            if let Some(classifications) = &classifications
+                && !filter.is_classification_kept(classifications[i])
+            {
+                return false;
+            }
+            if let Some(clip_polygon) = &filter.clip_polygon
+                && !clip_polygon.contains_point(&Point2::new(p.x, p.y))
+            {
+                return false;
+            }
+
+            true
+        })
+        .collect();
+
+    filter_by_mask(point_cloud, mask)
+}
+
+/// Keeps the points whose mask value is true and fails if no point remains.
+pub(crate) fn filter_by_mask(
+    point_cloud: PointCloud,
+    mask: Vec<bool>,
+) -> Result<PointCloud, Error> {
+    let remaining_point_count = mask.iter().filter(|x| **x).count();
+    if remaining_point_count == 0 {
+        return Err(NoRemainingPoints());
+    }
+    if remaining_point_count == mask.len() {
+        return Ok(point_cloud);
+    }
+
+    let filtered_point_cloud = point_cloud.filter_by_boolean_mask(&mask)?;
+    Ok(filtered_point_cloud)
+}
+
+/// Returns the classification of each point, if the point data contains a classification column.
+pub fn get_all_classifications(point_cloud: &PointCloud) -> Result<Vec<u8>, Error> {
+    get_all_u8_values(point_cloud, COLUMN_NAME_CLASSIFICATION_STR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{derive_classified_point_cloud, derive_point_cloud};
+    use nalgebra::Point3;
+
+    fn derive_classified_points() -> (Vec<Point3<f64>>, Vec<u8>) {
+        let points = vec![
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(1.0, 0.0, 5.0),
+            Point3::new(2.0, 0.0, 12.0),
+            Point3::new(3.0, 0.0, 2.0),
+            Point3::new(4.0, 0.0, 3.0),
+            Point3::new(5.0, 0.0, -1.0),
+        ];
+        (points, vec![2, 2, 2, 6, 7, 2])
+    }
+
+    #[test]
+    fn test_classes_and_z_range_are_combined() {
+        let (points, classifications) = derive_classified_points();
+        let filter = PointFilter {
+            classification_include: Some(HashSet::from([2, 6])),
+            classification_exclude: HashSet::from([6]),
+            z_min: Some(0.0),
+            z_max: Some(10.0),
+            ..Default::default()
+        };
+
+        let point_cloud = filter_point_cloud(
+            derive_classified_point_cloud(points, classifications),
+            &filter,
+        )
+        .expect("filtered point cloud");
+
+        assert_eq!(
+            point_cloud.point_data.get_all_points(),
+            vec![Point3::new(0.0, 0.0, 1.0), Point3::new(1.0, 0.0, 5.0)]
+        );
+        assert_eq!(get_all_classifications(&point_cloud).unwrap(), vec![2, 2]);
+    }
+
+    #[test]
+    fn test_excluded_classes_are_dropped_within_z_range() {
+        let (points, classifications) = derive_classified_points();
+        let filter = PointFilter {
+            classification_exclude: HashSet::from([2]),
+            z_min: Some(2.5),
+            ..Default::default()
+        };
+
+        let point_cloud = filter_point_cloud(
+            derive_classified_point_cloud(points, classifications),
+            &filter,
+        )
+        .expect("filtered point cloud");
+
+        assert_eq!(get_all_classifications(&point_cloud).unwrap(), vec![7]);
+    }
+
+    #[test]
+    fn test_z_range_without_classification_column() {
+        let (points, _) = derive_classified_points();
+        let filter = PointFilter {
+            z_max: Some(2.0),
+            ..Default::default()
+        };
+
+        let point_cloud =
+            filter_point_cloud(derive_point_cloud(points), &filter).expect("filtered point cloud");
+
+        assert_eq!(point_cloud.size(), 3);
+    }
+
+    #[test]
+    fn test_filter_fails_without_remaining_points() {
+        let (points, classifications) = derive_classified_points();
+        let filter = PointFilter {
+            classification_include: Some(HashSet::from([6])),
+            z_max: Some(0.0),
+            ..Default::default()
+        };
+
+        let result = filter_point_cloud(
+            derive_classified_point_cloud(points, classifications),
+            &filter,
+        );
+
+        assert!(matches!(result, Err(NoRemainingPoints())));
+    }
+}
@@ -0,0 +1,464 @@
+use crate::error::Error;
+use crate::error::Error::UnsupportedFrameAlignment;
+use crate::tileset::Vertex;
+use eproj::{Projector, SpatialReferenceIdentifier};
+use nalgebra::{Isometry3, Matrix3, Point3, Rotation3, Translation3, UnitQuaternion, Vector3};
+
+const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Origin of the local frame of a tileset.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TilesetOrigin {
+    /// Center of the bounding box of the points.
+    #[default]
+    Center,
+    /// Point given in the source CRS.
+    Source(Point3<f64>),
+    /// Point given in ECEF coordinates (EPSG:4978).
+    Ecef(Point3<f64>),
+}
+
+/// Local frame in which the points of a tileset are stored, which defines its root transform.
+///
+/// Tilesets built with the same frame at a fixed origin, e.g. from tiled input blocks, share the
+/// same root transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TilesetFrame {
+    /// Axes of the source CRS at the origin, as derived by the projection.
+    SourceAxes(TilesetOrigin),
+    /// East, north and up axes of the WGS 84 ellipsoid at the origin.
+    EastNorthUp(TilesetOrigin),
+    /// ECEF coordinates with the identity as root transform. The tiles store their positions
+    /// relative to their centers, and the tileset has no heights, as the z-axis is not up.
+    Ecef,
+}
+
+impl Default for TilesetFrame {
+    fn default() -> Self {
+        Self::SourceAxes(TilesetOrigin::Center)
+    }
+}
+
+impl TilesetFrame {
+    /// Derives the root transform from the local frame to ECEF, where `center` is the center of
+    /// the points in the source CRS.
+    pub(crate) fn derive_root_transform(
+        &self,
+        source_srs: SpatialReferenceIdentifier,
+        center: Point3<f64>,
+    ) -> Result<Isometry3<f64>, Error> {
+        let projector = Projector::new(source_srs, SpatialReferenceIdentifier::Epsg4978)?;
+
+        let root_transform = match self {
+            Self::SourceAxes(origin) => {
+                let source_origin = match origin {
+                    TilesetOrigin::Center => center,
+                    TilesetOrigin::Source(point) => *point,
+                    TilesetOrigin::Ecef(point) => {
+                        Projector::new(SpatialReferenceIdentifier::Epsg4978, source_srs)?
+                            .convert_point(*point)?
+                    }
+                };
+                projector.convert_isometry(Isometry3::from_parts(
+                    source_origin.into(),
+                    UnitQuaternion::default(),
+                ))?
+            }
+            Self::EastNorthUp(origin) => {
+                let ecef_origin = match origin {
+                    TilesetOrigin::Center => projector.convert_point(center)?,
+                    TilesetOrigin::Source(point) => projector.convert_point(*point)?,
+                    TilesetOrigin::Ecef(point) => *point,
+                };
+                Isometry3::from_parts(
+                    Translation3::from(ecef_origin.coords),
+                    derive_east_north_up_rotation(&ecef_origin),
+                )
+            }
+            Self::Ecef => Isometry3::identity(),
+        };
+
+        Ok(root_transform)
+    }
+
+    /// Fails if the octree cannot be aligned in the frame, which is the case for principal axes
+    /// in ECEF, as a rotation about the polar axis does not keep the horizontal axes horizontal.
+    pub(crate) fn check_alignment(&self, tileset_alignment: TilesetAlignment) -> Result<(), Error> {
+        if *self == Self::Ecef && tileset_alignment == TilesetAlignment::PrincipalAxes {
+            return Err(UnsupportedFrameAlignment());
+        }
+
+        Ok(())
+    }
+}
+
+/// Alignment of the horizontal axes in which the octree of a tileset is built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TilesetAlignment {
+    /// Axes of the tileset frame.
+    #[default]
+    FrameAxes,
+    /// Principal axes of the horizontal point distribution, rotated about the z-axis of the
+    /// tileset frame, so that elongated diagonal extents, e.g. of roads, railways or pipelines,
+    /// leave fewer octants empty. Not supported in the [`TilesetFrame::Ecef`] frame.
+    PrincipalAxes,
+}
+
+impl TilesetAlignment {
+    /// Rotates the vertices given in the tileset frame into the aligned frame and returns the
+    /// rotation from the aligned frame to the tileset frame, which is folded into the root
+    /// transform.
+    pub(crate) fn align_vertices(&self, vertices: &mut [Vertex]) -> UnitQuaternion<f64> {
+        let rotation = match self {
+            Self::FrameAxes => return UnitQuaternion::identity(),
+            Self::PrincipalAxes => derive_principal_axes_rotation(vertices),
+        };
+
+        let inverse_rotation = rotation.inverse();
+        for vertex in vertices.iter_mut() {
+            vertex.position = inverse_rotation * vertex.position;
+            if let Some(normal) = vertex.normal.as_mut() {
+                *normal = (inverse_rotation * normal.cast::<f64>()).cast::<f32>();
+            }
+        }
+        rotation
+    }
+}
+
+/// Placement of a point cloud in engineering or local coordinates without a CRS, which defines
+/// the root transform of its tileset.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LocalPlacement {
+    /// Local coordinates taken as ECEF coordinates, e.g. for viewers without a globe. The
+    /// tileset has no heights, as its z-axis is not up on the globe.
+    #[default]
+    Identity,
+    /// Places the local origin at the geographic position on the WGS 84 ellipsoid, with the
+    /// local x, y and z axes along east, north and up rotated by the heading.
+    Geographic {
+        /// Longitude in degrees.
+        longitude: f64,
+        /// Latitude in degrees.
+        latitude: f64,
+        /// Ellipsoidal height in meters.
+        height: f64,
+        /// Clockwise rotation of the local y-axis from north in degrees.
+        heading: f64,
+    },
+}
+
+impl LocalPlacement {
+    /// Derives the transform from the local coordinates to ECEF.
+    pub(crate) fn derive_transform(&self) -> Isometry3<f64> {
+        match *self {
+            Self::Identity => Isometry3::identity(),
+            Self::Geographic {
+                longitude,
+                latitude,
+                height,
+                heading,
+            } => {
+                let position = derive_ecef_position(longitude, latitude, height);
+                let heading_rotation =
+                    UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -heading.to_radians());
+                Isometry3::from_parts(
+                    Translation3::from(position.coords),
+                    derive_east_north_up_rotation(&position) * heading_rotation,
+                )
+            }
+        }
+    }
+}
+
+/// Derives the rotation of normals given in the axes of the source CRS at `center` into the
+/// local frame of the root transform.
+pub(crate) fn derive_normal_rotation(
+    source_srs: SpatialReferenceIdentifier,
+    center: Point3<f64>,
+    root_transform: &Isometry3<f64>,
+) -> Result<UnitQuaternion<f64>, Error> {
+    let projector = Projector::new(source_srs, SpatialReferenceIdentifier::Epsg4978)?;
+    let source_axes_transform = projector.convert_isometry(Isometry3::from_parts(
+        center.into(),
+        UnitQuaternion::default(),
+    ))?;
+
+    Ok(root_transform.rotation.inverse() * source_axes_transform.rotation)
+}
+
+/// Derives the rotation from the east, north and up axes at the ECEF position to ECEF, with up
+/// along the normal of the WGS 84 ellipsoid.
+pub(crate) fn derive_east_north_up_rotation(position: &Point3<f64>) -> UnitQuaternion<f64> {
+    let semi_minor_axis = WGS84_SEMI_MAJOR_AXIS * (1.0 - WGS84_FLATTENING);
+    let up = Vector3::new(
+        position.x / WGS84_SEMI_MAJOR_AXIS.powi(2),
+        position.y / WGS84_SEMI_MAJOR_AXIS.powi(2),
+        position.z / semi_minor_axis.powi(2),
+    )
+    .try_normalize(f64::EPSILON)
+    .unwrap_or_else(Vector3::z);
+    // at the poles, east is undefined and chosen along the x-axis
+    let east = Vector3::new(-position.y, position.x, 0.0)
+        .try_normalize(f64::EPSILON)
+        .unwrap_or_else(Vector3::x);
+    let north = up.cross(&east);
+
+    UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(Matrix3::from_columns(
+        &[east, north, up],
+    )))
+}
+
+/// Derives the rotation about the z-axis that maps the x-axis onto the major principal axis of
+/// the horizontal vertex positions.
+fn derive_principal_axes_rotation(vertices: &[Vertex]) -> UnitQuaternion<f64> {
+    if vertices.is_empty() {
+        return UnitQuaternion::identity();
+    }
+    let vertex_count = vertices.len() as f64;
+    let mean = vertices
+        .iter()
+        .fold(Vector3::zeros(), |sum, v| sum + v.position.coords)
+        / vertex_count;
+    let (variance_x, variance_y, covariance_xy) =
+        vertices.iter().fold((0.0, 0.0, 0.0), |(xx, yy, xy), v| {
+            let offset = v.position.coords - mean;
+            (
+                xx + offset.x * offset.x,
+                yy + offset.y * offset.y,
+                xy + offset.x * offset.y,
+            )
+        });
+    let angle = 0.5 * f64::atan2(2.0 * covariance_xy, variance_x - variance_y);
+
+    UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle)
+}
+
+/// Converts geodetic coordinates in degrees and meters on the WGS 84 ellipsoid to ECEF.
+fn derive_ecef_position(longitude: f64, latitude: f64, height: f64) -> Point3<f64> {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    let eccentricity_squared = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let prime_vertical_radius =
+        WGS84_SEMI_MAJOR_AXIS / (1.0 - eccentricity_squared * latitude.sin().powi(2)).sqrt();
+
+    Point3::new(
+        (prime_vertical_radius + height) * latitude.cos() * longitude.cos(),
+        (prime_vertical_radius + height) * latitude.cos() * longitude.sin(),
+        (prime_vertical_radius * (1.0 - eccentricity_squared) + height) * latitude.sin(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressReporter;
+    use crate::testing::derive_point_cloud;
+    use crate::tileset::Tileset;
+    use palette::Srgb;
+    use std::f64::consts::FRAC_PI_4;
+
+    /// Longitude and latitude in degrees and height in meters on the equator, at the north pole
+    /// and at mid-latitude.
+    const GEODETIC_POSITIONS: [(f64, f64, f64); 3] =
+        [(0.0, 0.0, 0.0), (0.0, 90.0, 100.0), (11.575, 48.137, 520.0)];
+
+    /// Derives the positions of a horizontal strip 1000 m long and 1 m wide along the direction,
+    /// e.g. of a road, centered at the origin.
+    fn derive_strip_positions(direction: Vector3<f64>) -> Vec<Point3<f64>> {
+        let across = Vector3::new(-direction.y, direction.x, 0.0);
+        (-1000..=1000)
+            .flat_map(|i| (-1..=1).map(move |j| (i, j)))
+            .map(|(i, j)| Point3::from(direction * (i as f64 * 0.5) + across * (j as f64 * 0.5)))
+            .collect()
+    }
+
+    fn derive_vertices(positions: &[Point3<f64>]) -> Vec<Vertex> {
+        positions
+            .iter()
+            .map(|p| Vertex {
+                position: *p,
+                color: Srgb::new(0.0, 0.0, 0.0),
+                normal: Some(Vector3::new(0.6, 0.0, 0.8)),
+                classification: None,
+                time: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ecef_position_matches_projection() {
+        let projector = Projector::new(
+            SpatialReferenceIdentifier::Epsg4979,
+            SpatialReferenceIdentifier::Epsg4978,
+        )
+        .expect("projector");
+
+        for (longitude, latitude, height) in GEODETIC_POSITIONS {
+            let position = derive_ecef_position(longitude, latitude, height);
+            let expected_position = projector
+                .convert_point(Point3::new(longitude, latitude, height))
+                .expect("ECEF position");
+
+            assert!(
+                (position - expected_position).norm() < 1e-6,
+                "{position} differs from {expected_position} at ({longitude}, {latitude}, {height})"
+            );
+        }
+        assert_eq!(
+            derive_ecef_position(0.0, 0.0, 0.0),
+            Point3::new(WGS84_SEMI_MAJOR_AXIS, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_up_is_orthogonal_to_east_and_north() {
+        let projector = Projector::new(
+            SpatialReferenceIdentifier::Epsg4978,
+            SpatialReferenceIdentifier::Epsg4979,
+        )
+        .expect("projector");
+
+        for (longitude, latitude, height) in GEODETIC_POSITIONS {
+            let position = derive_ecef_position(longitude, latitude, height);
+            let rotation = derive_east_north_up_rotation(&position);
+            let (east, north, up) = (
+                rotation * Vector3::x(),
+                rotation * Vector3::y(),
+                rotation * Vector3::z(),
+            );
+
+            assert!(up.dot(&east).abs() < 1e-12);
+            assert!(up.dot(&north).abs() < 1e-12);
+            assert!(east.dot(&north).abs() < 1e-12);
+            // a step along up only changes the ellipsoidal height
+            let raised_position = projector
+                .convert_point(position + up * 10.0)
+                .expect("geodetic position");
+            assert!((raised_position.z - (height + 10.0)).abs() < 1e-6);
+            if latitude < 90.0 {
+                assert!((raised_position.x - longitude).abs() < 1e-9);
+            }
+            assert!((raised_position.y - latitude).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_heading_turns_local_y_axis_clockwise_from_north() {
+        let (longitude, latitude, height) = GEODETIC_POSITIONS[2];
+        let derive_transform = |heading: f64| {
+            LocalPlacement::Geographic {
+                longitude,
+                latitude,
+                height,
+                heading,
+            }
+            .derive_transform()
+        };
+        let position = derive_ecef_position(longitude, latitude, height);
+        let east_north_up_rotation = derive_east_north_up_rotation(&position);
+        let (east, north) = (
+            east_north_up_rotation * Vector3::x(),
+            east_north_up_rotation * Vector3::y(),
+        );
+
+        let transform = derive_transform(0.0);
+        assert!((transform.translation.vector - position.coords).norm() < 1e-9);
+        assert!((transform.rotation * Vector3::y() - north).norm() < 1e-12);
+        assert!((transform.rotation * Vector3::x() - east).norm() < 1e-12);
+
+        let y_axis = derive_transform(90.0).rotation * Vector3::y();
+        assert!((y_axis - east).norm() < 1e-12);
+        let y_axis = derive_transform(45.0).rotation * Vector3::y();
+        assert!((y_axis - (north + east).normalize()).norm() < 1e-12);
+        let y_axis = derive_transform(-90.0).rotation * Vector3::y();
+        assert!((y_axis + east).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_principal_axes_rotation_of_diagonal_strips() {
+        for angle in [FRAC_PI_4, -FRAC_PI_4] {
+            let direction = Vector3::new(angle.cos(), angle.sin(), 0.0);
+            let vertices = derive_vertices(&derive_strip_positions(direction));
+
+            let rotation = derive_principal_axes_rotation(&vertices);
+
+            assert!((rotation * Vector3::x() - direction).norm() < 1e-6);
+            assert!((rotation * Vector3::z() - Vector3::z()).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_principal_axes_leave_fewer_octants_of_diagonal_strip_occupied() {
+        let positions = derive_strip_positions(Vector3::new(1.0, 1.0, 0.0).normalize());
+        let derive_occupied_octant_count = |tileset_alignment: TilesetAlignment| {
+            let tileset = Tileset::from_local_point_cloud(
+                derive_point_cloud(positions.clone()),
+                LocalPlacement::Identity,
+                tileset_alignment,
+                100,
+                Some(42),
+                &ProgressReporter::default(),
+            )
+            .expect("tileset");
+            tileset
+                .tiled_content
+                .cells()
+                .values()
+                .filter(|c| !c.is_empty())
+                .count()
+        };
+
+        let frame_axes_count = derive_occupied_octant_count(TilesetAlignment::FrameAxes);
+        let principal_axes_count = derive_occupied_octant_count(TilesetAlignment::PrincipalAxes);
+
+        assert!(
+            principal_axes_count < frame_axes_count,
+            "{principal_axes_count} octants with principal axes, {frame_axes_count} with frame axes"
+        );
+    }
+
+    #[test]
+    fn test_principal_axes_in_ecef_frame_are_rejected() {
+        let positions = derive_strip_positions(Vector3::new(1.0, 1.0, 0.0).normalize());
+
+        let result = Tileset::from_point_cloud_with_frame(
+            derive_point_cloud(positions),
+            SpatialReferenceIdentifier::Epsg4978,
+            TilesetFrame::Ecef,
+            TilesetAlignment::PrincipalAxes,
+            100,
+            Some(42),
+            &ProgressReporter::default(),
+        );
+
+        assert!(matches!(result, Err(UnsupportedFrameAlignment())));
+    }
+
+    #[test]
+    fn test_aligned_vertices_map_to_same_ecef_positions_through_root_transform() {
+        let root_transform = LocalPlacement::Geographic {
+            longitude: 11.575,
+            latitude: 48.137,
+            height: 520.0,
+            heading: 30.0,
+        }
+        .derive_transform();
+        let mut vertices =
+            derive_vertices(&derive_strip_positions(Vector3::new(3.0, 4.0, 0.0) / 5.0));
+        let ecef_positions: Vec<Point3<f64>> = vertices
+            .iter()
+            .map(|v| root_transform * v.position)
+            .collect();
+        let ecef_normal = root_transform.rotation * Vector3::new(0.6, 0.0, 0.8);
+
+        let rotation = TilesetAlignment::PrincipalAxes.align_vertices(&mut vertices);
+        let root_transform = root_transform * rotation;
+
+        assert!(rotation.angle() > 0.1);
+        for (vertex, ecef_position) in vertices.iter().zip(&ecef_positions) {
+            assert!((root_transform * vertex.position - ecef_position).norm() < 1e-6);
+            let normal = vertex.normal.expect("normal").cast::<f64>();
+            assert!((root_transform.rotation * normal - ecef_normal).norm() < 1e-6);
+        }
+    }
+}
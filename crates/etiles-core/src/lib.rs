@@ -1,7 +1,22 @@
 mod bounding_volume;
+mod colorize;
+mod column;
+mod downsample;
 mod error;
+mod extract;
+mod filter;
+mod frame;
+mod normal;
+mod outlier;
+mod polygon;
+mod progress;
+mod raster;
 mod reproject;
+mod statistics;
+#[cfg(test)]
+mod testing;
 mod tileset;
+mod tileset_builder;
 
 #[doc(inline)]
 pub use error::Error;
@@ -12,11 +27,142 @@ pub use bounding_volume::BoundingCube;
 #[doc(inline)]
 pub use bounding_volume::BoundingRegion;
 
+#[doc(inline)]
+pub use colorize::ColorGradient;
+
+#[doc(inline)]
+pub use colorize::ColorMode;
+
+#[doc(inline)]
+pub use colorize::colorize_point_cloud;
+
+#[doc(inline)]
+pub use colorize::set_all_colors;
+
+#[doc(inline)]
+pub use colorize::get_classification_color;
+
+#[doc(inline)]
+pub use colorize::get_return_number_color;
+
+#[doc(inline)]
+pub use downsample::downsample_voxel_grid;
+
+#[doc(inline)]
+pub use extract::LevelSelection;
+
+#[doc(inline)]
+pub use extract::extract_local_point_cloud;
+
+#[doc(inline)]
+pub use extract::extract_point_cloud;
+
+#[doc(inline)]
+pub use filter::PointFilter;
+
+#[doc(inline)]
+pub use filter::filter_point_cloud;
+
+#[doc(inline)]
+pub use filter::get_all_classifications;
+
+#[doc(inline)]
+pub use frame::LocalPlacement;
+
+#[doc(inline)]
+pub use frame::TilesetAlignment;
+
+#[doc(inline)]
+pub use frame::TilesetFrame;
+
+#[doc(inline)]
+pub use frame::TilesetOrigin;
+
+#[doc(inline)]
+pub use normal::NormalEstimation;
+
+#[doc(inline)]
+pub use normal::NormalOrientation;
+
+#[doc(inline)]
+pub use normal::estimate_normals;
+
+#[doc(inline)]
+pub use normal::get_all_normals;
+
+#[doc(inline)]
+pub use normal::has_normals;
+
+#[doc(inline)]
+pub use outlier::StatisticalOutlierRemoval;
+
+#[doc(inline)]
+pub use outlier::RadiusOutlierRemoval;
+
+#[doc(inline)]
+pub use outlier::remove_statistical_outliers;
+
+#[doc(inline)]
+pub use outlier::remove_radius_outliers;
+
+#[doc(inline)]
+pub use polygon::ClipPolygon;
+
+#[doc(inline)]
+pub use progress::CancellationToken;
+
+#[doc(inline)]
+pub use progress::PhaseProgress;
+
+#[doc(inline)]
+pub use progress::Progress;
+
+#[doc(inline)]
+pub use progress::ProgressObserver;
+
+#[doc(inline)]
+pub use progress::ProgressPhase;
+
+#[doc(inline)]
+pub use progress::ProgressReporter;
+
+#[doc(inline)]
+pub use raster::GeoRaster;
+
+#[doc(inline)]
+pub use raster::RasterSampling;
+
+#[doc(inline)]
+pub use raster::colorize_point_cloud_from_rasters;
+
 #[doc(inline)]
 pub use reproject::reproject_point_cloud;
 
+#[doc(inline)]
+pub use reproject::reproject_point_cloud_with_progress;
+
+#[doc(inline)]
+pub use statistics::OctantStatistics;
+
 #[doc(inline)]
 pub use tileset::Tileset;
 
 #[doc(inline)]
 pub use tileset::Vertex;
+
+#[doc(inline)]
+pub use tileset_builder::TilesetBuilder;
+
+#[doc(inline)]
+pub use tileset_builder::VertexSchema;
+
+/// Name of the optional point data column holding the ASPRS classification code of each point.
+pub const COLUMN_NAME_CLASSIFICATION_STR: &str = "classification";
+
+/// Name of the optional point data column holding the return number of each point.
+pub const COLUMN_NAME_RETURN_NUMBER_STR: &str = "return_number";
+
+/// Names of the optional point data columns holding the normal vector of each point.
+pub const COLUMN_NAME_NORMAL_X_STR: &str = "normal_x";
+pub const COLUMN_NAME_NORMAL_Y_STR: &str = "normal_y";
+pub const COLUMN_NAME_NORMAL_Z_STR: &str = "normal_z";
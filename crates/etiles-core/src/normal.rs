@@ -0,0 +1,219 @@
+use crate::column::get_all_f64_values;
+use crate::error::Error;
+use crate::error::Error::ParameterTooSmall;
+use crate::{COLUMN_NAME_NORMAL_X_STR, COLUMN_NAME_NORMAL_Y_STR, COLUMN_NAME_NORMAL_Z_STR};
+use epoint::PointCloud;
+use kiddo::{ImmutableKdTree, SquaredEuclidean};
+use nalgebra::{Matrix3, Point3, Vector3};
+use rayon::prelude::*;
+use std::num::NonZero;
+
+/// Parameters of the normal estimation by principal component analysis.
+///
+/// The normal of a point is the direction of least variance among its `neighbor_count` nearest
+/// neighbors, oriented according to `orientation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalEstimation {
+    pub neighbor_count: usize,
+    pub orientation: NormalOrientation,
+}
+
+/// Resolves the sign ambiguity of estimated normals.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NormalOrientation {
+    /// Normals point towards positive z (in source CRS).
+    #[default]
+    Up,
+    /// Normals point towards the position, e.g. of a stationary scanner (in source CRS).
+    TowardsPosition(Point3<f64>),
+}
+
+/// Estimates a normal for each point and writes it into the normal columns, replacing
+/// existing ones.
+pub fn estimate_normals(
+    mut point_cloud: PointCloud,
+    parameters: &NormalEstimation,
+) -> Result<PointCloud, Error> {
+    if parameters.neighbor_count < 3 {
+        return Err(ParameterTooSmall("neighbor_count", 3));
+    }
+    let query_count =
+        NonZero::new(parameters.neighbor_count).ok_or(ParameterTooSmall("neighbor_count", 3))?;
+
+    let all_points: Vec<[f64; 3]> = point_cloud
+        .point_data
+        .get_all_points()
+        .into_iter()
+        .map(|p| [p.x, p.y, p.z])
+        .collect();
+    let kd_tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&all_points);
+
+    let normals: Vec<Vector3<f64>> = all_points
+        .par_iter()
+        .map(|p| {
+            let neighbors: Vec<Vector3<f64>> = kd_tree
+                .nearest_n::<SquaredEuclidean>(p, query_count)
+                .iter()
+                .map(|n| Vector3::from(all_points[n.item as usize]))
+                .collect();
+            let normal = derive_pca_normal(&neighbors);
+
+            let point = Point3::from(*p);
+            let reference_direction = match parameters.orientation {
+                NormalOrientation::Up => Vector3::z(),
+                NormalOrientation::TowardsPosition(position) => position - point,
+            };
+            if normal.dot(&reference_direction) < 0.0 {
+                -normal
+            } else {
+                normal
+            }
+        })
+        .collect();
+
+    let point_data = &mut point_cloud.point_data;
+    point_data.add_f32_column(
+        COLUMN_NAME_NORMAL_X_STR,
+        normals.iter().map(|n| n.x as f32).collect(),
+    )?;
+    point_data.add_f32_column(
+        COLUMN_NAME_NORMAL_Y_STR,
+        normals.iter().map(|n| n.y as f32).collect(),
+    )?;
+    point_data.add_f32_column(
+        COLUMN_NAME_NORMAL_Z_STR,
+        normals.iter().map(|n| n.z as f32).collect(),
+    )?;
+
+    Ok(point_cloud)
+}
+
+/// Returns the unit eigenvector of the smallest eigenvalue of the neighborhood covariance.
+fn derive_pca_normal(neighbors: &[Vector3<f64>]) -> Vector3<f64> {
+    let centroid = neighbors.iter().sum::<Vector3<f64>>() / neighbors.len() as f64;
+    let covariance = neighbors
+        .iter()
+        .map(|n| {
+            let d = n - centroid;
+            d * d.transpose()
+        })
+        .sum::<Matrix3<f64>>();
+
+    let eigen = covariance.symmetric_eigen();
+    let smallest_index = eigen.eigenvalues.imin();
+    let normal = eigen.eigenvectors.column(smallest_index).into_owned();
+
+    normal.try_normalize(f64::EPSILON).unwrap_or(Vector3::z())
+}
+
+/// Returns the normal of each point, if the point data contains the normal columns.
+pub fn get_all_normals(point_cloud: &PointCloud) -> Result<Vec<Vector3<f32>>, Error> {
+    let x = get_all_f64_values(point_cloud, COLUMN_NAME_NORMAL_X_STR)?;
+    let y = get_all_f64_values(point_cloud, COLUMN_NAME_NORMAL_Y_STR)?;
+    let z = get_all_f64_values(point_cloud, COLUMN_NAME_NORMAL_Z_STR)?;
+
+    let normals = x
+        .into_par_iter()
+        .zip(y)
+        .zip(z)
+        .map(|((x, y), z)| {
+            Vector3::new(x as f32, y as f32, z as f32)
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(Vector3::z())
+        })
+        .collect();
+
+    Ok(normals)
+}
+
+/// Returns true if the point data contains the normal columns.
+pub fn has_normals(point_cloud: &PointCloud) -> bool {
+    let column_names = point_cloud.point_data.data_frame.get_column_names();
+    [
+        COLUMN_NAME_NORMAL_X_STR,
+        COLUMN_NAME_NORMAL_Y_STR,
+        COLUMN_NAME_NORMAL_Z_STR,
+    ]
+    .iter()
+    .all(|name| column_names.iter().any(|c| c.as_str() == *name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::derive_point_cloud;
+
+    /// Points on a 6×6 grid in the plane through the origin with the normal.
+    fn derive_plane_points(normal: &Vector3<f64>) -> Vec<Point3<f64>> {
+        (0..36)
+            .map(|i| {
+                let (x, y) = ((i % 6) as f64, (i / 6) as f64);
+                Point3::new(x, y, -(normal.x * x + normal.y * y) / normal.z)
+            })
+            .collect()
+    }
+
+    fn estimate_all_normals(
+        points: Vec<Point3<f64>>,
+        orientation: NormalOrientation,
+    ) -> Vec<Vector3<f32>> {
+        let point_cloud = estimate_normals(
+            derive_point_cloud(points),
+            &NormalEstimation {
+                neighbor_count: 8,
+                orientation,
+            },
+        )
+        .expect("point cloud");
+        get_all_normals(&point_cloud).expect("normals")
+    }
+
+    #[test]
+    fn test_normals_of_planar_patch_equal_plane_normal() {
+        let plane_normal = Vector3::new(-0.5, -0.25, 1.0).normalize();
+
+        let normals =
+            estimate_all_normals(derive_plane_points(&plane_normal), NormalOrientation::Up);
+
+        assert_eq!(normals.len(), 36);
+        assert!(
+            normals
+                .iter()
+                .all(|n| (n.cast::<f64>() - plane_normal).norm() < 1e-5)
+        );
+    }
+
+    #[test]
+    fn test_normals_face_towards_position() {
+        let points = derive_plane_points(&Vector3::z());
+
+        let up_normals = estimate_all_normals(points.clone(), NormalOrientation::Up);
+        let below_normals = estimate_all_normals(
+            points,
+            NormalOrientation::TowardsPosition(Point3::new(2.5, 2.5, -10.0)),
+        );
+
+        assert!(up_normals.iter().all(|n| (n - Vector3::z()).norm() < 1e-5));
+        assert!(
+            below_normals
+                .iter()
+                .all(|n| (n + Vector3::z()).norm() < 1e-5)
+        );
+    }
+
+    #[test]
+    fn test_too_few_neighbors_are_rejected() {
+        let result = estimate_normals(
+            derive_point_cloud(derive_plane_points(&Vector3::z())),
+            &NormalEstimation {
+                neighbor_count: 2,
+                orientation: NormalOrientation::Up,
+            },
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParameterTooSmall("neighbor_count", 3))
+        ));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::error::Error;
+use crate::error::Error::NonPositiveParameter;
+use crate::filter::filter_by_mask;
+use epoint::PointCloud;
+use kiddo::{ImmutableKdTree, SquaredEuclidean};
+use rayon::prelude::*;
+use std::num::NonZero;
+use tracing::info;
+
+/// Parameters of the statistical outlier removal.
+///
+/// A point is an outlier if the mean distance to its `neighbor_count` nearest neighbors exceeds
+/// the global mean of these distances by more than `standard_deviation_multiplier` standard
+/// deviations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatisticalOutlierRemoval {
+    pub neighbor_count: usize,
+    pub standard_deviation_multiplier: f64,
+}
+
+/// Parameters of the radius outlier removal.
+///
+/// A point is an outlier if fewer than `minimum_neighbor_count` other points lie within `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadiusOutlierRemoval {
+    pub radius: f64,
+    pub minimum_neighbor_count: usize,
+}
+
+pub fn remove_statistical_outliers(
+    point_cloud: PointCloud,
+    parameters: &StatisticalOutlierRemoval,
+) -> Result<PointCloud, Error> {
+    if parameters.neighbor_count == 0 {
+        return Err(NonPositiveParameter("neighbor_count"));
+    }
+    // the query point itself is always returned as its own nearest neighbor
+    let query_count = NonZero::<usize>::MIN.saturating_add(parameters.neighbor_count);
+
+    let all_points: Vec<[f64; 3]> = point_cloud
+        .point_data
+        .get_all_points()
+        .into_iter()
+        .map(|p| [p.x, p.y, p.z])
+        .collect();
+    let kd_tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&all_points);
+
+    let mean_distances: Vec<f64> = all_points
+        .par_iter()
+        .map(|p| {
+            let neighbors = kd_tree.nearest_n::<SquaredEuclidean>(p, query_count);
+            let neighbor_distance_sum: f64 =
+                neighbors.iter().skip(1).map(|n| n.distance.sqrt()).sum();
+            neighbor_distance_sum / (neighbors.len().saturating_sub(1).max(1) as f64)
+        })
+        .collect();
+
+    let point_count = mean_distances.len() as f64;
+    let mean = mean_distances.par_iter().sum::<f64>() / point_count;
+    let variance = mean_distances
+        .par_iter()
+        .map(|d| (d - mean).powi(2))
+        .sum::<f64>()
+        / point_count;
+    let distance_threshold = mean + parameters.standard_deviation_multiplier * variance.sqrt();
+
+    let mask: Vec<bool> = mean_distances
+        .par_iter()
+        .map(|d| *d <= distance_threshold)
+        .collect();
+    log_removed_points("statistical outliers", &mask);
+
+    filter_by_mask(point_cloud, mask)
+}
+
+pub fn remove_radius_outliers(
+    point_cloud: PointCloud,
+    parameters: &RadiusOutlierRemoval,
+) -> Result<PointCloud, Error> {
+    if parameters.radius <= 0.0 {
+        return Err(NonPositiveParameter("radius"));
+    }
+
+    let all_points: Vec<[f64; 3]> = point_cloud
+        .point_data
+        .get_all_points()
+        .into_iter()
+        .map(|p| [p.x, p.y, p.z])
+        .collect();
+    let kd_tree: ImmutableKdTree<f64, 3> = ImmutableKdTree::new_from_slice(&all_points);
+    let squared_radius = parameters.radius * parameters.radius;
+
+    let mask: Vec<bool> = all_points
+        .par_iter()
+        .map(|p| {
+            // the query point itself is contained in the result
+            let neighbor_count = kd_tree
+                .within_unsorted::<SquaredEuclidean>(p, squared_radius)
+                .len()
+                .saturating_sub(1);
+            neighbor_count >= parameters.minimum_neighbor_count
+        })
+        .collect();
+    log_removed_points("radius outliers", &mask);
+
+    filter_by_mask(point_cloud, mask)
+}
+
+fn log_removed_points(reason: &str, mask: &[bool]) {
+    let removed_point_count = mask.iter().filter(|x| !**x).count();
+    info!(
+        "Removed {} of {} points as {}",
+        removed_point_count,
+        mask.len(),
+        reason
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::derive_point_cloud;
+    use nalgebra::Point3;
+
+    const OUTLIER: Point3<f64> = Point3::new(2.0, 2.0, 20.0);
+
+    /// Points on a flat 5×5 grid with a spacing of 1 and a single point far above it.
+    fn derive_points_with_outlier() -> Vec<Point3<f64>> {
+        (0..25)
+            .map(|i| Point3::new((i % 5) as f64, (i / 5) as f64, 0.0))
+            .chain([OUTLIER])
+            .collect()
+    }
+
+    #[test]
+    fn test_statistical_outlier_removal_removes_only_outlier() {
+        let point_cloud = remove_statistical_outliers(
+            derive_point_cloud(derive_points_with_outlier()),
+            &StatisticalOutlierRemoval {
+                neighbor_count: 4,
+                standard_deviation_multiplier: 1.0,
+            },
+        )
+        .expect("point cloud");
+
+        let points = point_cloud.point_data.get_all_points();
+        assert_eq!(points.len(), 25);
+        assert!(!points.contains(&OUTLIER));
+    }
+
+    #[test]
+    fn test_radius_outlier_removal_removes_only_outlier() {
+        let point_cloud = remove_radius_outliers(
+            derive_point_cloud(derive_points_with_outlier()),
+            &RadiusOutlierRemoval {
+                radius: 1.5,
+                minimum_neighbor_count: 2,
+            },
+        )
+        .expect("point cloud");
+
+        let points = point_cloud.point_data.get_all_points();
+        assert_eq!(points.len(), 25);
+        assert!(!points.contains(&OUTLIER));
+    }
+
+    #[test]
+    fn test_outlier_removal_rejects_non_positive_parameters() {
+        let statistical_result = remove_statistical_outliers(
+            derive_point_cloud(derive_points_with_outlier()),
+            &StatisticalOutlierRemoval {
+                neighbor_count: 0,
+                standard_deviation_multiplier: 1.0,
+            },
+        );
+        let radius_result = remove_radius_outliers(
+            derive_point_cloud(derive_points_with_outlier()),
+            &RadiusOutlierRemoval {
+                radius: 0.0,
+                minimum_neighbor_count: 2,
+            },
+        );
+
+        assert!(matches!(
+            statistical_result,
+            Err(NonPositiveParameter("neighbor_count"))
+        ));
+        assert!(matches!(radius_result, Err(NonPositiveParameter("radius"))));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::error::Error;
+use crate::error::Error::{InvalidFileExtension, InvalidWkt, UnsupportedGeometryType};
+use geo_types::{Geometry, LineString, MultiPolygon, Polygon};
+use nalgebra::Point2;
+use std::fs;
+use std::path::Path;
+use wkt::TryFromWkt;
+
+pub const FILE_EXTENSION_GEOJSON_FORMAT: &str = "geojson";
+pub const FILE_EXTENSION_JSON_FORMAT: &str = "json";
+pub const FILE_EXTENSION_WKT_FORMAT: &str = "wkt";
+
+/// Horizontal clip polygon, possibly with holes and multiple parts, defined in the source CRS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipPolygon {
+    polygons: MultiPolygon<f64>,
+}
+
+impl ClipPolygon {
+    pub fn new(polygons: MultiPolygon<f64>) -> Self {
+        Self { polygons }
+    }
+
+    /// Creates a rectangular clip polygon from the minimum and maximum horizontal corner.
+    pub fn from_bounds(min: Point2<f64>, max: Point2<f64>) -> Self {
+        let rect = geo_types::Rect::new((min.x, min.y), (max.x, max.y));
+        Self::new(MultiPolygon::new(vec![rect.to_polygon()]))
+    }
+
+    /// Reads a clip polygon from a GeoJSON (`.geojson`, `.json`) or WKT (`.wkt`) file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let content = fs::read_to_string(path)?;
+
+        match extension.as_str() {
+            FILE_EXTENSION_GEOJSON_FORMAT | FILE_EXTENSION_JSON_FORMAT => {
+                Self::from_geojson_str(&content)
+            }
+            FILE_EXTENSION_WKT_FORMAT => Self::from_wkt_str(&content),
+            _ => Err(InvalidFileExtension(extension)),
+        }
+    }
+
+    /// Parses a GeoJSON geometry, feature or feature collection containing polygons.
+    pub fn from_geojson_str(geojson_str: &str) -> Result<Self, Error> {
+        let geojson: geojson::GeoJson = geojson_str.parse()?;
+        let geometry: Geometry<f64> = geojson.try_into()?;
+
+        Ok(Self::new(collect_polygons(geometry)?))
+    }
+
+    /// Parses a WKT `POLYGON`, `MULTIPOLYGON` or `GEOMETRYCOLLECTION` of polygons.
+    pub fn from_wkt_str(wkt_str: &str) -> Result<Self, Error> {
+        let geometry = Geometry::<f64>::try_from_wkt_str(wkt_str.trim())
+            .map_err(|e| InvalidWkt(e.to_string()))?;
+
+        Ok(Self::new(collect_polygons(geometry)?))
+    }
+
+    pub fn polygons(&self) -> &MultiPolygon<f64> {
+        &self.polygons
+    }
+
+    /// Returns true if the point lies within one of the polygons, but not within one of its holes.
+    pub fn contains_point(&self, point: &Point2<f64>) -> bool {
+        self.polygons
+            .iter()
+            .any(|p| polygon_contains_point(p, point))
+    }
+}
+
+fn collect_polygons(geometry: Geometry<f64>) -> Result<MultiPolygon<f64>, Error> {
+    let polygons: Vec<Polygon<f64>> = match geometry {
+        Geometry::Polygon(polygon) => vec![polygon],
+        Geometry::MultiPolygon(multi_polygon) => multi_polygon.0,
+        Geometry::Rect(rect) => vec![rect.to_polygon()],
+        Geometry::GeometryCollection(collection) => {
+            let mut polygons = Vec::new();
+            for current_geometry in collection {
+                polygons.extend(collect_polygons(current_geometry)?);
+            }
+            polygons
+        }
+        Geometry::Point(_) => return Err(UnsupportedGeometryType("Point")),
+        Geometry::Line(_) => return Err(UnsupportedGeometryType("Line")),
+        Geometry::LineString(_) => return Err(UnsupportedGeometryType("LineString")),
+        Geometry::MultiPoint(_) => return Err(UnsupportedGeometryType("MultiPoint")),
+        Geometry::MultiLineString(_) => return Err(UnsupportedGeometryType("MultiLineString")),
+        Geometry::Triangle(triangle) => vec![triangle.to_polygon()],
+    };
+
+    Ok(MultiPolygon::new(polygons))
+}
+
+fn polygon_contains_point(polygon: &Polygon<f64>, point: &Point2<f64>) -> bool {
+    ring_contains_point(polygon.exterior(), point)
+        && !polygon
+            .interiors()
+            .iter()
+            .any(|r| ring_contains_point(r, point))
+}
+
+/// Even-odd ray casting test for a closed ring.
+///
+/// Edges are half-open, so that a point on the boundary of a rectangle is inside on its left and
+/// lower edges, but outside on its right and upper edges, and a point on the shared edge of
+/// adjacent polygons lies in exactly one of them.
+fn ring_contains_point(ring: &LineString<f64>, point: &Point2<f64>) -> bool {
+    let mut inside = false;
+    for current_line in ring.lines() {
+        let (start, end) = (current_line.start, current_line.end);
+        if (start.y > point.y) != (end.y > point.y) {
+            let intersection_x =
+                start.x + (point.y - start.y) / (end.y - start.y) * (end.x - start.x);
+            if point.x < intersection_x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::polygon;
+
+    fn derive_square(min: f64, max: f64) -> LineString<f64> {
+        LineString::from(vec![
+            (min, min),
+            (max, min),
+            (max, max),
+            (min, max),
+            (min, min),
+        ])
+    }
+
+    #[test]
+    fn test_concave_polygon_excludes_notch() {
+        // U-shape open to the top with the notch between x = 1 and x = 2
+        let clip_polygon = ClipPolygon::new(MultiPolygon::new(vec![polygon![
+            (x: 0.0, y: 0.0),
+            (x: 3.0, y: 0.0),
+            (x: 3.0, y: 3.0),
+            (x: 2.0, y: 3.0),
+            (x: 2.0, y: 1.0),
+            (x: 1.0, y: 1.0),
+            (x: 1.0, y: 3.0),
+            (x: 0.0, y: 3.0),
+        ]]));
+
+        assert!(clip_polygon.contains_point(&Point2::new(0.5, 2.0)));
+        assert!(clip_polygon.contains_point(&Point2::new(2.5, 2.0)));
+        assert!(clip_polygon.contains_point(&Point2::new(1.5, 0.5)));
+        assert!(!clip_polygon.contains_point(&Point2::new(1.5, 2.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(3.5, 0.5)));
+    }
+
+    #[test]
+    fn test_polygon_with_hole_excludes_hole() {
+        let clip_polygon = ClipPolygon::new(MultiPolygon::new(vec![Polygon::new(
+            derive_square(0.0, 4.0),
+            vec![derive_square(1.0, 3.0)],
+        )]));
+
+        assert!(clip_polygon.contains_point(&Point2::new(0.5, 0.5)));
+        assert!(clip_polygon.contains_point(&Point2::new(3.5, 2.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(2.0, 2.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(5.0, 2.0)));
+    }
+
+    #[test]
+    fn test_multi_polygon_contains_points_of_every_part() {
+        let clip_polygon = ClipPolygon::new(MultiPolygon::new(vec![
+            Polygon::new(derive_square(0.0, 1.0), vec![]),
+            Polygon::new(derive_square(2.0, 3.0), vec![]),
+        ]));
+
+        assert!(clip_polygon.contains_point(&Point2::new(0.5, 0.5)));
+        assert!(clip_polygon.contains_point(&Point2::new(2.5, 2.5)));
+        assert!(!clip_polygon.contains_point(&Point2::new(1.5, 1.5)));
+    }
+
+    #[test]
+    fn test_boundary_points_belong_to_lower_and_left_edges() {
+        let clip_polygon = ClipPolygon::from_bounds(Point2::new(0.0, 0.0), Point2::new(1.0, 1.0));
+
+        assert!(clip_polygon.contains_point(&Point2::new(0.0, 0.5)));
+        assert!(clip_polygon.contains_point(&Point2::new(0.5, 0.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(1.0, 0.5)));
+        assert!(!clip_polygon.contains_point(&Point2::new(0.5, 1.0)));
+
+        assert!(clip_polygon.contains_point(&Point2::new(0.0, 0.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(1.0, 0.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(1.0, 1.0)));
+        assert!(!clip_polygon.contains_point(&Point2::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_adjacent_polygons_share_no_boundary_points() {
+        let left_polygon = ClipPolygon::from_bounds(Point2::new(0.0, 0.0), Point2::new(1.0, 1.0));
+        let right_polygon = ClipPolygon::from_bounds(Point2::new(1.0, 0.0), Point2::new(2.0, 1.0));
+
+        for point in [Point2::new(1.0, 0.0), Point2::new(1.0, 0.5)] {
+            assert!(left_polygon.contains_point(&point) != right_polygon.contains_point(&point));
+        }
+    }
+}
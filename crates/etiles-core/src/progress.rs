@@ -0,0 +1,256 @@
+use crate::error::Error;
+use crate::error::Error::Cancelled;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Phase of building and writing a tileset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProgressPhase {
+    /// Reading the input point cloud files.
+    Read,
+    /// Reprojecting the points into the local frame of the tileset.
+    Reproject,
+    /// Building the octree.
+    Octree,
+    /// Encoding the content of the tiles.
+    Encode,
+    /// Appending the entries to the archive.
+    Archive,
+}
+
+impl ProgressPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProgressPhase::Read => "read",
+            ProgressPhase::Reproject => "reproject",
+            ProgressPhase::Octree => "octree",
+            ProgressPhase::Encode => "encode",
+            ProgressPhase::Archive => "archive",
+        }
+    }
+}
+
+impl fmt::Display for ProgressPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Progress within a phase, reported to a [`ProgressObserver`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub phase: ProgressPhase,
+    /// Number of completed items of the phase, such as points, tiles or archive entries.
+    pub completed: u64,
+    pub total: u64,
+    /// Duration since the start of the phase.
+    pub elapsed: Duration,
+    /// Remaining duration of the phase, extrapolated from the completed items.
+    pub remaining: Option<Duration>,
+}
+
+/// Receives the progress of long-running operations, possibly from multiple threads.
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressObserver for F {
+    fn on_progress(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Token to cancel long-running operations from another thread.
+///
+/// Clones share the cancellation state. Operations check the token between work items and
+/// return [`Error::Cancelled`] once it is cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress observer and cancellation token passed to long-running operations.
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    observer: Option<Arc<dyn ProgressObserver>>,
+    cancellation_token: CancellationToken,
+}
+
+impl ProgressReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    /// Returns [`Error::Cancelled`] if the operation was cancelled.
+    pub fn check_cancelled(&self) -> Result<(), Error> {
+        if self.cancellation_token.is_cancelled() {
+            return Err(Cancelled());
+        }
+
+        Ok(())
+    }
+
+    /// Starts a phase of `total` items and reports it without completed items.
+    pub fn begin_phase(
+        &self,
+        phase: ProgressPhase,
+        total: u64,
+    ) -> Result<PhaseProgress<'_>, Error> {
+        self.check_cancelled()?;
+        let phase_progress = PhaseProgress {
+            reporter: self,
+            phase,
+            total,
+            completed: AtomicU64::new(0),
+            start: Instant::now(),
+        };
+        phase_progress.report(0);
+
+        Ok(phase_progress)
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("observer", &self.observer.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .finish()
+    }
+}
+
+/// Progress of a started phase, which can be advanced from multiple threads.
+#[derive(Debug)]
+pub struct PhaseProgress<'a> {
+    reporter: &'a ProgressReporter,
+    phase: ProgressPhase,
+    total: u64,
+    completed: AtomicU64,
+    start: Instant,
+}
+
+impl PhaseProgress<'_> {
+    /// Adds completed items and reports the progress. Fails if the operation was cancelled.
+    pub fn advance(&self, count: u64) -> Result<(), Error> {
+        let completed = self.completed.fetch_add(count, Ordering::Relaxed) + count;
+        self.report(completed);
+        self.reporter.check_cancelled()
+    }
+
+    /// Reports all items of the phase as completed.
+    pub fn finish(self) -> Result<(), Error> {
+        self.report(self.total);
+        self.reporter.check_cancelled()
+    }
+
+    fn report(&self, completed: u64) {
+        let Some(observer) = &self.reporter.observer else {
+            return;
+        };
+
+        let completed = completed.min(self.total);
+        let elapsed = self.start.elapsed();
+        let remaining = (completed > 0)
+            .then(|| elapsed.mul_f64((self.total - completed) as f64 / completed as f64));
+        observer.on_progress(&Progress {
+            phase: self.phase,
+            completed,
+            total: self.total,
+            elapsed,
+            remaining,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::derive_point_cloud;
+    use crate::tileset::Tileset;
+    use eproj::SpatialReferenceIdentifier;
+    use nalgebra::Point3;
+    use std::sync::Mutex;
+
+    /// Builds the tileset of points on a grid at an ECEF position on the earth's surface.
+    fn derive_tileset(progress_reporter: &ProgressReporter) -> Result<Tileset, Error> {
+        let points = (0..1_000)
+            .map(|i| {
+                Point3::new(
+                    4_157_000.0 + (i % 10) as f64,
+                    671_000.0 + ((i / 10) % 10) as f64,
+                    4_774_000.0 + (i / 100) as f64,
+                )
+            })
+            .collect();
+
+        Tileset::from_point_cloud_with_progress(
+            derive_point_cloud(points),
+            SpatialReferenceIdentifier::Epsg4978,
+            100,
+            Some(42),
+            progress_reporter,
+        )
+    }
+
+    #[test]
+    fn test_cancelled_token_stops_building() {
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let progress_reporter = ProgressReporter::new().with_cancellation_token(cancellation_token);
+
+        let result = derive_tileset(&progress_reporter);
+
+        assert!(matches!(result, Err(Cancelled())));
+    }
+
+    #[test]
+    fn test_every_phase_of_building_is_completed() {
+        let reported_progress: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let observed_progress = reported_progress.clone();
+        let progress_reporter = ProgressReporter::new().with_observer(move |p: &Progress| {
+            observed_progress.lock().expect("progress").push(*p)
+        });
+
+        derive_tileset(&progress_reporter).expect("tileset");
+
+        let reported_progress = reported_progress.lock().expect("progress");
+        for phase in [ProgressPhase::Reproject, ProgressPhase::Octree] {
+            let last_progress = reported_progress
+                .iter()
+                .rfind(|p| p.phase == phase)
+                .unwrap_or_else(|| panic!("no progress of phase {phase}"));
+            assert_eq!(last_progress.completed, last_progress.total);
+            assert_eq!(last_progress.total, 1_000);
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use crate::colorize::set_all_colors;
+use crate::error::Error;
+use crate::error::Error::InvalidGeoTiff;
+use crate::tileset::DEFAULT_POINT_COLOR;
+use epoint::PointCloud;
+use eproj::{Projector, SpatialReferenceIdentifier};
+use nalgebra::{Matrix3, Point2, Point3};
+use palette::Srgb;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use tiff::ColorType;
+use tiff::decoder::{ChunkType, Decoder, DecodingResult};
+use tiff::tags::Tag;
+use tracing::info;
+
+const GEO_KEY_MODEL_TYPE: u16 = 1024;
+const GEO_KEY_RASTER_TYPE: u16 = 1025;
+const GEO_KEY_GEOGRAPHIC_TYPE: u16 = 2048;
+const GEO_KEY_PROJECTED_CS_TYPE: u16 = 3072;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_TYPE_PIXEL_IS_POINT: u16 = 2;
+const PLANAR_CONFIGURATION_PLANAR: u16 = 2;
+
+/// Interpolation used when sampling a raster at a point position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RasterSampling {
+    /// Color of the pixel containing the position.
+    Nearest,
+    /// Bilinear interpolation between the four closest pixel centers.
+    #[default]
+    Bilinear,
+}
+
+/// Georeferenced RGB or grayscale raster, such as an orthophoto, read from a GeoTIFF file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoRaster {
+    crs: SpatialReferenceIdentifier,
+    width: usize,
+    height: usize,
+    /// Row-major RGB values.
+    pixels: RasterPixels,
+    /// Affine transform from horizontal CRS coordinates to continuous raster coordinates,
+    /// where the pixel `(column, row)` covers `[column, column + 1) × [row, row + 1)`.
+    world_to_raster: Matrix3<f64>,
+}
+
+impl GeoRaster {
+    /// Reads the first image of a GeoTIFF file. The CRS is taken from the GeoKey directory and
+    /// must be an EPSG code.
+    ///
+    /// The image is decoded strip by strip or tile by tile, keeping three 8 bit samples per
+    /// pixel for 8 bit images and three 16 bit samples otherwise.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = BufReader::new(File::open(path.as_ref())?);
+        let mut decoder = Decoder::new(file)?;
+        let (width, height) = decoder.dimensions()?;
+        let (width, height) = (width as usize, height as usize);
+
+        if decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)?
+            == Some(PLANAR_CONFIGURATION_PLANAR)
+        {
+            return Err(InvalidGeoTiff(
+                "planar sample configuration is not supported".to_string(),
+            ));
+        }
+
+        let geo_keys = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::GeoKeyDirectoryTag)?
+            .ok_or_else(|| InvalidGeoTiff("missing GeoKey directory".to_string()))?;
+        let crs = read_crs(&geo_keys)?;
+        let pixel_is_point =
+            read_geo_key(&geo_keys, GEO_KEY_RASTER_TYPE) == Some(RASTER_TYPE_PIXEL_IS_POINT);
+        let raster_to_world = read_raster_to_world(&mut decoder, pixel_is_point)?;
+        let world_to_raster = raster_to_world
+            .try_inverse()
+            .ok_or_else(|| InvalidGeoTiff("singular raster transformation".to_string()))?;
+
+        let (channel_count, bit_depth) = match decoder.colortype()? {
+            ColorType::Gray(bit_depth) => (1, bit_depth),
+            ColorType::GrayA(bit_depth) => (2, bit_depth),
+            ColorType::RGB(bit_depth) => (3, bit_depth),
+            ColorType::RGBA(bit_depth) => (4, bit_depth),
+            ColorType::Multiband {
+                bit_depth,
+                num_samples,
+            } if num_samples >= 3 => (num_samples as usize, bit_depth),
+            other => {
+                return Err(InvalidGeoTiff(format!("unsupported color type {other:?}")));
+            }
+        };
+        let pixels = read_pixels(&mut decoder, width, height, channel_count, bit_depth)?;
+
+        Ok(Self {
+            crs,
+            width,
+            height,
+            pixels,
+            world_to_raster,
+        })
+    }
+
+    pub fn crs(&self) -> SpatialReferenceIdentifier {
+        self.crs
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color at the horizontal position given in the raster CRS, or `None` if the
+    /// position lies outside the raster.
+    pub fn sample(&self, position: &Point2<f64>, sampling: RasterSampling) -> Option<Srgb<f32>> {
+        let raster_position = self.world_to_raster.transform_point(position);
+        let (u, v) = (raster_position.x, raster_position.y);
+        if !(0.0..self.width as f64).contains(&u) || !(0.0..self.height as f64).contains(&v) {
+            return None;
+        }
+
+        let color = match sampling {
+            RasterSampling::Nearest => self.get_pixel(u as usize, v as usize),
+            RasterSampling::Bilinear => {
+                let x = (u - 0.5).clamp(0.0, (self.width - 1) as f64);
+                let y = (v - 0.5).clamp(0.0, (self.height - 1) as f64);
+                let column = (x as usize).min(self.width.saturating_sub(2));
+                let row = (y as usize).min(self.height.saturating_sub(2));
+                let next_column = (column + 1).min(self.width - 1);
+                let next_row = (row + 1).min(self.height - 1);
+                let fx = (x - column as f64) as f32;
+                let fy = (y - row as f64) as f32;
+
+                let top = lerp(
+                    self.get_pixel(column, row),
+                    self.get_pixel(next_column, row),
+                    fx,
+                );
+                let bottom = lerp(
+                    self.get_pixel(column, next_row),
+                    self.get_pixel(next_column, next_row),
+                    fx,
+                );
+                lerp(top, bottom, fy)
+            }
+        };
+
+        Some(Srgb::new(color[0], color[1], color[2]))
+    }
+
+    fn get_pixel(&self, column: usize, row: usize) -> [f32; 3] {
+        let index = row * self.width + column;
+        match &self.pixels {
+            RasterPixels::U8(pixels) => pixels[index].map(|v| v as f32 / 255.0),
+            RasterPixels::U16(pixels) => pixels[index].map(|v| v as f32 / 65535.0),
+        }
+    }
+}
+
+/// RGB values of the pixels, stored with the precision of the image samples.
+#[derive(Debug, Clone, PartialEq)]
+enum RasterPixels {
+    U8(Vec<[u8; 3]>),
+    U16(Vec<[u16; 3]>),
+}
+
+/// Decodes the pixels chunk by chunk, so that only one strip or tile is held in full besides
+/// the RGB values.
+fn read_pixels(
+    decoder: &mut Decoder<BufReader<File>>,
+    width: usize,
+    height: usize,
+    channel_count: usize,
+    bit_depth: u8,
+) -> Result<RasterPixels, Error> {
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    let (chunk_width, chunk_height) = (chunk_width as usize, chunk_height as usize);
+    let (chunk_count, chunks_across) = match decoder.get_chunk_type() {
+        ChunkType::Strip => (decoder.strip_count()?, 1),
+        ChunkType::Tile => (decoder.tile_count()?, width.div_ceil(chunk_width.max(1))),
+    };
+    if chunk_count as usize != chunks_across * height.div_ceil(chunk_height.max(1)) {
+        return Err(InvalidGeoTiff("incomplete image data".to_string()));
+    }
+
+    let mut pixels = if bit_depth == 8 {
+        RasterPixels::U8(vec![[0; 3]; width * height])
+    } else {
+        RasterPixels::U16(vec![[0; 3]; width * height])
+    };
+    for chunk_index in 0..chunk_count {
+        let (data_width, data_height) = decoder.chunk_data_dimensions(chunk_index);
+        let chunk = ChunkPlacement {
+            column_offset: (chunk_index as usize % chunks_across) * chunk_width,
+            row_offset: (chunk_index as usize / chunks_across) * chunk_height,
+            data_width: data_width as usize,
+            data_height: data_height as usize,
+            channel_count,
+            image_width: width,
+        };
+
+        match (&mut pixels, decoder.read_chunk(chunk_index)?) {
+            (RasterPixels::U8(target), DecodingResult::U8(samples)) => {
+                chunk.copy_samples(target, &samples, |v| v)?
+            }
+            (RasterPixels::U16(target), DecodingResult::U16(samples)) => {
+                chunk.copy_samples(target, &samples, |v| v)?
+            }
+            (RasterPixels::U16(target), DecodingResult::F32(samples)) => {
+                chunk.copy_samples(target, &samples, |v| {
+                    (v.clamp(0.0, 1.0) * 65535.0).round() as u16
+                })?
+            }
+            _ => {
+                return Err(InvalidGeoTiff(
+                    "only 8 bit, 16 bit and float samples are supported".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Position and size of the data of a strip or tile within the image.
+struct ChunkPlacement {
+    column_offset: usize,
+    row_offset: usize,
+    data_width: usize,
+    data_height: usize,
+    channel_count: usize,
+    image_width: usize,
+}
+
+impl ChunkPlacement {
+    /// Copies the first three samples, or the gray sample, of each pixel of the chunk.
+    fn copy_samples<S: Copy, T>(
+        &self,
+        pixels: &mut [[T; 3]],
+        samples: &[S],
+        convert: impl Fn(S) -> T,
+    ) -> Result<(), Error> {
+        if samples.len() < self.data_width * self.data_height * self.channel_count {
+            return Err(InvalidGeoTiff("incomplete image data".to_string()));
+        }
+
+        for (row, row_samples) in samples
+            .chunks_exact(self.data_width * self.channel_count)
+            .take(self.data_height)
+            .enumerate()
+        {
+            let row_start = (self.row_offset + row) * self.image_width + self.column_offset;
+            for (pixel, s) in pixels[row_start..row_start + self.data_width]
+                .iter_mut()
+                .zip(row_samples.chunks_exact(self.channel_count))
+            {
+                *pixel = if self.channel_count < 3 {
+                    [convert(s[0]), convert(s[0]), convert(s[0])]
+                } else {
+                    [convert(s[0]), convert(s[1]), convert(s[2])]
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+fn read_geo_key(geo_keys: &[u16], key_id: u16) -> Option<u16> {
+    // header of four values, followed by entries of (key, tag location, count, value)
+    geo_keys
+        .get(4..)?
+        .chunks_exact(4)
+        .find(|entry| entry[0] == key_id && entry[1] == 0)
+        .map(|entry| entry[3])
+}
+
+fn read_crs(geo_keys: &[u16]) -> Result<SpatialReferenceIdentifier, Error> {
+    let key_id = if read_geo_key(geo_keys, GEO_KEY_MODEL_TYPE) == Some(MODEL_TYPE_GEOGRAPHIC) {
+        GEO_KEY_GEOGRAPHIC_TYPE
+    } else {
+        GEO_KEY_PROJECTED_CS_TYPE
+    };
+    let code = read_geo_key(geo_keys, key_id)
+        .ok_or_else(|| InvalidGeoTiff("missing EPSG code of the raster CRS".to_string()))?;
+
+    SpatialReferenceIdentifier::from_code(code as u32)
+        .map_err(|_| InvalidGeoTiff(format!("unsupported raster CRS code {code}")))
+}
+
+fn read_raster_to_world(
+    decoder: &mut Decoder<BufReader<File>>,
+    pixel_is_point: bool,
+) -> Result<Matrix3<f64>, Error> {
+    let mut raster_to_world = if let Some(m) = decoder
+        .find_tag(Tag::ModelTransformationTag)?
+        .map(|v| v.into_f64_vec())
+        .transpose()?
+    {
+        if m.len() < 8 {
+            return Err(InvalidGeoTiff("invalid model transformation".to_string()));
+        }
+        Matrix3::new(m[0], m[1], m[3], m[4], m[5], m[7], 0.0, 0.0, 1.0)
+    } else {
+        let scale = decoder
+            .find_tag(Tag::ModelPixelScaleTag)?
+            .map(|v| v.into_f64_vec())
+            .transpose()?
+            .filter(|s| s.len() >= 2)
+            .ok_or_else(|| InvalidGeoTiff("missing model pixel scale".to_string()))?;
+        let tie_point = decoder
+            .find_tag(Tag::ModelTiepointTag)?
+            .map(|v| v.into_f64_vec())
+            .transpose()?
+            .filter(|t| t.len() >= 6)
+            .ok_or_else(|| InvalidGeoTiff("missing model tie point".to_string()))?;
+
+        Matrix3::new(
+            scale[0],
+            0.0,
+            tie_point[3] - tie_point[0] * scale[0],
+            0.0,
+            -scale[1],
+            tie_point[4] + tie_point[1] * scale[1],
+            0.0,
+            0.0,
+            1.0,
+        )
+    };
+
+    if pixel_is_point {
+        // raster coordinates refer to pixel centers instead of pixel corners
+        raster_to_world *= Matrix3::new(1.0, 0.0, -0.5, 0.0, 1.0, -0.5, 0.0, 0.0, 1.0);
+    }
+
+    Ok(raster_to_world)
+}
+
+/// Colors each point by sampling the rasters at its horizontal position.
+///
+/// The rasters are tried in the given order and the first one covering the point is used.
+/// Points outside all rasters keep their color, or get the default color if the point cloud has
+/// no colors.
+pub fn colorize_point_cloud_from_rasters(
+    mut point_cloud: PointCloud,
+    source_crs: SpatialReferenceIdentifier,
+    rasters: &[GeoRaster],
+    sampling: RasterSampling,
+) -> Result<PointCloud, Error> {
+    let all_points = point_cloud.point_data.get_all_points();
+    let mut colors: Vec<Option<Srgb<f32>>> = vec![None; all_points.len()];
+
+    for raster in rasters {
+        let positions = if raster.crs() == source_crs {
+            all_points.clone()
+        } else {
+            let num_threads = std::cmp::max(rayon::current_num_threads(), 1);
+            let chunk_size = all_points.len().div_ceil(num_threads).max(1);
+            all_points
+                .par_chunks(chunk_size)
+                .map(|x| {
+                    let projector = Projector::new(source_crs, raster.crs())?;
+                    Ok(projector.convert_points(x.to_vec())?)
+                })
+                .collect::<Result<Vec<Vec<Point3<f64>>>, Error>>()?
+                .into_iter()
+                .flatten()
+                .collect()
+        };
+
+        colors
+            .par_iter_mut()
+            .zip(positions.par_iter())
+            .filter(|(c, _)| c.is_none())
+            .for_each(|(c, p)| *c = raster.sample(&p.xy(), sampling));
+    }
+
+    let covered_count = colors.iter().filter(|c| c.is_some()).count();
+    info!(
+        "Colorized {} of {} points from rasters",
+        covered_count,
+        colors.len()
+    );
+
+    let existing_colors: Option<Vec<Srgb<f32>>> = point_cloud
+        .point_data
+        .get_all_colors()
+        .ok()
+        .map(|colors| colors.into_iter().map(|c| c.into_format()).collect());
+    let colors: Vec<Srgb<f32>> = colors
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| {
+            c.unwrap_or_else(|| {
+                existing_colors
+                    .as_ref()
+                    .map_or(DEFAULT_POINT_COLOR, |e| e[i])
+            })
+        })
+        .collect();
+
+    set_all_colors(&mut point_cloud, colors)?;
+    Ok(point_cloud)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Raster of 2×2 pixels with a size of 10 m, whose upper left corner lies at (100, 200).
+    /// The pixels are black, red (right), green (below) and blue (diagonal).
+    fn derive_raster() -> GeoRaster {
+        GeoRaster {
+            crs: SpatialReferenceIdentifier::Epsg25832,
+            width: 2,
+            height: 2,
+            pixels: RasterPixels::U8(vec![[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]]),
+            world_to_raster: Matrix3::new(0.1, 0.0, -10.0, 0.0, -0.1, 20.0, 0.0, 0.0, 1.0),
+        }
+    }
+
+    fn assert_color(color: Option<Srgb<f32>>, expected: [f32; 3]) {
+        let color = color.expect("color");
+        let difference = [
+            color.red - expected[0],
+            color.green - expected[1],
+            color.blue - expected[2],
+        ];
+        assert!(
+            difference.iter().all(|d| d.abs() < 1e-6),
+            "{color:?} differs from {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_sampling_at_pixel_centers_returns_pixels() {
+        let raster = derive_raster();
+
+        for sampling in [RasterSampling::Nearest, RasterSampling::Bilinear] {
+            assert_color(
+                raster.sample(&Point2::new(105.0, 195.0), sampling),
+                [0.0; 3],
+            );
+            assert_color(
+                raster.sample(&Point2::new(115.0, 195.0), sampling),
+                [1.0, 0.0, 0.0],
+            );
+            assert_color(
+                raster.sample(&Point2::new(105.0, 185.0), sampling),
+                [0.0, 1.0, 0.0],
+            );
+            assert_color(
+                raster.sample(&Point2::new(115.0, 185.0), sampling),
+                [0.0, 0.0, 1.0],
+            );
+        }
+    }
+
+    #[test]
+    fn test_sampling_at_pixel_edges() {
+        let raster = derive_raster();
+
+        // shared edge of the upper pixels
+        assert_color(
+            raster.sample(&Point2::new(110.0, 195.0), RasterSampling::Nearest),
+            [1.0, 0.0, 0.0],
+        );
+        assert_color(
+            raster.sample(&Point2::new(110.0, 195.0), RasterSampling::Bilinear),
+            [0.5, 0.0, 0.0],
+        );
+
+        // shared corner of all pixels
+        assert_color(
+            raster.sample(&Point2::new(110.0, 190.0), RasterSampling::Nearest),
+            [0.0, 0.0, 1.0],
+        );
+        assert_color(
+            raster.sample(&Point2::new(110.0, 190.0), RasterSampling::Bilinear),
+            [0.25, 0.25, 0.25],
+        );
+
+        // left edge of the raster, where the bilinear interpolation is clamped
+        for sampling in [RasterSampling::Nearest, RasterSampling::Bilinear] {
+            assert_color(
+                raster.sample(&Point2::new(100.0, 195.0), sampling),
+                [0.0; 3],
+            );
+        }
+    }
+
+    #[test]
+    fn test_sampling_outside_raster_returns_none() {
+        let raster = derive_raster();
+
+        for sampling in [RasterSampling::Nearest, RasterSampling::Bilinear] {
+            assert_eq!(raster.sample(&Point2::new(120.0, 195.0), sampling), None);
+            assert_eq!(raster.sample(&Point2::new(105.0, 200.5), sampling), None);
+            assert_eq!(raster.sample(&Point2::new(99.0, 185.0), sampling), None);
+            assert_eq!(raster.sample(&Point2::new(105.0, 180.0), sampling), None);
+        }
+    }
+}
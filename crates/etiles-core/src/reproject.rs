@@ -1,27 +1,48 @@
 use crate::error::Error;
+use crate::progress::{PhaseProgress, ProgressPhase, ProgressReporter};
 use epoint::PointCloud;
 use eproj::{Projector, SpatialReferenceIdentifier};
 use nalgebra::Point3;
 use rayon::prelude::*;
 
+/// Number of points converted by one projector between progress reports.
+const REPROJECTION_CHUNK_SIZE: usize = 100_000;
+
 pub fn reproject_point_cloud(
+    point_cloud: PointCloud,
+    from: SpatialReferenceIdentifier,
+    to: SpatialReferenceIdentifier,
+) -> Result<PointCloud, Error> {
+    let progress_reporter = ProgressReporter::default();
+    let reproject_progress = progress_reporter.begin_phase(
+        ProgressPhase::Reproject,
+        point_cloud.point_data.height() as u64,
+    )?;
+
+    reproject_point_cloud_with_progress(point_cloud, from, to, &reproject_progress)
+}
+
+/// Reprojects the points like [`reproject_point_cloud`], advancing the progress after each
+/// chunk of points. Fails with [`Error::Cancelled`] once the operation is cancelled.
+pub fn reproject_point_cloud_with_progress(
     mut point_cloud: PointCloud,
     from: SpatialReferenceIdentifier,
     to: SpatialReferenceIdentifier,
+    reproject_progress: &PhaseProgress<'_>,
 ) -> Result<PointCloud, Error> {
     let all_points = point_cloud.point_data.get_all_points();
-    let num_threads = std::cmp::max(rayon::current_num_threads(), 1);
-    let chunk_size = all_points.len().div_ceil(num_threads);
 
-    let projected_points: Vec<Point3<f64>> = all_points
-        .par_chunks(chunk_size)
-        .flat_map(|x| {
-            let projector = Projector::new(from, to).unwrap();
+    let projected_chunks: Vec<Vec<Point3<f64>>> = all_points
+        .par_chunks(REPROJECTION_CHUNK_SIZE)
+        .map(|x| {
+            let projector = Projector::new(from, to)?;
+            let projected_points = projector.convert_points(x.to_vec())?;
+            reproject_progress.advance(x.len() as u64)?;
 
-            projector.convert_points(x.to_vec()).unwrap()
+            Ok(projected_points)
         })
-        .collect();
-    point_cloud.update_points(projected_points, None)?;
+        .collect::<Result<_, Error>>()?;
+    point_cloud.update_points(projected_chunks.into_iter().flatten().collect(), None)?;
 
     Ok(point_cloud)
 }
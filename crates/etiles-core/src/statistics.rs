@@ -0,0 +1,143 @@
+use crate::frame::derive_east_north_up_rotation;
+use crate::tileset::Tileset;
+use ecoord::octree::OctantIndex;
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+
+const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Maximum height of the origin above or below the ellipsoid, beyond which the origin is not
+/// considered geodetic, e.g. the center of the earth of an identity root transform.
+const MAXIMUM_ORIGIN_HEIGHT: f64 = 100_000.0;
+/// Maximum angle in radians between the z-axis of the local frame and the ellipsoid normal at
+/// the origin, for the z-axis to be considered up.
+const MAXIMUM_UP_AXIS_DEVIATION: f64 = 0.01;
+
+/// Point statistics of an octant, as attached to tiles as metadata.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OctantStatistics {
+    /// Number of points in the content of the octant itself.
+    pub point_count: u64,
+    /// Minimum ellipsoidal height of the points of the octant and all its descendants, if the
+    /// tileset has heights, see [`Tileset::derive_origin_height`].
+    pub minimum_height: Option<f64>,
+    /// Maximum ellipsoidal height of the points of the octant and all its descendants, if the
+    /// tileset has heights.
+    pub maximum_height: Option<f64>,
+}
+
+impl Tileset {
+    /// Returns the ellipsoidal height (WGS 84) of the origin of the root transform, if the origin
+    /// lies near the ellipsoid and the z-axis of the local frame points up there.
+    ///
+    /// Otherwise, e.g. for ECEF coordinates or local coordinates placed without a geographic
+    /// position, the local z values are no heights and `None` is returned.
+    pub fn derive_origin_height(&self) -> Option<f64> {
+        let origin = Point3::from(self.root_transform.translation.vector);
+        let origin_height = derive_ellipsoidal_height(&origin);
+        let up_axis = derive_east_north_up_rotation(&origin) * Vector3::z();
+        let z_axis = self.root_transform.rotation * Vector3::z();
+
+        (origin_height.abs() <= MAXIMUM_ORIGIN_HEIGHT
+            && z_axis.angle(&up_axis) <= MAXIMUM_UP_AXIS_DEVIATION)
+            .then_some(origin_height)
+    }
+
+    /// Derives the statistics of all occupied octants.
+    ///
+    /// Heights are approximated by adding the local z value to the height of the origin, as the
+    /// z axis of the local frame points up at the origin, and are omitted if the tileset has no
+    /// origin height. Octants without content in themselves or their descendants get the
+    /// height range of their bounding cube.
+    pub fn derive_octant_statistics(&self) -> HashMap<OctantIndex, OctantStatistics> {
+        let origin_height = self.derive_origin_height();
+
+        let mut octant_statistics: HashMap<OctantIndex, OctantStatistics> = HashMap::new();
+        for (octant_index, vertices) in self.tiled_content.cells() {
+            let (minimum_z, maximum_z) =
+                vertices.iter().fold((f64::MAX, f64::MIN), |(min, max), v| {
+                    (min.min(v.position.z), max.max(v.position.z))
+                });
+
+            for ancestor_index in octant_index.get_ancestors() {
+                let statistics =
+                    octant_statistics
+                        .entry(ancestor_index)
+                        .or_insert(OctantStatistics {
+                            point_count: 0,
+                            minimum_height: origin_height.map(|_| f64::MAX),
+                            maximum_height: origin_height.map(|_| f64::MIN),
+                        });
+                if ancestor_index == *octant_index {
+                    statistics.point_count = vertices.len() as u64;
+                }
+                statistics.minimum_height = statistics
+                    .minimum_height
+                    .zip(origin_height)
+                    .map(|(h, o)| h.min(o + minimum_z));
+                statistics.maximum_height = statistics
+                    .maximum_height
+                    .zip(origin_height)
+                    .map(|(h, o)| h.max(o + maximum_z));
+            }
+        }
+
+        let bounds = self.tiled_content.bounds();
+        let maximum_level = self
+            .tiled_content
+            .get_max_occupied_level()
+            .unwrap_or_default();
+        for level in 0..=maximum_level {
+            for octant_index in self
+                .tiled_content
+                .occupancy_graph()
+                .get_occupied_cell_indices_of_level(level)
+            {
+                octant_statistics.entry(octant_index).or_insert_with(|| {
+                    let bounding_cube = bounds.get_octant_bounding_cube(octant_index);
+                    OctantStatistics {
+                        point_count: 0,
+                        minimum_height: origin_height.map(|o| {
+                            o + bounding_cube.center().z - bounding_cube.half_edge_length()
+                        }),
+                        maximum_height: origin_height.map(|o| {
+                            o + bounding_cube.center().z + bounding_cube.half_edge_length()
+                        }),
+                    }
+                });
+            }
+        }
+
+        octant_statistics
+    }
+}
+
+/// Converts an ECEF position to its height above the WGS 84 ellipsoid by fixed-point iteration
+/// of the geodetic latitude.
+fn derive_ellipsoidal_height(position: &Point3<f64>) -> f64 {
+    let eccentricity_squared = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let semi_minor_axis = WGS84_SEMI_MAJOR_AXIS * (1.0 - WGS84_FLATTENING);
+    let distance_to_axis = position.x.hypot(position.y);
+    if distance_to_axis < 1.0 {
+        return position.z.abs() - semi_minor_axis;
+    }
+
+    let mut latitude = position
+        .z
+        .atan2(distance_to_axis * (1.0 - eccentricity_squared));
+    let mut height = 0.0;
+    for _ in 0..5 {
+        let prime_vertical_radius =
+            WGS84_SEMI_MAJOR_AXIS / (1.0 - eccentricity_squared * latitude.sin().powi(2)).sqrt();
+        height = distance_to_axis / latitude.cos() - prime_vertical_radius;
+        latitude = position.z.atan2(
+            distance_to_axis
+                * (1.0
+                    - eccentricity_squared * prime_vertical_radius
+                        / (prime_vertical_radius + height)),
+        );
+    }
+
+    height
+}
@@ -0,0 +1,34 @@
+use crate::COLUMN_NAME_CLASSIFICATION_STR;
+use ecoord::TransformTree;
+use epoint::{PointCloud, PointCloudInfo, PointDataColumns};
+use nalgebra::Point3;
+use polars::prelude::{NamedFrom, Series};
+
+/// Creates a point cloud with only positions.
+pub(crate) fn derive_point_cloud(points: Vec<Point3<f64>>) -> PointCloud {
+    let point_data =
+        PointDataColumns::new(points, None, None, None, None, None, None).expect("point data");
+    PointCloud::new(
+        point_data,
+        PointCloudInfo::new(None),
+        TransformTree::default(),
+    )
+    .expect("point cloud")
+}
+
+/// Creates a point cloud with positions and a classification column.
+pub(crate) fn derive_classified_point_cloud(
+    points: Vec<Point3<f64>>,
+    classifications: Vec<u8>,
+) -> PointCloud {
+    let mut point_cloud = derive_point_cloud(points);
+    point_cloud
+        .point_data
+        .data_frame
+        .with_column(Series::new(
+            COLUMN_NAME_CLASSIFICATION_STR.into(),
+            classifications,
+        ))
+        .expect("classification column");
+    point_cloud
+}
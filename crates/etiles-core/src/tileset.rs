@@ -1,18 +1,40 @@
 use crate::error::Error;
-use crate::reproject::reproject_point_cloud;
-use ecoord::HasAabb;
-use ecoord::octree::{Octree, StorageMode};
-use epoint::transform::apply_isometry;
-use eproj::{Projector, SpatialReferenceIdentifier};
-use nalgebra::{Isometry3, Point3, UnitQuaternion};
+use crate::error::Error::{
+    EmptyTileset, InvalidEnclosingCube, NonPositiveParameter, PointsNotFound, PointsOutsideBounds,
+};
+use crate::filter::get_all_classifications;
+use crate::frame::{LocalPlacement, TilesetAlignment, TilesetFrame, derive_normal_rotation};
+use crate::normal::{get_all_normals, has_normals};
+use crate::progress::{PhaseProgress, ProgressPhase, ProgressReporter};
+use crate::reproject::reproject_point_cloud_with_progress;
+use ecoord::octree::{OctantIndex, Octree, OctreeBounds, OctreeOccupancyGraph, StorageMode};
+use ecoord::{AxisAlignedBoundingBox, AxisAlignedBoundingCube, HasAabb};
+use eproj::SpatialReferenceIdentifier;
+use nalgebra::{Isometry3, Point3, Translation3, Vector3};
 use palette::Srgb;
+use rand::SeedableRng;
+use rand::prelude::{SliceRandom, StdRng};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::f64;
 use std::iter::zip;
 
+/// Color of points for which the point cloud provides no color.
+pub(crate) const DEFAULT_POINT_COLOR: Srgb<f32> = Srgb::new(0.83144885, 0.83144885, 0.83144885);
+
+/// Deepest octree level to which inserted points are passed on, so that the octant coordinates
+/// fit the 32-bit Morton indices of the subtrees.
+const MAXIMUM_OCTANT_LEVEL: u32 = 31;
+
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
     pub position: Point3<f64>,
     pub color: Srgb<f32>,
+    pub normal: Option<Vector3<f32>>,
+    /// ASPRS classification code, if provided by the point cloud.
+    pub classification: Option<u8>,
+    /// Acquisition time in seconds since the Unix epoch, if provided by the point cloud.
+    pub time: Option<f64>,
 }
 
 impl HasAabb for Vertex {
@@ -29,6 +51,7 @@ impl HasAabb for Vertex {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Tileset {
     pub tiled_content: Octree<Vertex>,
     pub root_transform: Isometry3<f64>,
@@ -43,67 +66,782 @@ impl Tileset {
         maximum_points_per_octant: u64,
         seed_number: Option<u64>,
     ) -> Result<Self, Error> {
-        let number_of_points = point_cloud.point_data.height();
-        let projector = Projector::new(source_srs, SpatialReferenceIdentifier::Epsg4978)?;
-        let isometry = Isometry3::from_parts(
-            point_cloud.point_data.get_local_center().into(),
-            UnitQuaternion::default(),
-        );
-        let converted_isometry = projector.convert_isometry(isometry)?;
-        //info!("Derived isometry: {:?}", &converted_isometry);
+        Self::from_point_cloud_with_progress(
+            point_cloud,
+            source_srs,
+            maximum_points_per_octant,
+            seed_number,
+            &ProgressReporter::default(),
+        )
+    }
 
-        // info!("Start reprojecting");
-        let reprojected_point_cloud = reproject_point_cloud(
+    /// Builds the tileset like [`Tileset::from_point_cloud`], reporting the reproject and octree
+    /// phases. Fails with [`Error::Cancelled`] once the cancellation token is cancelled.
+    pub fn from_point_cloud_with_progress(
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+        progress_reporter: &ProgressReporter,
+    ) -> Result<Self, Error> {
+        Self::from_point_cloud_with_frame(
             point_cloud,
             source_srs,
-            SpatialReferenceIdentifier::Epsg4978,
-        )?;
+            TilesetFrame::default(),
+            TilesetAlignment::default(),
+            maximum_points_per_octant,
+            seed_number,
+            progress_reporter,
+        )
+    }
 
-        //info!("Start applying isometry");
-        let geodetic_transform_isometry = converted_isometry.inverse();
-        let local_point_cloud =
-            apply_isometry(&reprojected_point_cloud, geodetic_transform_isometry).unwrap();
-
-        let point_cloud_positions = local_point_cloud.point_data.get_all_points();
-        let point_cloud_colors: Vec<Srgb<f32>> =
-            match local_point_cloud.point_data.get_all_colors().ok() {
-                Some(colors) => colors.into_iter().map(|c| c.into_format()).collect(),
-                None => {
-                    vec![
-                        Srgb::<f32>::new(0.83144885, 0.83144885, 0.83144885);
-                        local_point_cloud.point_data.height()
-                    ]
-                }
-            };
-        let point_cloud_vertices: Vec<Vertex> = zip(point_cloud_positions, point_cloud_colors)
-            .map(|(p, c)| Vertex {
-                position: p,
-                color: c,
-            })
-            .collect();
+    /// Builds the tileset like [`Tileset::from_point_cloud_with_progress`], storing the points in
+    /// the given local frame instead of the axes of the source CRS at their center. The octree
+    /// is built in the horizontal axes of the alignment, whose rotation is folded into the root
+    /// transform. Fails if the alignment is not supported in the frame.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_point_cloud_with_frame(
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        tileset_frame: TilesetFrame,
+        tileset_alignment: TilesetAlignment,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+        progress_reporter: &ProgressReporter,
+    ) -> Result<Self, Error> {
+        tileset_frame.check_alignment(tileset_alignment)?;
+        let converted_isometry = tileset_frame
+            .derive_root_transform(source_srs, point_cloud.point_data.get_local_center())?;
+
+        let mut point_cloud_vertices = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Source(source_srs),
+            &converted_isometry,
+            progress_reporter,
+        )?;
+        let converted_isometry =
+            converted_isometry * tileset_alignment.align_vertices(&mut point_cloud_vertices);
 
-        //info!("Start building octree");
-        let point_cloud_octree = Octree::new(
+        Self::from_local_vertices(
             point_cloud_vertices,
-            maximum_points_per_octant as usize,
-            StorageMode::AllOctants,
+            converted_isometry,
+            None,
+            maximum_points_per_octant,
             seed_number,
-        )?;
+            progress_reporter,
+        )
+    }
+
+    /// Builds the octree over vertices given in the local frame of the root transform, reporting
+    /// the octree phase.
+    ///
+    /// The octree is spanned by `fixed_bounding_box` if given, which must contain all vertices,
+    /// and by the bounding box of the vertices otherwise.
+    pub(crate) fn from_local_vertices(
+        vertices: Vec<Vertex>,
+        root_transform: Isometry3<f64>,
+        fixed_bounding_box: Option<AxisAlignedBoundingBox>,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+        progress_reporter: &ProgressReporter,
+    ) -> Result<Self, Error> {
+        check_maximum_points_per_octant(maximum_points_per_octant)?;
+        let number_of_points = vertices.len();
+
+        let octree_progress =
+            progress_reporter.begin_phase(ProgressPhase::Octree, number_of_points as u64)?;
+        let mut tileset = match fixed_bounding_box {
+            Some(bounding_box) => {
+                let (lower_bound, upper_bound) =
+                    (bounding_box.lower_bound(), bounding_box.upper_bound());
+                let outside_count = vertices
+                    .iter()
+                    .filter(|v| {
+                        (0..3).any(|i| {
+                            v.position[i] < lower_bound[i] || v.position[i] > upper_bound[i]
+                        })
+                    })
+                    .count();
+                if outside_count > 0 {
+                    return Err(PointsOutsideBounds(outside_count));
+                }
+
+                let bounds = OctreeBounds::new(bounding_box);
+                let occupancy_graph = derive_occupancy_graph(std::iter::empty());
+                let mut tileset = Self {
+                    tiled_content: Octree::from_raw_parts(bounds, occupancy_graph, HashMap::new())?,
+                    root_transform,
+                    root_geometric_error: 0.0,
+                    geometric_error: 0.0,
+                };
+                tileset.insert_vertices_with_progress(
+                    vertices,
+                    maximum_points_per_octant,
+                    seed_number,
+                    &octree_progress,
+                )?;
+                tileset
+            }
+            None => Self {
+                tiled_content: Octree::new(
+                    vertices,
+                    maximum_points_per_octant as usize,
+                    StorageMode::AllOctants,
+                    seed_number,
+                )?,
+                root_transform,
+                root_geometric_error: 0.0,
+                geometric_error: 0.0,
+            },
+        };
+        octree_progress.finish()?;
 
-        let root_geometric_error = point_cloud_octree.bounds().bounding_box().diagonal().norm();
-        let geometric_error = {
-            let bounding_box_volume = point_cloud_octree.bounds().bounding_box().volume();
+        let bounding_box = tileset.tiled_content.bounds().bounding_box();
+        tileset.root_geometric_error = bounding_box.diagonal().norm();
+        tileset.geometric_error = {
+            let bounding_box_volume = bounding_box.volume();
             let average_spacing = (bounding_box_volume / number_of_points as f64).cbrt();
             let base_scaling = 7.0;
             average_spacing * 2.0f64.sqrt() * base_scaling
         };
-        //info!("geometric_error: {geometric_error}");
+
+        Ok(tileset)
+    }
+
+    /// Builds the tileset from a point cloud in engineering or local coordinates without a CRS,
+    /// e.g. an indoor or plant scan. The points are not reprojected, but shifted to their center
+    /// to keep their precision, which is folded into the root transform together with the
+    /// placement and the rotation of the alignment.
+    pub fn from_local_point_cloud(
+        point_cloud: epoint::PointCloud,
+        local_placement: LocalPlacement,
+        tileset_alignment: TilesetAlignment,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+        progress_reporter: &ProgressReporter,
+    ) -> Result<Self, Error> {
+        let placement_transform = local_placement.derive_transform();
+        let root_transform = placement_transform
+            * Translation3::from(point_cloud.point_data.get_local_center().coords);
+        let mut point_cloud_vertices = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Local(placement_transform),
+            &root_transform,
+            progress_reporter,
+        )?;
+        let root_transform =
+            root_transform * tileset_alignment.align_vertices(&mut point_cloud_vertices);
+
+        Self::from_local_vertices(
+            point_cloud_vertices,
+            root_transform,
+            None,
+            maximum_points_per_octant,
+            seed_number,
+            progress_reporter,
+        )
+    }
+
+    /// Assembles a tileset from the vertices of each octant, e.g. as read from an existing
+    /// archive. The octree is spanned by the root `enclosing_cube`.
+    pub fn from_raw_parts(
+        enclosing_cube: &AxisAlignedBoundingCube,
+        cells: HashMap<OctantIndex, Vec<Vertex>>,
+        root_transform: Isometry3<f64>,
+        root_geometric_error: f64,
+        geometric_error: f64,
+    ) -> Result<Self, Error> {
+        let bounds = derive_octree_bounds(enclosing_cube)?;
+        let occupancy_graph = derive_occupancy_graph(cells.keys());
+        let tiled_content = Octree::from_raw_parts(bounds, occupancy_graph, cells)?;
 
         Ok(Self {
-            tiled_content: point_cloud_octree,
-            root_transform: converted_isometry,
+            tiled_content,
+            root_transform,
             root_geometric_error,
             geometric_error,
         })
     }
+
+    /// Inserts the points into the existing octree without changing its bounds or the root
+    /// transform.
+    ///
+    /// Points fill up octants with fewer than `maximum_points_per_octant` points, starting at
+    /// the root, and are passed on to the child octants otherwise. The octants at level 31 keep
+    /// all points passed on to them, e.g. when more points share a position. Fails if any point
+    /// lies outside the octree bounds or `maximum_points_per_octant` is zero. The geometric error
+    /// is scaled to the new number of points. Returns the indices of all octants whose content
+    /// changed.
+    pub fn insert_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        check_maximum_points_per_octant(maximum_points_per_octant)?;
+        let vertices = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Source(source_srs),
+            &self.root_transform,
+            &ProgressReporter::default(),
+        )?;
+        self.insert_vertices(vertices, maximum_points_per_octant, seed_number)
+    }
+
+    /// Inserts the points of a point cloud in local coordinates like
+    /// [`Tileset::insert_point_cloud`], where the tileset was built by
+    /// [`Tileset::from_local_point_cloud`] with the same placement.
+    pub fn insert_local_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        local_placement: LocalPlacement,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        check_maximum_points_per_octant(maximum_points_per_octant)?;
+        let vertices = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Local(local_placement.derive_transform()),
+            &self.root_transform,
+            &ProgressReporter::default(),
+        )?;
+        self.insert_vertices(vertices, maximum_points_per_octant, seed_number)
+    }
+
+    /// Inserts vertices given in the local frame of the root transform, see
+    /// [`Tileset::insert_point_cloud`].
+    pub fn insert_vertices(
+        &mut self,
+        vertices: Vec<Vertex>,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        check_maximum_points_per_octant(maximum_points_per_octant)?;
+        let progress_reporter = ProgressReporter::default();
+        let octree_progress =
+            progress_reporter.begin_phase(ProgressPhase::Octree, vertices.len() as u64)?;
+
+        self.insert_vertices_with_progress(
+            vertices,
+            maximum_points_per_octant,
+            seed_number,
+            &octree_progress,
+        )
+    }
+
+    /// Inserts the vertices like [`Tileset::insert_vertices`], advancing the progress after each
+    /// octree level. The tileset is unchanged if the operation is cancelled.
+    fn insert_vertices_with_progress(
+        &mut self,
+        mut vertices: Vec<Vertex>,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+        octree_progress: &PhaseProgress<'_>,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        let bounds = *self.tiled_content.bounds();
+
+        let outside_count = vertices
+            .iter()
+            .filter(|v| !bounds.enclosing_cube().contains_point(&v.position))
+            .count();
+        if outside_count > 0 {
+            return Err(PointsOutsideBounds(outside_count));
+        }
+        if let Some(seed_number) = seed_number {
+            vertices.shuffle(&mut StdRng::seed_from_u64(seed_number));
+        }
+
+        let mut cells = self.tiled_content.cells().clone();
+        let previous_number_of_points: usize = cells.values().map(|c| c.len()).sum();
+        let mut changed_octant_indices: HashSet<OctantIndex> = HashSet::new();
+        let mut pending_vertices: HashMap<OctantIndex, Vec<Vertex>> =
+            HashMap::from([(OctantIndex::origin(), vertices)]);
+
+        while !pending_vertices.is_empty() {
+            let mut next_pending_vertices: HashMap<OctantIndex, Vec<Vertex>> = HashMap::new();
+            let mut inserted_count = 0;
+
+            for (octant_index, mut octant_vertices) in pending_vertices {
+                let free_count = if octant_index.level >= MAXIMUM_OCTANT_LEVEL {
+                    octant_vertices.len()
+                } else {
+                    let cell_size = cells.get(&octant_index).map_or(0, |c| c.len());
+                    (maximum_points_per_octant as usize).saturating_sub(cell_size)
+                };
+                let remaining_vertices =
+                    octant_vertices.split_off(free_count.min(octant_vertices.len()));
+
+                if !octant_vertices.is_empty() {
+                    inserted_count += octant_vertices.len();
+                    changed_octant_indices.insert(octant_index);
+                    cells
+                        .entry(octant_index)
+                        .or_default()
+                        .extend(octant_vertices);
+                }
+
+                let center = bounds.get_octant_bounding_cube(octant_index).center();
+                let child_base = octant_index.get_child_base_octant();
+                for vertex in remaining_vertices {
+                    let child_index = OctantIndex::new_unchecked(
+                        child_base.level,
+                        child_base.x + (vertex.position.x >= center.x) as u64,
+                        child_base.y + (vertex.position.y >= center.y) as u64,
+                        child_base.z + (vertex.position.z >= center.z) as u64,
+                    );
+                    next_pending_vertices
+                        .entry(child_index)
+                        .or_default()
+                        .push(vertex);
+                }
+            }
+
+            pending_vertices = next_pending_vertices;
+            octree_progress.advance(inserted_count as u64)?;
+        }
+
+        let number_of_points = cells.values().map(|c| c.len()).sum();
+        let occupancy_graph = derive_occupancy_graph(cells.keys());
+        self.tiled_content = Octree::from_raw_parts(bounds, occupancy_graph, cells)?;
+        self.geometric_error = scale_geometric_error(
+            self.geometric_error,
+            previous_number_of_points,
+            number_of_points,
+        );
+
+        Ok(changed_octant_indices)
+    }
+
+    /// Removes the points from the existing octree without changing its bounds or the root
+    /// transform, e.g. to withdraw a previously inserted point cloud file.
+    ///
+    /// Each point removes the closest vertex within `tolerance` along the path of its octant from
+    /// the root. Octants are not refilled from their descendants. The geometric error is scaled
+    /// to the number of remaining points. Fails without changes if any point matches no
+    /// vertex or no vertex remains. Returns the indices of all octants whose content changed.
+    pub fn remove_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        tolerance: f64,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        let positions = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Source(source_srs),
+            &self.root_transform,
+            &ProgressReporter::default(),
+        )?
+        .into_iter()
+        .map(|v| v.position)
+        .collect();
+        self.remove_positions(positions, tolerance)
+    }
+
+    /// Removes the points of a point cloud in local coordinates like
+    /// [`Tileset::remove_point_cloud`], where the tileset was built by
+    /// [`Tileset::from_local_point_cloud`] with the same placement.
+    pub fn remove_local_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        local_placement: LocalPlacement,
+        tolerance: f64,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        let positions = derive_local_vertices(
+            point_cloud,
+            PointCloudCoordinates::Local(local_placement.derive_transform()),
+            &self.root_transform,
+            &ProgressReporter::default(),
+        )?
+        .into_iter()
+        .map(|v| v.position)
+        .collect();
+        self.remove_positions(positions, tolerance)
+    }
+
+    /// Removes vertices at positions given in the local frame of the root transform, see
+    /// [`Tileset::remove_point_cloud`].
+    pub fn remove_positions(
+        &mut self,
+        positions: Vec<Point3<f64>>,
+        tolerance: f64,
+    ) -> Result<HashSet<OctantIndex>, Error> {
+        if tolerance <= 0.0 || tolerance.is_nan() {
+            return Err(NonPositiveParameter("tolerance"));
+        }
+        let bounds = *self.tiled_content.bounds();
+        let maximum_level = self
+            .tiled_content
+            .get_max_occupied_level()
+            .unwrap_or_default();
+
+        let (positions, outside_positions): (Vec<Point3<f64>>, Vec<Point3<f64>>) = positions
+            .into_iter()
+            .partition(|p| bounds.enclosing_cube().contains_point(p));
+        let mut unmatched_count = outside_positions.len();
+        let mut cells = self.tiled_content.cells().clone();
+        let previous_number_of_points: usize = cells.values().map(|c| c.len()).sum();
+        let mut changed_octant_indices: HashSet<OctantIndex> = HashSet::new();
+        let mut pending_positions: HashMap<OctantIndex, Vec<Point3<f64>>> =
+            HashMap::from([(OctantIndex::origin(), positions)]);
+
+        while !pending_positions.is_empty() {
+            let mut next_pending_positions: HashMap<OctantIndex, Vec<Point3<f64>>> = HashMap::new();
+
+            for (octant_index, octant_positions) in pending_positions {
+                let octant_positions = match cells.get_mut(&octant_index) {
+                    Some(cell) => {
+                        let cell_size = cell.len();
+                        let remaining_positions =
+                            remove_closest_vertices(cell, octant_positions, tolerance);
+                        if cell.len() < cell_size {
+                            changed_octant_indices.insert(octant_index);
+                        }
+                        remaining_positions
+                    }
+                    None => octant_positions,
+                };
+                if octant_index.level >= maximum_level {
+                    unmatched_count += octant_positions.len();
+                    continue;
+                }
+
+                let center = bounds.get_octant_bounding_cube(octant_index).center();
+                let child_base = octant_index.get_child_base_octant();
+                for position in octant_positions {
+                    let child_index = OctantIndex::new_unchecked(
+                        child_base.level,
+                        child_base.x + (position.x >= center.x) as u64,
+                        child_base.y + (position.y >= center.y) as u64,
+                        child_base.z + (position.z >= center.z) as u64,
+                    );
+                    next_pending_positions
+                        .entry(child_index)
+                        .or_default()
+                        .push(position);
+                }
+            }
+
+            pending_positions = next_pending_positions;
+        }
+
+        if unmatched_count > 0 {
+            return Err(PointsNotFound(unmatched_count));
+        }
+        cells.retain(|_, c| !c.is_empty());
+        if cells.is_empty() {
+            return Err(EmptyTileset());
+        }
+        let number_of_points = cells.values().map(|c| c.len()).sum();
+        let occupancy_graph = derive_occupancy_graph(cells.keys());
+        self.tiled_content = Octree::from_raw_parts(bounds, occupancy_graph, cells)?;
+        self.geometric_error = scale_geometric_error(
+            self.geometric_error,
+            previous_number_of_points,
+            number_of_points,
+        );
+
+        Ok(changed_octant_indices)
+    }
+}
+
+/// Coordinates of a point cloud that is transformed into the local frame of a tileset.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PointCloudCoordinates {
+    /// Coordinates in the source CRS, which are reprojected to ECEF.
+    Source(SpatialReferenceIdentifier),
+    /// Local coordinates without a CRS, which are placed in ECEF by the transform.
+    Local(Isometry3<f64>),
+}
+
+/// Transforms the points via ECEF into the local frame of the root transform, reprojecting them
+/// from a source CRS. Reports the reproject phase.
+pub(crate) fn derive_local_vertices(
+    point_cloud: epoint::PointCloud,
+    coordinates: PointCloudCoordinates,
+    root_transform: &Isometry3<f64>,
+    progress_reporter: &ProgressReporter,
+) -> Result<Vec<Vertex>, Error> {
+    let reproject_progress = progress_reporter.begin_phase(
+        ProgressPhase::Reproject,
+        point_cloud.point_data.height() as u64,
+    )?;
+    let point_cloud_normals: Vec<Option<Vector3<f32>>> = if has_normals(&point_cloud) {
+        let normal_rotation = match coordinates {
+            PointCloudCoordinates::Source(source_srs) => derive_normal_rotation(
+                source_srs,
+                point_cloud.point_data.get_local_center(),
+                root_transform,
+            )?,
+            PointCloudCoordinates::Local(placement_transform) => {
+                (root_transform.inverse() * placement_transform).rotation
+            }
+        };
+        get_all_normals(&point_cloud)?
+            .into_iter()
+            .map(|n| Some((normal_rotation * n.cast::<f64>()).cast::<f32>()))
+            .collect()
+    } else {
+        vec![None; point_cloud.point_data.height()]
+    };
+    let point_cloud_classifications: Vec<Option<u8>> = match get_all_classifications(&point_cloud) {
+        Ok(classifications) => classifications.into_iter().map(Some).collect(),
+        Err(_) => vec![None; point_cloud.point_data.height()],
+    };
+    let point_cloud_times: Vec<Option<f64>> = match point_cloud.point_data.get_all_timestamps() {
+        Ok(timestamps) => timestamps
+            .into_iter()
+            .map(|t| Some(t.timestamp() as f64 + t.timestamp_subsec_nanos() as f64 * 1e-9))
+            .collect(),
+        Err(_) => vec![None; point_cloud.point_data.height()],
+    };
+
+    let (point_cloud, placement_transform) = match coordinates {
+        PointCloudCoordinates::Source(source_srs) => {
+            let reprojected_point_cloud = reproject_point_cloud_with_progress(
+                point_cloud,
+                source_srs,
+                SpatialReferenceIdentifier::Epsg4978,
+                &reproject_progress,
+            )?;
+            (reprojected_point_cloud, Isometry3::identity())
+        }
+        PointCloudCoordinates::Local(placement_transform) => (point_cloud, placement_transform),
+    };
+
+    let local_transform = root_transform.inverse() * placement_transform;
+    let point_cloud_positions: Vec<Point3<f64>> = point_cloud
+        .point_data
+        .get_all_points()
+        .into_par_iter()
+        .map(|p| local_transform * p)
+        .collect();
+    let point_cloud_colors: Vec<Srgb<f32>> = match point_cloud.point_data.get_all_colors().ok() {
+        Some(colors) => colors.into_iter().map(|c| c.into_format()).collect(),
+        None => {
+            vec![DEFAULT_POINT_COLOR; point_cloud.point_data.height()]
+        }
+    };
+    let point_cloud_vertices: Vec<Vertex> = zip(point_cloud_positions, point_cloud_colors)
+        .zip(point_cloud_normals)
+        .zip(point_cloud_classifications)
+        .zip(point_cloud_times)
+        .map(|((((p, c), n), k), t)| Vertex {
+            position: p,
+            color: c,
+            normal: n,
+            classification: k,
+            time: t,
+        })
+        .collect();
+    reproject_progress.finish()?;
+
+    Ok(point_cloud_vertices)
+}
+
+/// Scales the geometric error from the previous to the current number of points, as the
+/// average point spacing it is derived from scales with the cube root of the point density.
+fn scale_geometric_error(
+    geometric_error: f64,
+    previous_number_of_points: usize,
+    number_of_points: usize,
+) -> f64 {
+    if previous_number_of_points == 0 || number_of_points == 0 {
+        return geometric_error;
+    }
+    geometric_error * (previous_number_of_points as f64 / number_of_points as f64).cbrt()
+}
+
+/// Fails if no point fits into an octant, as points would be passed on to child octants forever.
+fn check_maximum_points_per_octant(maximum_points_per_octant: u64) -> Result<(), Error> {
+    if maximum_points_per_octant == 0 {
+        return Err(NonPositiveParameter("maximum_points_per_octant"));
+    }
+    Ok(())
+}
+
+/// Derives the octree bounds whose enclosing cube is the given cube, which must have a power of
+/// two edge length as written for octrees built by this crate.
+fn derive_octree_bounds(enclosing_cube: &AxisAlignedBoundingCube) -> Result<OctreeBounds, Error> {
+    let edge_length = enclosing_cube.edge_length();
+    // powers of two have a zero mantissa
+    let mantissa_mask = (1u64 << (f64::MANTISSA_DIGITS - 1)) - 1;
+    if !edge_length.is_normal() || edge_length < 0.0 || edge_length.to_bits() & mantissa_mask != 0 {
+        return Err(InvalidEnclosingCube(edge_length));
+    }
+
+    // the bounds enlarge the extent of the box to the next strictly greater power of two
+    let half_extent = Vector3::from_element(edge_length * 0.375);
+    let bounding_box = AxisAlignedBoundingBox::new(
+        enclosing_cube.center() - half_extent,
+        enclosing_cube.center() + half_extent,
+    )
+    .map_err(ecoord::Error::from)?;
+    let bounds = OctreeBounds::new(bounding_box);
+
+    let derived_cube = bounds.enclosing_cube();
+    let deviation = (derived_cube.get_lower_bound() - enclosing_cube.get_lower_bound()).amax();
+    if derived_cube.edge_length() != edge_length || deviation > edge_length * f64::EPSILON * 4.0 {
+        return Err(InvalidEnclosingCube(edge_length));
+    }
+
+    Ok(bounds)
+}
+
+/// Removes the closest vertex within the tolerance for each position and returns the positions
+/// without a match. Vertices are looked up in a grid with the tolerance as cell size.
+fn remove_closest_vertices(
+    vertices: &mut Vec<Vertex>,
+    positions: Vec<Point3<f64>>,
+    tolerance: f64,
+) -> Vec<Point3<f64>> {
+    let grid_cell = |p: &Point3<f64>| (p.coords / tolerance).map(|c| c.floor() as i64);
+    let mut grid: HashMap<Vector3<i64>, Vec<usize>> = HashMap::new();
+    for (i, vertex) in vertices.iter().enumerate() {
+        grid.entry(grid_cell(&vertex.position)).or_default().push(i);
+    }
+
+    let mut removed = vec![false; vertices.len()];
+    let mut unmatched_positions = Vec::new();
+    for position in positions {
+        let cell = grid_cell(&position);
+        let closest_index = (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| Vector3::new(x, y, z))))
+            .filter_map(|offset| grid.get(&(cell + offset)))
+            .flatten()
+            .filter(|i| !removed[**i])
+            .map(|i| (*i, nalgebra::distance(&vertices[*i].position, &position)))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i);
+        match closest_index {
+            Some(i) => removed[i] = true,
+            None => unmatched_positions.push(position),
+        }
+    }
+
+    let mut removed = removed.into_iter();
+    vertices.retain(|_| !removed.next().unwrap_or_default());
+    unmatched_positions
+}
+
+/// Marks all children of octants with descendant content as occupied, as done when building
+/// the octree.
+fn derive_occupancy_graph<'a>(
+    cell_indices: impl Iterator<Item = &'a OctantIndex>,
+) -> OctreeOccupancyGraph {
+    let subdivided_octant_indices: HashSet<OctantIndex> = cell_indices
+        .filter_map(|i| i.get_parent())
+        .flat_map(|i| i.get_ancestors())
+        .collect();
+
+    let mut occupancy_graph = OctreeOccupancyGraph::new();
+    occupancy_graph.add_cell_occupancy(OctantIndex::origin());
+    for octant_index in subdivided_octant_indices {
+        octant_index
+            .get_children()
+            .into_iter()
+            .for_each(|c| occupancy_graph.add_cell_occupancy(c));
+    }
+
+    occupancy_graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::derive_point_cloud;
+
+    fn derive_tileset() -> Tileset {
+        let points = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            Point3::new(2.0, 2.0, 2.0),
+        ];
+        Tileset::from_local_point_cloud(
+            derive_point_cloud(points),
+            LocalPlacement::Identity,
+            TilesetAlignment::FrameAxes,
+            2,
+            Some(42),
+            &ProgressReporter::default(),
+        )
+        .expect("tileset")
+    }
+
+    #[test]
+    fn test_duplicate_points_are_kept_in_deepest_octant() {
+        let mut tileset = derive_tileset();
+
+        let duplicate_points = vec![Point3::new(0.5, 0.5, 0.5); 100];
+        tileset
+            .insert_local_point_cloud(
+                derive_point_cloud(duplicate_points),
+                LocalPlacement::Identity,
+                2,
+                Some(42),
+            )
+            .expect("inserted points");
+
+        let cells = tileset.tiled_content.cells();
+        assert_eq!(cells.values().flatten().count(), 103);
+        let (deepest_octant_index, deepest_cell) = cells
+            .iter()
+            .max_by_key(|(i, _)| i.level)
+            .expect("deepest octant");
+        assert_eq!(deepest_octant_index.level, MAXIMUM_OCTANT_LEVEL);
+        assert!(deepest_cell.len() > 2);
+    }
+
+    #[test]
+    fn test_zero_maximum_points_per_octant_is_rejected() {
+        let mut tileset = derive_tileset();
+
+        let result = tileset.insert_local_point_cloud(
+            derive_point_cloud(vec![Point3::new(0.5, 0.5, 0.5)]),
+            LocalPlacement::Identity,
+            0,
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(NonPositiveParameter("maximum_points_per_octant"))
+        ));
+        let result = Tileset::from_local_point_cloud(
+            derive_point_cloud(vec![Point3::new(0.5, 0.5, 0.5)]),
+            LocalPlacement::Identity,
+            TilesetAlignment::FrameAxes,
+            0,
+            None,
+            &ProgressReporter::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(NonPositiveParameter("maximum_points_per_octant"))
+        ));
+    }
+
+    #[test]
+    fn test_geometric_error_follows_point_count() {
+        let mut tileset = derive_tileset();
+        let initial_geometric_error = tileset.geometric_error;
+
+        let points: Vec<Point3<f64>> = (0..24)
+            .map(|i| Point3::new(0.1 * (i % 4) as f64, 0.1 * (i / 4) as f64, 0.5))
+            .collect();
+        tileset
+            .insert_local_point_cloud(
+                derive_point_cloud(points.clone()),
+                LocalPlacement::Identity,
+                2,
+                Some(42),
+            )
+            .expect("inserted points");
+        assert!(tileset.geometric_error < initial_geometric_error);
+
+        tileset
+            .remove_local_point_cloud(derive_point_cloud(points), LocalPlacement::Identity, 0.01)
+            .expect("removed points");
+        assert!((tileset.geometric_error - initial_geometric_error).abs() < 1e-12);
+    }
 }
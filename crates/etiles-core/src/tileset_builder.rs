@@ -0,0 +1,350 @@
+use crate::error::Error;
+use crate::error::Error::{EmptyTileset, MissingVertexAttribute};
+use crate::frame::{TilesetAlignment, TilesetFrame, derive_normal_rotation};
+use crate::progress::{ProgressPhase, ProgressReporter};
+use crate::tileset::{Tileset, Vertex};
+use ecoord::AxisAlignedBoundingBox;
+use eproj::{Projector, SpatialReferenceIdentifier};
+use nalgebra::{Isometry3, Point3, Vector3};
+
+/// Optional vertex attributes stored in the tileset.
+///
+/// Declared attributes must be provided by every vertex, while undeclared attributes are
+/// dropped, so that all tiles carry the same attributes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VertexSchema {
+    pub normal: bool,
+    pub classification: bool,
+    pub time: bool,
+}
+
+/// `TilesetBuilder` builds a tileset from vertices pushed in chunks, e.g. as generated by a
+/// simulation or received from a sensor stream, without materializing a point cloud first.
+///
+/// Vertex positions and normals are given in the source CRS and transformed into the local frame
+/// on building. By default, the frame is placed at the center of the vertices and the octree
+/// spans their bounding box.
+#[derive(Debug, Clone)]
+pub struct TilesetBuilder {
+    source_srs: SpatialReferenceIdentifier,
+    maximum_points_per_octant: u64,
+    seed_number: Option<u64>,
+    tileset_frame: TilesetFrame,
+    tileset_alignment: TilesetAlignment,
+    bounding_box: Option<AxisAlignedBoundingBox>,
+    vertex_schema: Option<VertexSchema>,
+    progress_reporter: ProgressReporter,
+    vertices: Vec<Vertex>,
+}
+
+impl TilesetBuilder {
+    pub fn new(source_srs: SpatialReferenceIdentifier, maximum_points_per_octant: u64) -> Self {
+        Self {
+            source_srs,
+            maximum_points_per_octant,
+            seed_number: None,
+            tileset_frame: TilesetFrame::default(),
+            tileset_alignment: TilesetAlignment::default(),
+            bounding_box: None,
+            vertex_schema: None,
+            progress_reporter: ProgressReporter::default(),
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Shuffles the vertices with the seed before distributing them into the octants.
+    pub fn with_seed_number(mut self, seed_number: u64) -> Self {
+        self.seed_number = Some(seed_number);
+        self
+    }
+
+    /// Stores the points in the local frame, e.g. at a fixed origin shared between tilesets.
+    pub fn with_frame(mut self, tileset_frame: TilesetFrame) -> Self {
+        self.tileset_frame = tileset_frame;
+        self
+    }
+
+    /// Builds the octree in the horizontal axes of the alignment, e.g. the principal axes of the
+    /// vertices, whose rotation is folded into the root transform.
+    pub fn with_alignment(mut self, tileset_alignment: TilesetAlignment) -> Self {
+        self.tileset_alignment = tileset_alignment;
+        self
+    }
+
+    /// Spans the octree by the bounding box given in the source CRS instead of the bounding box
+    /// of the vertices, which leaves room for inserting further points later. The center of the
+    /// bounding box is used as origin of the frame, unless a fixed origin is set.
+    pub fn with_bounding_box(mut self, bounding_box: AxisAlignedBoundingBox) -> Self {
+        self.bounding_box = Some(bounding_box);
+        self
+    }
+
+    /// Sets the optional attributes stored for every vertex. Otherwise, the attributes are kept
+    /// as pushed.
+    pub fn with_vertex_schema(mut self, vertex_schema: VertexSchema) -> Self {
+        self.vertex_schema = Some(vertex_schema);
+        self
+    }
+
+    /// Reports the reproject and octree phases on building and stops once the cancellation
+    /// token is cancelled.
+    pub fn with_progress_reporter(mut self, progress_reporter: ProgressReporter) -> Self {
+        self.progress_reporter = progress_reporter;
+        self
+    }
+
+    /// Returns the number of vertices pushed so far.
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn push(&mut self, vertex: Vertex) {
+        self.vertices.push(vertex);
+    }
+
+    /// Reprojects the vertices into the local frame of the root transform and builds the
+    /// octree. Fails if no vertex was pushed, the alignment is not supported in the frame, or a
+    /// vertex lacks an attribute of the schema or lies outside the bounding box.
+    pub fn build(self) -> Result<Tileset, Error> {
+        if self.vertices.is_empty() {
+            return Err(EmptyTileset());
+        }
+        self.tileset_frame.check_alignment(self.tileset_alignment)?;
+        let mut vertices = self.vertices;
+        if let Some(vertex_schema) = self.vertex_schema {
+            apply_vertex_schema(&mut vertices, vertex_schema)?;
+        }
+
+        let center = self
+            .bounding_box
+            .map(|b| b.get_center())
+            .unwrap_or_else(|| derive_center(&vertices));
+        let root_transform = self
+            .tileset_frame
+            .derive_root_transform(self.source_srs, center)?;
+        let projector = Projector::new(self.source_srs, SpatialReferenceIdentifier::Epsg4978)?;
+
+        let reproject_progress = self
+            .progress_reporter
+            .begin_phase(ProgressPhase::Reproject, vertices.len() as u64)?;
+        let positions = projector.convert_points(vertices.iter().map(|v| v.position).collect())?;
+        let local_transform = root_transform.inverse();
+        for (vertex, position) in vertices.iter_mut().zip(positions) {
+            vertex.position = local_transform * position;
+        }
+        if vertices.iter().any(|v| v.normal.is_some()) {
+            let normal_rotation = derive_normal_rotation(self.source_srs, center, &root_transform)?;
+            for normal in vertices.iter_mut().filter_map(|v| v.normal.as_mut()) {
+                *normal = (normal_rotation * normal.cast::<f64>()).cast::<f32>();
+            }
+        }
+        let root_transform = root_transform * self.tileset_alignment.align_vertices(&mut vertices);
+        let local_transform = root_transform.inverse();
+        let local_bounding_box = self
+            .bounding_box
+            .map(|b| derive_local_bounding_box(&b, &projector, &local_transform))
+            .transpose()?;
+        reproject_progress.finish()?;
+
+        Tileset::from_local_vertices(
+            vertices,
+            root_transform,
+            local_bounding_box,
+            self.maximum_points_per_octant,
+            self.seed_number,
+            &self.progress_reporter,
+        )
+    }
+}
+
+/// Appends a chunk of vertices.
+impl Extend<Vertex> for TilesetBuilder {
+    fn extend<T: IntoIterator<Item = Vertex>>(&mut self, vertices: T) {
+        self.vertices.extend(vertices);
+    }
+}
+
+/// Drops the attributes not declared by the schema and fails if a vertex lacks a declared one.
+fn apply_vertex_schema(vertices: &mut [Vertex], vertex_schema: VertexSchema) -> Result<(), Error> {
+    let count_missing =
+        |is_missing: fn(&Vertex) -> bool| vertices.iter().filter(|v| is_missing(v)).count();
+    for (declared, attribute, missing_count) in [
+        (
+            vertex_schema.normal,
+            "normal",
+            count_missing(|v| v.normal.is_none()),
+        ),
+        (
+            vertex_schema.classification,
+            "classification",
+            count_missing(|v| v.classification.is_none()),
+        ),
+        (
+            vertex_schema.time,
+            "time",
+            count_missing(|v| v.time.is_none()),
+        ),
+    ] {
+        if declared && missing_count > 0 {
+            return Err(MissingVertexAttribute(attribute, missing_count));
+        }
+    }
+
+    for vertex in vertices.iter_mut() {
+        if !vertex_schema.normal {
+            vertex.normal = None;
+        }
+        if !vertex_schema.classification {
+            vertex.classification = None;
+        }
+        if !vertex_schema.time {
+            vertex.time = None;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the center of the bounding box of the vertex positions.
+fn derive_center(vertices: &[Vertex]) -> Point3<f64> {
+    let (min, max) = vertices.iter().fold(
+        (vertices[0].position, vertices[0].position),
+        |(min, max), v| (min.inf(&v.position), max.sup(&v.position)),
+    );
+    nalgebra::center(&min, &max)
+}
+
+/// Derives the bounding box in the local frame enclosing the bounding box given in the source
+/// CRS, sampled at its corners, edge and face centers to account for the curvature of the Earth.
+fn derive_local_bounding_box(
+    bounding_box: &AxisAlignedBoundingBox,
+    projector: &Projector,
+    local_transform: &Isometry3<f64>,
+) -> Result<AxisAlignedBoundingBox, Error> {
+    let (lower_bound, diagonal) = (bounding_box.lower_bound(), bounding_box.diagonal());
+    let sample_points: Vec<Point3<f64>> = (0..27)
+        .map(|i| {
+            let factors = Vector3::new((i % 3) as f64, ((i / 3) % 3) as f64, (i / 9) as f64) / 2.0;
+            lower_bound + diagonal.component_mul(&factors)
+        })
+        .collect();
+    let local_points: Vec<Point3<f64>> = projector
+        .convert_points(sample_points)?
+        .into_iter()
+        .map(|p| local_transform * p)
+        .collect();
+
+    let (min, max) = local_points
+        .iter()
+        .fold((local_points[0], local_points[0]), |(min, max), p| {
+            (min.inf(p), max.sup(p))
+        });
+    let local_bounding_box = AxisAlignedBoundingBox::new(min, max).map_err(ecoord::Error::from)?;
+    Ok(local_bounding_box)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error::PointsOutsideBounds;
+    use palette::Srgb;
+
+    /// ECEF position of the first vertex on the earth's surface.
+    const BASE_POSITION: Point3<f64> = Point3::new(4_157_000.0, 671_000.0, 4_774_000.0);
+
+    /// Creates a builder with vertices on a grid of 10 x 10 x 10 meters in ECEF coordinates,
+    /// each with a classification and a time.
+    fn derive_tileset_builder() -> TilesetBuilder {
+        let mut tileset_builder =
+            TilesetBuilder::new(SpatialReferenceIdentifier::Epsg4978, 100).with_seed_number(42);
+        tileset_builder.extend((0..1_000).map(|i| Vertex {
+            position: BASE_POSITION
+                + Vector3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64),
+            color: Srgb::new(0.2, 0.4, 0.6),
+            normal: None,
+            classification: Some(2),
+            time: Some(1.7e9 + i as f64),
+        }));
+        tileset_builder
+    }
+
+    fn derive_bounding_box(upper_offset: Vector3<f64>) -> AxisAlignedBoundingBox {
+        AxisAlignedBoundingBox::new(
+            BASE_POSITION - Vector3::repeat(0.5),
+            BASE_POSITION + upper_offset,
+        )
+        .expect("bounding box")
+    }
+
+    #[test]
+    fn test_missing_declared_attribute_is_rejected() {
+        let mut tileset_builder = derive_tileset_builder();
+        tileset_builder.push(Vertex {
+            position: BASE_POSITION,
+            color: Srgb::new(0.2, 0.4, 0.6),
+            normal: None,
+            classification: None,
+            time: Some(1.7e9),
+        });
+
+        let result = tileset_builder
+            .with_vertex_schema(VertexSchema {
+                classification: true,
+                time: true,
+                ..Default::default()
+            })
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(MissingVertexAttribute("classification", 1))
+        ));
+    }
+
+    #[test]
+    fn test_undeclared_attributes_are_dropped() {
+        let tileset = derive_tileset_builder()
+            .with_vertex_schema(VertexSchema {
+                time: true,
+                ..Default::default()
+            })
+            .build()
+            .expect("tileset");
+
+        let vertices: Vec<&Vertex> = tileset.tiled_content.cells().values().flatten().collect();
+        assert_eq!(vertices.len(), 1_000);
+        assert!(vertices.iter().all(|v| v.classification.is_none()));
+        assert!(vertices.iter().all(|v| v.time.is_some()));
+    }
+
+    #[test]
+    fn test_points_outside_bounding_box_are_rejected() {
+        let result = derive_tileset_builder()
+            .with_bounding_box(derive_bounding_box(Vector3::new(4.5, 9.5, 9.5)))
+            .build();
+
+        assert!(matches!(result, Err(PointsOutsideBounds(500))));
+    }
+
+    #[test]
+    fn test_octree_spans_bounding_box() {
+        let tileset = derive_tileset_builder()
+            .with_bounding_box(derive_bounding_box(Vector3::repeat(99.5)))
+            .build()
+            .expect("tileset");
+
+        let bounding_box = tileset.tiled_content.bounds().bounding_box();
+        assert!(bounding_box.diagonal().min() >= 100.0);
+    }
+
+    #[test]
+    fn test_empty_builder_is_rejected() {
+        let result = TilesetBuilder::new(SpatialReferenceIdentifier::Epsg4978, 100).build();
+
+        assert!(matches!(result, Err(EmptyTileset())));
+    }
+}
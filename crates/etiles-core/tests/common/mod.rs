@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+use ecoord::TransformTree;
+use epoint::{PointCloud, PointCloudInfo, PointDataColumns};
+use nalgebra::Point3;
+
+pub const SEED_NUMBER: u64 = 42;
+
+/// Points on a regular grid of `size` points per axis with the spacing, starting at the origin.
+pub fn derive_grid_points(origin: Point3<f64>, size: usize, spacing: f64) -> Vec<Point3<f64>> {
+    (0..size.pow(3))
+        .map(|i| {
+            let (x, y, z) = (i % size, (i / size) % size, i / size.pow(2));
+            origin + nalgebra::Vector3::new(x as f64, y as f64, z as f64) * spacing
+        })
+        .collect()
+}
+
+/// Creates a point cloud with only positions.
+pub fn derive_point_cloud(points: Vec<Point3<f64>>) -> PointCloud {
+    let point_data =
+        PointDataColumns::new(points, None, None, None, None, None, None).expect("point data");
+    PointCloud::new(
+        point_data,
+        PointCloudInfo::new(None),
+        TransformTree::default(),
+    )
+    .expect("point cloud")
+}
+
+/// Returns the positions sorted lexicographically, for comparing point sets regardless of the
+/// order of the points.
+pub fn sort_points(mut points: Vec<Point3<f64>>) -> Vec<Point3<f64>> {
+    points.sort_by(|a, b| {
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| a.total_cmp(b))
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    points
+}
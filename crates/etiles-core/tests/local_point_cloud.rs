@@ -0,0 +1,115 @@
+mod common;
+
+use common::{SEED_NUMBER, derive_grid_points, derive_point_cloud, sort_points};
+use etiles_core::{
+    LevelSelection, LocalPlacement, ProgressReporter, Tileset, TilesetAlignment,
+    extract_local_point_cloud,
+};
+use nalgebra::{Point3, Vector3};
+
+const PLACEMENT: LocalPlacement = LocalPlacement::Geographic {
+    longitude: 11.57,
+    latitude: 48.15,
+    height: 520.0,
+    heading: 30.0,
+};
+
+fn derive_tileset(points: Vec<Point3<f64>>, local_placement: LocalPlacement) -> Tileset {
+    Tileset::from_local_point_cloud(
+        derive_point_cloud(points),
+        local_placement,
+        TilesetAlignment::FrameAxes,
+        100,
+        Some(SEED_NUMBER),
+        &ProgressReporter::default(),
+    )
+    .expect("tileset")
+}
+
+fn derive_positions(tileset: &Tileset) -> Vec<Point3<f64>> {
+    tileset
+        .tiled_content
+        .cells()
+        .values()
+        .flatten()
+        .map(|v| v.position)
+        .collect()
+}
+
+#[test]
+fn test_local_vertices_are_centered() {
+    let offset = Point3::new(1_000.0, 2_000.0, 10.0);
+    let tileset = derive_tileset(
+        derive_grid_points(offset, 10, 0.5),
+        LocalPlacement::Identity,
+    );
+
+    let center = offset + Vector3::repeat(2.25);
+    assert!((tileset.root_transform.translation.vector - center.coords).norm() < 1e-9);
+    assert!(
+        derive_positions(&tileset)
+            .iter()
+            .all(|p| p.coords.amax() <= 2.25 + 1e-9)
+    );
+}
+
+#[test]
+fn test_identity_placement_has_no_heights() {
+    let tileset = derive_tileset(
+        derive_grid_points(Point3::origin(), 10, 0.5),
+        LocalPlacement::Identity,
+    );
+
+    assert_eq!(tileset.derive_origin_height(), None);
+    assert!(
+        tileset
+            .derive_octant_statistics()
+            .values()
+            .all(|s| s.minimum_height.is_none() && s.maximum_height.is_none())
+    );
+}
+
+#[test]
+fn test_geographic_placement_has_heights() {
+    let tileset = derive_tileset(derive_grid_points(Point3::origin(), 10, 0.5), PLACEMENT);
+
+    let origin_height = tileset.derive_origin_height().expect("origin height");
+    assert!((origin_height - 522.25).abs() < 1e-3);
+}
+
+#[test]
+fn test_local_insert_and_extract_keep_local_coordinates() {
+    let points = derive_grid_points(Point3::new(100.0, 200.0, 0.0), 10, 0.5);
+    let inserted_points = derive_grid_points(Point3::new(100.25, 200.25, 0.25), 4, 0.5);
+    let mut tileset = derive_tileset(points.clone(), PLACEMENT);
+
+    tileset
+        .insert_local_point_cloud(
+            derive_point_cloud(inserted_points.clone()),
+            PLACEMENT,
+            100,
+            Some(SEED_NUMBER),
+        )
+        .expect("insert");
+    let point_cloud =
+        extract_local_point_cloud(&tileset, PLACEMENT, LevelSelection::All).expect("point cloud");
+
+    let expected_points = sort_points(points.into_iter().chain(inserted_points).collect());
+    let extracted_points = sort_points(point_cloud.point_data.get_all_points());
+    assert_eq!(extracted_points.len(), expected_points.len());
+    for (extracted_point, expected_point) in extracted_points.iter().zip(&expected_points) {
+        assert!((extracted_point - expected_point).norm() < 1e-6);
+    }
+}
+
+#[test]
+fn test_local_removal_removes_matching_points() {
+    let points = derive_grid_points(Point3::new(100.0, 200.0, 0.0), 10, 0.5);
+    let mut tileset = derive_tileset(points.clone(), PLACEMENT);
+
+    tileset
+        .remove_local_point_cloud(derive_point_cloud(points[..100].to_vec()), PLACEMENT, 1e-3)
+        .expect("removal");
+
+    assert_eq!(derive_positions(&tileset).len(), points.len() - 100);
+}
@@ -0,0 +1,31 @@
+use crate::Error;
+use chrono::{DateTime, Utc};
+
+/// Modification time and ownership written into the header of every archive entry.
+///
+/// The defaults of the Unix epoch, ids of zero and empty names make the archive independent of
+/// the time and user of writing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ArchiveHeaderInfo {
+    pub modification_time: DateTime<Utc>,
+    pub user_id: u64,
+    pub group_id: u64,
+    pub user_name: String,
+    pub group_name: String,
+}
+
+impl ArchiveHeaderInfo {
+    pub(crate) fn create_header(&self, size: usize) -> Result<tar::Header, Error> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(size as u64);
+        header.set_mode(0o664);
+        header.set_mtime(self.modification_time.timestamp().max(0) as u64);
+        header.set_uid(self.user_id);
+        header.set_gid(self.group_id);
+        header.set_username(&self.user_name)?;
+        header.set_groupname(&self.group_name)?;
+        header.set_cksum();
+
+        Ok(header)
+    }
+}
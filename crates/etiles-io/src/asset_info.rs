@@ -0,0 +1,147 @@
+use crate::documents::asset::Asset;
+use crate::documents::tileset::TilesetDocument;
+use serde_json::{Map, Value};
+
+/// Name and version of this crate, written as generator of tilesets and glTF tiles.
+pub const GENERATOR: &str = concat!("etiles ", env!("CARGO_PKG_VERSION"));
+
+const EXTRAS_KEY_COPYRIGHT: &str = "copyright";
+
+/// Descriptive information written into the asset of the tileset and of every glTF tile.
+///
+/// As the asset of a tileset has no copyright property, the copyright is added to its extras.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AssetInfo {
+    pub tileset_version: Option<String>,
+    pub copyright: Option<String>,
+    pub extras: Map<String, Value>,
+    pub extensions_used: Vec<String>,
+    pub extensions_required: Vec<String>,
+}
+
+impl AssetInfo {
+    /// Recovers the asset information from a tileset document written by this crate.
+    pub(crate) fn from_tileset_document(tileset_document: &TilesetDocument) -> Self {
+        let mut extras = tileset_document.asset.extras.clone();
+        let copyright = match extras.remove(EXTRAS_KEY_COPYRIGHT) {
+            Some(Value::String(copyright)) => Some(copyright),
+            Some(other) => {
+                extras.insert(EXTRAS_KEY_COPYRIGHT.to_string(), other);
+                None
+            }
+            None => None,
+        };
+
+        Self {
+            tileset_version: tileset_document.asset.tileset_version.clone(),
+            copyright,
+            extras,
+            extensions_used: tileset_document.extensions_used.clone(),
+            extensions_required: tileset_document.extensions_required.clone(),
+        }
+    }
+
+    /// Sets the generator, version, extras and extensions of the tileset document.
+    pub(crate) fn apply_to_tileset_document(&self, tileset_document: &mut TilesetDocument) {
+        let mut extras = self.extras.clone();
+        if let Some(copyright) = &self.copyright {
+            extras.insert(
+                EXTRAS_KEY_COPYRIGHT.to_string(),
+                Value::from(copyright.as_str()),
+            );
+        }
+        tileset_document.asset = Asset {
+            tileset_version: self.tileset_version.clone(),
+            generator: Some(GENERATOR.to_string()),
+            extras,
+            ..tileset_document.asset.clone()
+        };
+
+        // required extensions must also be listed as used
+        let mut extensions_used = self.extensions_used.clone();
+        for extension in &self.extensions_required {
+            if !extensions_used.contains(extension) {
+                extensions_used.push(extension.clone());
+            }
+        }
+        tileset_document.extensions_used = extensions_used;
+        tileset_document.extensions_required = self.extensions_required.clone();
+    }
+
+    /// Returns the asset of a glTF tile with copyright, generator and extras.
+    pub(crate) fn derive_gltf_asset(&self) -> Result<gltf_json::Asset, serde_json::Error> {
+        let extras = if self.extras.is_empty() {
+            None
+        } else {
+            Some(serde_json::value::to_raw_value(&self.extras)?)
+        };
+
+        Ok(gltf_json::Asset {
+            copyright: self.copyright.clone(),
+            generator: Some(GENERATOR.to_string()),
+            extras,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::documents::bounding_volume::BoundingVolume;
+    use crate::documents::tile::Tile;
+
+    fn derive_tileset_document() -> TilesetDocument {
+        let root_tile = Tile {
+            geometric_error: 1.0,
+            content: None,
+            contents: vec![],
+            bounding_volume: BoundingVolume::Box([0.0; 12]),
+            children: vec![],
+            transform: None,
+            refine: None,
+            implicit_tiling: None,
+        };
+
+        TilesetDocument::new(root_tile, None, 10.0)
+    }
+
+    #[test]
+    fn test_asset_info_survives_tileset_document() {
+        let asset_info = AssetInfo {
+            tileset_version: Some("2024-05".to_string()),
+            copyright: Some("City Survey Office".to_string()),
+            extras: Map::from_iter([("campaign".to_string(), Value::from("spring"))]),
+            extensions_used: vec!["EXT_a".to_string(), "EXT_b".to_string()],
+            extensions_required: vec!["EXT_b".to_string()],
+        };
+        let mut tileset_document = derive_tileset_document();
+
+        asset_info.apply_to_tileset_document(&mut tileset_document);
+        let tileset_document: TilesetDocument = serde_json::from_slice(
+            &serde_json::to_vec(&tileset_document).expect("serialized document"),
+        )
+        .expect("deserialized document");
+
+        assert_eq!(tileset_document.asset.generator.as_deref(), Some(GENERATOR));
+        assert_eq!(
+            AssetInfo::from_tileset_document(&tileset_document),
+            asset_info
+        );
+    }
+
+    #[test]
+    fn test_required_extensions_are_added_to_used_extensions() {
+        let asset_info = AssetInfo {
+            extensions_used: vec!["EXT_a".to_string()],
+            extensions_required: vec!["EXT_b".to_string(), "EXT_a".to_string()],
+            ..Default::default()
+        };
+        let mut tileset_document = derive_tileset_document();
+
+        asset_info.apply_to_tileset_document(&mut tileset_document);
+
+        assert_eq!(tileset_document.extensions_used, vec!["EXT_a", "EXT_b"]);
+        assert_eq!(tileset_document.extensions_required, vec!["EXT_b", "EXT_a"]);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::Error::{InvalidFileExtension, NoFileExtension};
+use crate::error::Error;
+use crate::{
+    FILE_EXTENSION_ETILES_GZIP, FILE_EXTENSION_ETILES_UNCOMPRESSED, FILE_EXTENSION_ETILES_ZSTD,
+    FILE_EXTENSION_PRECOMPRESSED_ENTRY,
+};
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use flate2::write::GzEncoder;
+use flate2::{Compression, GzBuilder};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC_NUMBER_GZIP: [u8; 2] = [0x1f, 0x8b];
+const MAGIC_NUMBER_ZSTD: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_COMPRESSION_LEVEL: i32 = 9;
+
+/// Compression applied to the archive as a whole.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ArchiveCompression {
+    /// Plain TAR archive (`.tar`).
+    #[default]
+    None,
+    /// Gzip-compressed TAR archive (`.tar.gz`).
+    Gzip,
+    /// Zstandard-compressed TAR archive (`.tar.zst`).
+    Zstd,
+}
+
+impl ArchiveCompression {
+    /// Derives the compression from the file extension of the path, which must be `.tar`,
+    /// `.tar.gz` or `.tar.zst`.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let extension = path.extension().ok_or(NoFileExtension())?;
+        let file_name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default();
+
+        [Self::None, Self::Gzip, Self::Zstd]
+            .into_iter()
+            .find(|c| file_name.ends_with(&format!(".{}", c.file_extension())))
+            .ok_or_else(|| InvalidFileExtension(extension.to_str().unwrap_or_default().to_string()))
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Self::None => FILE_EXTENSION_ETILES_UNCOMPRESSED,
+            Self::Gzip => FILE_EXTENSION_ETILES_GZIP,
+            Self::Zstd => FILE_EXTENSION_ETILES_ZSTD,
+        }
+    }
+
+    /// Returns the file name of the path without the archive extension.
+    pub(crate) fn derive_file_stem(path: &Path) -> Option<&str> {
+        let file_name = path.file_name()?.to_str()?;
+        let compression = Self::from_path(path).ok()?;
+        file_name.strip_suffix(&format!(".{}", compression.file_extension()))
+    }
+
+    /// Detects the compression from the leading bytes of an archive.
+    fn detect(leading_bytes: &[u8]) -> Self {
+        if leading_bytes.starts_with(&MAGIC_NUMBER_GZIP) {
+            Self::Gzip
+        } else if leading_bytes.starts_with(&MAGIC_NUMBER_ZSTD) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Wraps the reader of an archive into a decoder matching its compression, which is detected
+/// from its leading bytes.
+pub(crate) fn decompress_archive<'a, R: Read + 'a>(reader: R) -> Result<Box<dyn Read + 'a>, Error> {
+    let mut reader = BufReader::new(reader);
+    let compression = ArchiveCompression::detect(reader.fill_buf()?);
+
+    Ok(match compression {
+        ArchiveCompression::None => Box::new(reader),
+        ArchiveCompression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        ArchiveCompression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+    })
+}
+
+/// Encoder of an archive matching its compression, which must be finished once the archive is
+/// complete.
+pub(crate) enum ArchiveEncoder<W: Write> {
+    None(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> ArchiveEncoder<W> {
+    pub(crate) fn new(writer: W, compression: ArchiveCompression) -> Result<Self, Error> {
+        Ok(match compression {
+            ArchiveCompression::None => Self::None(writer),
+            // the default gzip header carries no modification time, keeping the output
+            // reproducible
+            ArchiveCompression::Gzip => Self::Gzip(GzEncoder::new(writer, Compression::default())),
+            ArchiveCompression::Zstd => {
+                Self::Zstd(zstd::Encoder::new(writer, ZSTD_COMPRESSION_LEVEL)?)
+            }
+        })
+    }
+
+    /// Returns the underlying writer, which has received the data encoded so far.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        match self {
+            Self::None(writer) => writer,
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<W, Error> {
+        let mut writer = match self {
+            Self::None(writer) => writer,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for ArchiveEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::None(writer) => writer.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::None(writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Appends the extension `.gz` of precompressed entries to the path, as expected by static
+/// hosting serving them with `Content-Encoding: gzip`.
+pub(crate) fn derive_precompressed_path(path: &Path) -> PathBuf {
+    let mut precompressed_path = path.as_os_str().to_owned();
+    precompressed_path.push(format!(".{FILE_EXTENSION_PRECOMPRESSED_ENTRY}"));
+    precompressed_path.into()
+}
+
+/// Restores the path and content of a precompressed entry, or returns `None` if the entry is not
+/// precompressed.
+pub(crate) fn decompress_entry(
+    path: &Path,
+    buffer: &[u8],
+) -> Result<Option<(PathBuf, Vec<u8>)>, Error> {
+    if path.extension().and_then(|e| e.to_str()) != Some(FILE_EXTENSION_PRECOMPRESSED_ENTRY) {
+        return Ok(None);
+    }
+
+    Ok(Some((
+        path.with_extension(""),
+        gunzip_entry_content(buffer)?,
+    )))
+}
+
+pub(crate) fn gzip_entry_content(buffer: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzBuilder::new().write(Vec::new(), Compression::best());
+    encoder.write_all(buffer)?;
+    Ok(encoder.finish()?)
+}
+
+pub(crate) fn gunzip_entry_content(buffer: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decompressed_buffer: Vec<u8> = Vec::new();
+    GzDecoder::new(buffer).read_to_end(&mut decompressed_buffer)?;
+    Ok(decompressed_buffer)
+}
@@ -0,0 +1,136 @@
+use crate::documents::metadata::{ClassProperty, ComponentType, MetadataClass, MetadataEntity};
+use crate::error::Error;
+use crate::error::Error::{InvalidContentLayerName, UnassignedContentLayerPoints};
+use etiles_core::Vertex;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+pub const METADATA_CLASS_CONTENT_LAYER: &str = "contentLayer";
+pub const METADATA_PROPERTY_NAME: &str = "name";
+pub const METADATA_PROPERTY_CLASSIFICATIONS: &str = "classifications";
+
+/// Content of each tile written as separate glTF file, holding the points of the given ASPRS
+/// classification codes.
+///
+/// A layer without classification codes collects all points not assigned to another layer,
+/// including points without classification. The name is used as directory of the layer's
+/// content and as name of its metadata group, allowing clients to toggle layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentLayer {
+    pub name: String,
+    pub classifications: Vec<u8>,
+}
+
+impl ContentLayer {
+    pub fn new(name: impl Into<String>, classifications: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            classifications,
+        }
+    }
+
+    /// Returns true, if the layer collects the points not assigned to another layer.
+    pub fn is_remainder(&self) -> bool {
+        self.classifications.is_empty()
+    }
+
+    pub(crate) fn derive_group_entity(&self) -> MetadataEntity {
+        let mut properties = BTreeMap::from([(
+            METADATA_PROPERTY_NAME.to_string(),
+            Value::from(self.name.as_str()),
+        )]);
+        if !self.classifications.is_empty() {
+            properties.insert(
+                METADATA_PROPERTY_CLASSIFICATIONS.to_string(),
+                Value::from(self.classifications.clone()),
+            );
+        }
+
+        MetadataEntity {
+            class: METADATA_CLASS_CONTENT_LAYER.to_string(),
+            properties,
+        }
+    }
+
+    /// Recovers the layer from a group entity written by [`ContentLayer::derive_group_entity`].
+    pub(crate) fn from_group_entity(entity: &MetadataEntity) -> Option<Self> {
+        if entity.class != METADATA_CLASS_CONTENT_LAYER {
+            return None;
+        }
+        let name = entity.properties.get(METADATA_PROPERTY_NAME)?.as_str()?;
+        let classifications = match entity.properties.get(METADATA_PROPERTY_CLASSIFICATIONS) {
+            Some(values) => values
+                .as_array()?
+                .iter()
+                .map(|v| v.as_u64().and_then(|v| u8::try_from(v).ok()))
+                .collect::<Option<Vec<u8>>>()?,
+            None => Vec::new(),
+        };
+
+        Some(Self::new(name, classifications))
+    }
+}
+
+pub(crate) fn derive_content_layer_class() -> MetadataClass {
+    MetadataClass {
+        description: Some("Layer of tile contents split by classification.".to_string()),
+        properties: BTreeMap::from([
+            (METADATA_PROPERTY_NAME.to_string(), ClassProperty::string()),
+            (
+                METADATA_PROPERTY_CLASSIFICATIONS.to_string(),
+                ClassProperty {
+                    array: true,
+                    ..ClassProperty::scalar(ComponentType::Uint8)
+                },
+            ),
+        ]),
+    }
+}
+
+/// Returns the index of the layer the vertex is assigned to, preferring layers listing its
+/// classification over a remainder layer.
+pub(crate) fn derive_content_layer_index(
+    content_layers: &[ContentLayer],
+    vertex: &Vertex,
+) -> Option<usize> {
+    vertex
+        .classification
+        .and_then(|c| {
+            content_layers
+                .iter()
+                .position(|l| l.classifications.contains(&c))
+        })
+        .or_else(|| content_layers.iter().position(|l| l.is_remainder()))
+}
+
+/// Checks that the layer names are unique and usable as directory names, and that every vertex
+/// is assigned to a layer.
+pub(crate) fn validate_content_layers<'a>(
+    content_layers: &[ContentLayer],
+    vertices: impl Iterator<Item = &'a Vertex>,
+) -> Result<(), Error> {
+    if content_layers.is_empty() {
+        return Ok(());
+    }
+
+    let mut names: HashSet<&str> = HashSet::new();
+    for content_layer in content_layers {
+        let is_valid = !content_layer.name.is_empty()
+            && content_layer
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !is_valid || !names.insert(&content_layer.name) {
+            return Err(InvalidContentLayerName(content_layer.name.clone()));
+        }
+    }
+
+    let unassigned_count = vertices
+        .filter(|v| derive_content_layer_index(content_layers, v).is_none())
+        .count();
+    if unassigned_count > 0 {
+        return Err(UnassignedContentLayerPoints(unassigned_count));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Listing of the archive entries with their sizes and hashes for integrity verification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDocument {
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex-encoded SHA-256 hash of the entry.
+    pub sha256: String,
+}
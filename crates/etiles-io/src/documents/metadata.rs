@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Metadata schema defining the classes of tileset, group and tile metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub classes: BTreeMap<String, MetadataClass>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataClass {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, ClassProperty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassProperty {
+    #[serde(rename = "type")]
+    pub element_type: ElementType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_type: Option<ComponentType>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub array: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic: Option<String>,
+}
+
+impl ClassProperty {
+    pub fn scalar(component_type: ComponentType) -> Self {
+        Self {
+            element_type: ElementType::Scalar,
+            component_type: Some(component_type),
+            array: false,
+            semantic: None,
+        }
+    }
+
+    pub fn string() -> Self {
+        Self {
+            element_type: ElementType::String,
+            component_type: None,
+            array: false,
+            semantic: None,
+        }
+    }
+
+    pub fn with_semantic(mut self, semantic: &str) -> Self {
+        self.semantic = Some(semantic.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ElementType {
+    Scalar,
+    Vec2,
+    Vec3,
+    Vec4,
+    String,
+    Boolean,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ComponentType {
+    Uint8,
+    Int32,
+    Uint32,
+    Int64,
+    Uint64,
+    Float32,
+    Float64,
+}
+
+/// Metadata of the tileset or a group, conforming to a class of the schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataEntity {
+    pub class: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// Property table of binary metadata values, e.g. of the tiles of a subtree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyTable {
+    pub class: String,
+    pub count: u32,
+    pub properties: BTreeMap<String, PropertyTableProperty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyTableProperty {
+    /// Index of the buffer view containing the values.
+    pub values: u32,
+}
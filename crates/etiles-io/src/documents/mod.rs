@@ -2,5 +2,8 @@ pub mod asset;
 pub mod bounding_volume;
 pub mod content;
 pub mod implicit_tiling;
+pub mod manifest;
+pub mod metadata;
+pub mod style;
 pub mod tile;
 pub mod tileset;
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Declarative style as defined by the 3D Tiles styling language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleDocument {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defines: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Conditions>,
+}
+
+/// Expressions evaluated in order, of which the first with a true condition applies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    pub conditions: Vec<[String; 2]>,
+}
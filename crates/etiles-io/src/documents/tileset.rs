@@ -1,4 +1,5 @@
 use crate::documents::asset::{Asset, Version};
+use crate::documents::metadata::{MetadataEntity, Schema};
 use crate::documents::tile::{Refinement, Tile};
 use nalgebra::Isometry3;
 use serde::{Deserialize, Serialize};
@@ -7,6 +8,16 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub struct TilesetDocument {
     pub asset: Asset,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions_used: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions_required: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataEntity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<MetadataEntity>,
     pub geometric_error: f64,
     pub root: Tile,
 }
@@ -23,11 +34,13 @@ impl TilesetDocument {
         root_tile.transform = transform_values;
         root_tile.refine = Some(Refinement::Add);
 
-        let asset = Asset {
-            version: Version::V1_1,
-        };
         Self {
-            asset,
+            asset: Asset::new(Version::V1_1),
+            extensions_used: Vec::new(),
+            extensions_required: Vec::new(),
+            schema: None,
+            metadata: None,
+            groups: Vec::new(),
             geometric_error,
             root: root_tile,
         }
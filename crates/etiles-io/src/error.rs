@@ -1,3 +1,4 @@
+use ecoord::octree::OctantIndex;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +9,8 @@ pub enum Error {
     #[error(transparent)]
     EcoordError(#[from] ecoord::Error),
     #[error(transparent)]
+    EprojError(#[from] eproj::Error),
+    #[error(transparent)]
     EpointError(#[from] epoint::Error),
     #[error(transparent)]
     EpointTransformError(#[from] epoint::transform::Error),
@@ -15,6 +18,15 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Parsing(#[from] serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "object-store")]
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+    #[cfg(feature = "object-store")]
+    #[error(transparent)]
+    ObjectStorePath(#[from] object_store::path::Error),
 
     #[error("file extension `{0}` is invalid")]
     InvalidFileExtension(String),
@@ -24,4 +36,48 @@ pub enum Error {
     NoFileExtension(),
     #[error("file extension is invalid")]
     PointDataFileNotFound(),
+
+    #[error("glTF encoding failed")]
+    GltfEncoding(#[from] gltf::Error),
+    #[error("glTF decoding failed")]
+    GltfDecoding(gltf::Error),
+    #[error("subtree encoding failed")]
+    SubtreeEncoding(#[from] bincode::error::EncodeError),
+    #[error("subtree decoding failed")]
+    SubtreeDecoding(#[from] bincode::error::DecodeError),
+    #[error("subtree is invalid: {0}")]
+    InvalidSubtree(&'static str),
+    #[error("subtree root octant {0} is not occupied")]
+    UnoccupiedSubtreeRoot(OctantIndex),
+    #[error("content contains no vertices")]
+    EmptyContent(),
+    #[error("size of {size} bytes exceeds the limit of {limit} bytes")]
+    SizeLimitExceeded { size: usize, limit: usize },
+    #[error("archive contains no entry `{0}`")]
+    MissingArchiveEntry(String),
+    #[error("multiple tilesets are named `{0}`")]
+    DuplicateTilesetName(String),
+    #[error("no child tilesets were added")]
+    NoChildTilesets(),
+    #[error("metadata property `{0}` must be a string, number, boolean or array thereof")]
+    UnsupportedMetadataValue(String),
+    #[error("no statistics for octant {0}")]
+    MissingOctantStatistics(OctantIndex),
+    #[error("content layer name `{0}` is empty, duplicated or not a valid directory name")]
+    InvalidContentLayerName(String),
+    #[error("{0} points are not assigned to any content layer")]
+    UnassignedContentLayerPoints(usize),
+    #[error("style requires the point attribute `{0}`, which no point has")]
+    MissingStyleAttribute(&'static str),
+    #[error("path `{}` is not valid UTF-8", .0.display())]
+    NonUtf8Path(std::path::PathBuf),
+    #[error("content encoding `{0}` is not supported")]
+    UnsupportedContentEncoding(String),
+    #[error("archive is unusable after a previous failure")]
+    UnusableArchive(),
+    #[error("tileset is not supported: {0}")]
+    UnsupportedTileset(&'static str),
+    #[cfg(feature = "object-store")]
+    #[error("object store URL `{0}` is invalid, expected `s3://{{bucket}}/{{prefix}}`")]
+    InvalidObjectStoreUrl(String),
 }
@@ -0,0 +1,40 @@
+use crate::asset_info::AssetInfo;
+use crate::content_layer::ContentLayer;
+use crate::metadata::TilesetMetadata;
+use crate::style::TilesetStyle;
+use crate::{CONTENT_DIRECTORY_PATH, LEVELS_PER_SUBTREE, SUBTREES_DIRECTORY_PATH};
+use std::path::PathBuf;
+
+/// Paths and subdivision of the implicit tiling as stored in an archive, together with the
+/// user-supplied metadata, asset information, content layers, style and entry options to be
+/// written.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ArchiveLayout {
+    pub content_directory_path: PathBuf,
+    pub content_layers: Vec<ContentLayer>,
+    pub subtrees_directory_path: PathBuf,
+    pub levels_per_subtree: usize,
+    pub metadata: TilesetMetadata,
+    pub asset_info: AssetInfo,
+    pub style: Option<TilesetStyle>,
+    /// Whether a manifest with the sizes and hashes of all entries is written.
+    pub manifest: bool,
+    /// Whether each entry is stored gzip-compressed with the additional extension `.gz`.
+    pub precompressed_entries: bool,
+}
+
+impl Default for ArchiveLayout {
+    fn default() -> Self {
+        Self {
+            content_directory_path: CONTENT_DIRECTORY_PATH.into(),
+            content_layers: Vec::new(),
+            subtrees_directory_path: SUBTREES_DIRECTORY_PATH.into(),
+            levels_per_subtree: LEVELS_PER_SUBTREE,
+            metadata: TilesetMetadata::default(),
+            asset_info: AssetInfo::default(),
+            style: None,
+            manifest: false,
+            precompressed_entries: false,
+        }
+    }
+}
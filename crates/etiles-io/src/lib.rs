@@ -1,11 +1,103 @@
+mod archive_header;
+mod asset_info;
+mod compression;
+mod content_layer;
 mod documents;
 mod error;
+mod layout;
+mod manifest;
+mod merge;
+mod metadata;
+mod read;
+mod read_impl;
+mod sink;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod style;
+#[cfg(test)]
+mod testing;
+mod update;
+mod verify;
 mod write;
+#[cfg(feature = "async")]
+mod write_async;
 mod write_impl;
 
 #[doc(inline)]
 pub use write::EtilesWriter;
 
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use write_async::AsyncEtilesWriter;
+
+#[doc(inline)]
+pub use read::EtilesReader;
+
+#[doc(inline)]
+pub use merge::EtilesMerger;
+
+#[doc(inline)]
+pub use update::EtilesUpdater;
+
+#[doc(inline)]
+pub use verify::EtilesVerifier;
+
+#[doc(inline)]
+pub use verify::VerificationReport;
+
+#[doc(inline)]
+pub use sink::OutputSink;
+
+#[doc(inline)]
+pub use sink::ContentEncoding;
+
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use sink::AsyncOutputSink;
+
+#[doc(inline)]
+pub use sink::tar_sink::TarSink;
+
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use sink::async_tar_sink::AsyncTarSink;
+
+#[doc(inline)]
+pub use sink::directory_sink::DirectorySink;
+
+#[cfg(feature = "object-store")]
+#[doc(inline)]
+pub use sink::object_store_sink::ObjectStoreSink;
+
+#[cfg(feature = "sqlite")]
+#[doc(inline)]
+pub use sqlite::SqliteContainer;
+
+#[cfg(feature = "sqlite")]
+#[doc(inline)]
+pub use sqlite::SqliteContainerEntry;
+
+#[doc(inline)]
+pub use metadata::TilesetMetadata;
+
+#[doc(inline)]
+pub use asset_info::AssetInfo;
+
+#[doc(inline)]
+pub use archive_header::ArchiveHeaderInfo;
+
+#[doc(inline)]
+pub use compression::ArchiveCompression;
+
+#[doc(inline)]
+pub use content_layer::ContentLayer;
+
+#[doc(inline)]
+pub use style::TilesetStyle;
+
+#[doc(inline)]
+pub use read_impl::read_gltf_tile::read_gltf_tile;
+
 #[doc(inline)]
 pub use write_impl::write::write_tileset_json;
 
@@ -15,6 +107,9 @@ pub use write_impl::write::write_subtree_info;
 #[doc(inline)]
 pub use write_impl::write::derive_content_filename;
 
+#[doc(inline)]
+pub use write_impl::write::parse_content_filename;
+
 #[doc(inline)]
 pub use write_impl::content::EncodableContent;
 
@@ -22,7 +117,13 @@ pub use write_impl::content::EncodableContent;
 pub use error::Error;
 
 pub const FILE_EXTENSION_ETILES_UNCOMPRESSED: &str = "tar";
+pub const FILE_EXTENSION_ETILES_GZIP: &str = "tar.gz";
+pub const FILE_EXTENSION_ETILES_ZSTD: &str = "tar.zst";
+pub const FILE_EXTENSION_PRECOMPRESSED_ENTRY: &str = "gz";
+pub const FILE_EXTENSION_ETILES_SQLITE: &str = "3dtiles";
 pub const FILE_NAME_TILESET_JSON: &str = "tileset.json";
+pub const FILE_NAME_STYLE_JSON: &str = "style.json";
+pub const FILE_NAME_MANIFEST_JSON: &str = "manifest.json";
 
 pub const CONTENT_DIRECTORY_PATH: &str = "content/";
 pub const SUBTREES_DIRECTORY_PATH: &str = "subtrees/";
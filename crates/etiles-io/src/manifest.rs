@@ -0,0 +1,36 @@
+use crate::Error;
+use crate::documents::manifest::{ManifestDocument, ManifestEntry};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Lists the entries in the given order with their sizes and SHA-256 hashes.
+pub(crate) fn derive_manifest_document(archive_entries: &[(PathBuf, Vec<u8>)]) -> ManifestDocument {
+    let entries = archive_entries
+        .par_iter()
+        .map(|(path, buffer)| ManifestEntry {
+            path: path.to_string_lossy().replace('\\', "/"),
+            size: buffer.len() as u64,
+            sha256: derive_sha256_hex(buffer),
+        })
+        .collect();
+
+    ManifestDocument { entries }
+}
+
+pub(crate) fn write_manifest_json(
+    archive_entries: &[(PathBuf, Vec<u8>)],
+) -> Result<Vec<u8>, Error> {
+    let manifest_document = derive_manifest_document(archive_entries);
+
+    let mut manifest_document_buffer: Vec<u8> = Vec::new();
+    serde_json::to_writer_pretty(&mut manifest_document_buffer, &manifest_document)?;
+    Ok(manifest_document_buffer)
+}
+
+pub(crate) fn derive_sha256_hex(buffer: &[u8]) -> String {
+    Sha256::digest(buffer)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
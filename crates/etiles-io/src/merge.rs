@@ -0,0 +1,383 @@
+use crate::Error::{DuplicateTilesetName, MissingArchiveEntry, NoChildTilesets, NoFileExtension};
+use crate::FILE_NAME_TILESET_JSON;
+use crate::archive_header::ArchiveHeaderInfo;
+use crate::asset_info::GENERATOR;
+use crate::compression::{ArchiveCompression, decompress_archive, decompress_entry};
+use crate::documents::asset::{Asset, Version};
+use crate::documents::bounding_volume::BoundingVolume;
+use crate::documents::content::Content;
+use crate::documents::metadata::{ClassProperty, MetadataClass, MetadataEntity, Schema};
+use crate::documents::tile::{Refinement, Tile};
+use crate::documents::tileset::TilesetDocument;
+use crate::error::Error;
+use crate::metadata::{
+    METADATA_CLASS_EPOCH, METADATA_PROPERTY_EPOCH, METADATA_SCHEMA_ID, TilesetMetadata,
+};
+use eproj::{Projector, SpatialReferenceIdentifier};
+use nalgebra::{Matrix4, Point3, Vector3};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// `EtilesMerger` combines multiple tileset archives under a parent tileset, which references
+/// the tileset of each archive as external tileset.
+///
+/// Each child tileset is referenced as `{name}/tileset.json`, where the name is the file name
+/// of its archive without the archive extension. Compressed child archives are decompressed,
+/// while precompressed entries are copied as they are.
+///
+/// For multi-epoch scans of the same site, each child can be assigned an epoch, e.g. the date of
+/// the scan. The content of each such child then belongs to a metadata group carrying its epoch,
+/// allowing clients to show and compare single epochs.
+#[derive(Debug, Clone, Default)]
+pub struct EtilesMerger {
+    children: Vec<ChildTileset>,
+}
+
+#[derive(Debug, Clone)]
+struct ChildTileset {
+    name: String,
+    archive_path: PathBuf,
+    document: TilesetDocument,
+    epoch: Option<String>,
+}
+
+impl EtilesMerger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an archive as child tileset, reading only its `tileset.json`.
+    ///
+    /// The acquisition date of the child's metadata, if any, is used as its epoch.
+    pub fn add_archive(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.add_child(path.as_ref(), None)
+    }
+
+    /// Adds an archive as child tileset of the given epoch, e.g. an ISO 8601 date.
+    pub fn add_archive_with_epoch(
+        &mut self,
+        path: impl AsRef<Path>,
+        epoch: impl Into<String>,
+    ) -> Result<(), Error> {
+        self.add_child(path.as_ref(), Some(epoch.into()))
+    }
+
+    fn add_child(&mut self, path: &Path, epoch: Option<String>) -> Result<(), Error> {
+        let name = ArchiveCompression::derive_file_stem(path)
+            .ok_or(NoFileExtension())?
+            .to_string();
+        if self.children.iter().any(|c| c.name == name) {
+            return Err(DuplicateTilesetName(name));
+        }
+
+        let mut archive = tar::Archive::new(decompress_archive(BufReader::new(File::open(path)?))?);
+        let mut tileset_document: Option<TilesetDocument> = None;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            if path == Path::new(FILE_NAME_TILESET_JSON) {
+                let mut buffer = Vec::new();
+                entry.read_to_end(&mut buffer)?;
+                tileset_document = Some(serde_json::from_slice(&buffer)?);
+                break;
+            }
+            if path.with_extension("") == Path::new(FILE_NAME_TILESET_JSON) {
+                let mut buffer = Vec::new();
+                entry.read_to_end(&mut buffer)?;
+                if let Some((_, buffer)) = decompress_entry(&path, &buffer)? {
+                    tileset_document = Some(serde_json::from_slice(&buffer)?);
+                    break;
+                }
+            }
+        }
+        let document =
+            tileset_document.ok_or(MissingArchiveEntry(FILE_NAME_TILESET_JSON.to_string()))?;
+        let epoch =
+            epoch.or_else(|| TilesetMetadata::from_tileset_document(&document).acquisition_date);
+
+        self.children.push(ChildTileset {
+            name,
+            archive_path: path.to_path_buf(),
+            document,
+            epoch,
+        });
+        Ok(())
+    }
+
+    /// Writes only the parent `tileset.json`. The child archives must be extracted into
+    /// directories named after them next to it.
+    pub fn write_tileset_json<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let tileset_document = self.derive_parent_tileset_document()?;
+        serde_json::to_writer_pretty(writer, &tileset_document)?;
+
+        Ok(())
+    }
+
+    /// Writes a single archive with the parent `tileset.json` and the entries of each child
+    /// archive in a directory named after it.
+    pub fn write_archive<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut archive_builder = tar::Builder::new(writer);
+
+        let mut tileset_document_buffer: Vec<u8> = Vec::new();
+        self.write_tileset_json(&mut tileset_document_buffer)?;
+        archive_builder.append_data(
+            &mut ArchiveHeaderInfo::default().create_header(tileset_document_buffer.len())?,
+            FILE_NAME_TILESET_JSON,
+            Cursor::new(tileset_document_buffer),
+        )?;
+
+        for child in &self.children {
+            let mut archive = tar::Archive::new(decompress_archive(BufReader::new(File::open(
+                &child.archive_path,
+            )?))?);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+
+                let path = Path::new(&child.name).join(entry.path()?);
+                let mut buffer = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut buffer)?;
+                archive_builder.append_data(
+                    &mut ArchiveHeaderInfo::default().create_header(buffer.len())?,
+                    path,
+                    Cursor::new(buffer),
+                )?;
+            }
+        }
+        archive_builder.finish()?;
+
+        Ok(())
+    }
+
+    fn derive_parent_tileset_document(&self) -> Result<TilesetDocument, Error> {
+        if self.children.is_empty() {
+            return Err(NoChildTilesets());
+        }
+        let projector = Projector::new(
+            SpatialReferenceIdentifier::Epsg4978,
+            SpatialReferenceIdentifier::Epsg4979,
+        )?;
+
+        let mut children: Vec<Tile> = Vec::with_capacity(self.children.len());
+        let mut groups: Vec<MetadataEntity> = Vec::new();
+        let mut all_corners: Vec<Point3<f64>> = Vec::new();
+        for child in &self.children {
+            let group = child.epoch.as_ref().map(|epoch| {
+                groups.push(MetadataEntity {
+                    class: METADATA_CLASS_EPOCH.to_string(),
+                    properties: BTreeMap::from([(
+                        METADATA_PROPERTY_EPOCH.to_string(),
+                        Value::from(epoch.as_str()),
+                    )]),
+                });
+                (groups.len() - 1) as u32
+            });
+            let corners = derive_ecef_sample_points(&child.document.root)?;
+            let region = derive_region(&corners, &projector)?;
+            all_corners.extend(corners);
+
+            children.push(Tile {
+                geometric_error: child.document.geometric_error,
+                content: Some(Content {
+                    uri: format!("{}/{}", child.name, FILE_NAME_TILESET_JSON),
+                    group,
+                }),
+                contents: vec![],
+                bounding_volume: BoundingVolume::Region(region),
+                children: vec![],
+                transform: None,
+                refine: None,
+                implicit_tiling: None,
+            });
+        }
+
+        let region = derive_region(&all_corners, &projector)?;
+        let root_geometric_error = children
+            .iter()
+            .map(|c| c.geometric_error)
+            .fold(0.0, f64::max);
+        // the parent is only refined once the whole area is small on screen
+        let geometric_error = derive_diagonal(&all_corners).max(root_geometric_error);
+
+        Ok(TilesetDocument {
+            asset: Asset {
+                generator: Some(GENERATOR.to_string()),
+                ..Asset::new(Version::V1_1)
+            },
+            extensions_used: Vec::new(),
+            extensions_required: Vec::new(),
+            schema: (!groups.is_empty()).then(derive_epoch_schema),
+            metadata: None,
+            groups,
+            geometric_error,
+            root: Tile {
+                geometric_error: root_geometric_error,
+                content: None,
+                contents: vec![],
+                bounding_volume: BoundingVolume::Region(region),
+                children,
+                transform: None,
+                refine: Some(Refinement::Add),
+                implicit_tiling: None,
+            },
+        })
+    }
+}
+
+fn derive_epoch_schema() -> Schema {
+    Schema {
+        id: METADATA_SCHEMA_ID.to_string(),
+        classes: BTreeMap::from([(
+            METADATA_CLASS_EPOCH.to_string(),
+            MetadataClass {
+                description: Some("Epoch of a scan of a multi-epoch tileset.".to_string()),
+                properties: BTreeMap::from([(
+                    METADATA_PROPERTY_EPOCH.to_string(),
+                    ClassProperty::string(),
+                )]),
+            },
+        )]),
+    }
+}
+
+/// Returns the corners, edge and face centers of the bounding volume of the root tile in ECEF.
+///
+/// The face centers are needed as the bottom face of a box in a local tangent frame is closest
+/// to the ellipsoid at its center.
+fn derive_ecef_sample_points(root: &Tile) -> Result<Vec<Point3<f64>>, Error> {
+    let transform = root
+        .transform
+        .map(|t| Matrix4::from_column_slice(&t))
+        .unwrap_or_else(Matrix4::identity);
+
+    let corners = match root.bounding_volume {
+        BoundingVolume::Box(b) => {
+            let center = Point3::new(b[0], b[1], b[2]);
+            let x_axis = Vector3::new(b[3], b[4], b[5]);
+            let y_axis = Vector3::new(b[6], b[7], b[8]);
+            let z_axis = Vector3::new(b[9], b[10], b[11]);
+
+            [-1.0, 0.0, 1.0]
+                .into_iter()
+                .flat_map(|x| [-1.0, 0.0, 1.0].into_iter().map(move |y| (x, y)))
+                .flat_map(|(x, y)| [-1.0, 0.0, 1.0].into_iter().map(move |z| (x, y, z)))
+                .map(|(x, y, z)| {
+                    transform.transform_point(&(center + x * x_axis + y * y_axis + z * z_axis))
+                })
+                .collect()
+        }
+        BoundingVolume::Region(r) => {
+            // regions are geodetic and not affected by the tile transform
+            let geodetic_corners: Vec<Point3<f64>> = [r[0], r[2]]
+                .into_iter()
+                .flat_map(|lon| [r[1], r[3]].into_iter().map(move |lat| (lon, lat)))
+                .flat_map(|(lon, lat)| [r[4], r[5]].into_iter().map(move |h| (lon, lat, h)))
+                .map(|(lon, lat, h)| Point3::new(lon.to_degrees(), lat.to_degrees(), h))
+                .collect();
+            let inverse_projector = Projector::new(
+                SpatialReferenceIdentifier::Epsg4979,
+                SpatialReferenceIdentifier::Epsg4978,
+            )?;
+            inverse_projector.convert_points(geodetic_corners)?
+        }
+    };
+
+    Ok(corners)
+}
+
+/// Returns the bounding region `[west, south, east, north, minimum height, maximum height]` in
+/// radians and meters enclosing the ECEF points. Regions crossing the antimeridian have a west
+/// longitude greater than the east longitude.
+fn derive_region(ecef_points: &[Point3<f64>], projector: &Projector) -> Result<[f64; 6], Error> {
+    let geodetic_points = projector.convert_points(ecef_points.to_vec())?;
+
+    let longitudes: Vec<f64> = geodetic_points.iter().map(|p| p.x.to_radians()).collect();
+    let (west, east) = derive_longitude_range(&longitudes);
+    let mut region = [west, f64::MAX, east, f64::MIN, f64::MAX, f64::MIN];
+    for p in geodetic_points {
+        region[1] = region[1].min(p.y.to_radians());
+        region[3] = region[3].max(p.y.to_radians());
+        region[4] = region[4].min(p.z);
+        region[5] = region[5].max(p.z);
+    }
+
+    Ok(region)
+}
+
+/// Returns the west and east longitude of the narrower of the two longitude ranges enclosing the
+/// longitudes in `[-π, π]`, i.e. either the plain range or the one crossing the antimeridian.
+fn derive_longitude_range(longitudes: &[f64]) -> (f64, f64) {
+    let (west, east) = longitudes
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(w, e), l| (w.min(*l), e.max(*l)));
+    if east - west <= PI {
+        return (west, east);
+    }
+
+    // compare the longitudes in [0, 2π), where the antimeridian lies in the interior
+    let (shifted_west, shifted_east) = longitudes
+        .iter()
+        .map(|l| if *l < 0.0 { l + 2.0 * PI } else { *l })
+        .fold((f64::MAX, f64::MIN), |(w, e), l| (w.min(l), e.max(l)));
+    if shifted_east - shifted_west >= east - west {
+        return (west, east);
+    }
+
+    let wrap = |l: f64| if l > PI { l - 2.0 * PI } else { l };
+    (wrap(shifted_west), wrap(shifted_east))
+}
+
+fn derive_diagonal(points: &[Point3<f64>]) -> f64 {
+    let mut min = Vector3::from_element(f64::MAX);
+    let mut max = Vector3::from_element(f64::MIN);
+    for p in points {
+        min = min.inf(&p.coords);
+        max = max.sup(&p.coords);
+    }
+
+    (max - min).norm()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longitude_range_without_antimeridian() {
+        let longitudes = [-0.2, 0.1, 0.3];
+
+        assert_eq!(derive_longitude_range(&longitudes), (-0.2, 0.3));
+    }
+
+    #[test]
+    fn test_longitude_range_crossing_antimeridian() {
+        let longitudes = [PI - 0.1, -PI + 0.05, PI - 0.02];
+
+        let (west, east) = derive_longitude_range(&longitudes);
+
+        assert!((west - (PI - 0.1)).abs() < 1e-12);
+        assert!((east - (-PI + 0.05)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_longitude_range_wider_than_half_circle() {
+        let longitudes = [-2.0, 0.0, 2.0];
+
+        assert_eq!(derive_longitude_range(&longitudes), (-2.0, 2.0));
+    }
+
+    #[test]
+    fn test_merger_without_children_is_rejected() {
+        let merger = EtilesMerger::new();
+
+        let result = merger.write_tileset_json(Vec::new());
+
+        assert!(matches!(result, Err(NoChildTilesets())));
+    }
+}
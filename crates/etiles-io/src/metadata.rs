@@ -0,0 +1,365 @@
+use crate::documents::metadata::{
+    ClassProperty, ComponentType, ElementType, MetadataClass, MetadataEntity, Schema,
+};
+use crate::documents::tileset::TilesetDocument;
+use crate::error::Error;
+use crate::error::Error::UnsupportedMetadataValue;
+use chrono::{DateTime, Utc};
+use etiles_core::{OctantStatistics, Tileset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+pub const METADATA_SCHEMA_ID: &str = "etiles";
+pub const METADATA_CLASS_TILESET: &str = "tileset";
+pub const METADATA_CLASS_ACQUISITION: &str = "acquisition";
+pub const METADATA_CLASS_TILE: &str = "tile";
+pub const METADATA_CLASS_EPOCH: &str = "epoch";
+
+pub const METADATA_PROPERTY_PROJECT_ID: &str = "projectId";
+pub const METADATA_PROPERTY_TIME_REFERENCE: &str = "timeReference";
+pub const METADATA_PROPERTY_ACQUISITION_DATE: &str = "acquisitionDate";
+pub const METADATA_PROPERTY_EPOCH: &str = "epoch";
+pub const METADATA_PROPERTY_POINT_COUNT: &str = "pointCount";
+pub const METADATA_PROPERTY_MINIMUM_HEIGHT: &str = "minimumHeight";
+pub const METADATA_PROPERTY_MAXIMUM_HEIGHT: &str = "maximumHeight";
+
+/// User-supplied provenance written as 3D Tiles 1.1 metadata.
+///
+/// The project ID and time reference are attached to the tileset, while the acquisition
+/// properties are attached to a group containing all tile contents. Additional properties may be
+/// strings, numbers, booleans or arrays thereof and are added to the acquisition group.
+///
+/// Point times are only encoded in glTF tiles if a time reference is set or derived from the
+/// earliest point time. Each tile stores them in seconds since its own time reference, which is
+/// written into the extras of the time accessor.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TilesetMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_reference: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquisition_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensor: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_files: Vec<String>,
+    #[serde(flatten)]
+    pub additional_properties: BTreeMap<String, Value>,
+}
+
+impl TilesetMetadata {
+    /// Reads the metadata from a JSON object with the camel case property names.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let metadata: Self = serde_json::from_reader(reader)?;
+        metadata.derive_acquisition_class()?;
+
+        Ok(metadata)
+    }
+
+    /// Recovers the user-supplied metadata from a tileset document written by this crate.
+    pub(crate) fn from_tileset_document(tileset_document: &TilesetDocument) -> Self {
+        let mut properties = serde_json::Map::new();
+        for name in [
+            METADATA_PROPERTY_PROJECT_ID,
+            METADATA_PROPERTY_TIME_REFERENCE,
+        ] {
+            if let Some(value) = tileset_document
+                .metadata
+                .as_ref()
+                .and_then(|m| m.properties.get(name))
+            {
+                properties.insert(name.to_string(), value.clone());
+            }
+        }
+        if let Some(group) = tileset_document
+            .groups
+            .iter()
+            .find(|g| g.class == METADATA_CLASS_ACQUISITION)
+        {
+            properties.extend(group.properties.clone());
+        }
+
+        serde_json::from_value(Value::Object(properties)).unwrap_or_default()
+    }
+
+    /// Sets the time reference to the start of the second of the earliest point time, unless
+    /// already set, e.g. by a previous write of the tileset.
+    pub(crate) fn derive_time_reference(&mut self, tileset: &Tileset) {
+        if self.time_reference.is_some() {
+            return;
+        }
+
+        self.time_reference = tileset
+            .tiled_content
+            .cells()
+            .values()
+            .flatten()
+            .filter_map(|v| v.time)
+            .min_by(f64::total_cmp)
+            .and_then(|t| DateTime::from_timestamp(t.floor() as i64, 0));
+    }
+
+    fn acquisition_properties(&self) -> BTreeMap<String, Value> {
+        let mut properties = self.additional_properties.clone();
+        if let Some(acquisition_date) = &self.acquisition_date {
+            properties.insert(
+                METADATA_PROPERTY_ACQUISITION_DATE.to_string(),
+                Value::from(acquisition_date.as_str()),
+            );
+        }
+        if let Some(sensor) = &self.sensor {
+            properties.insert("sensor".to_string(), Value::from(sensor.as_str()));
+        }
+        if !self.source_files.is_empty() {
+            properties.insert(
+                "sourceFiles".to_string(),
+                Value::from(self.source_files.clone()),
+            );
+        }
+
+        properties
+    }
+
+    fn derive_acquisition_class(&self) -> Result<Option<MetadataClass>, Error> {
+        let properties = self.acquisition_properties();
+        if properties.is_empty() {
+            return Ok(None);
+        }
+
+        let class_properties = properties
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), derive_class_property(name, value)?)))
+            .collect::<Result<BTreeMap<String, ClassProperty>, Error>>()?;
+        Ok(Some(MetadataClass {
+            description: Some("Provenance of the point cloud content.".to_string()),
+            properties: class_properties,
+        }))
+    }
+
+    /// Returns the schema with the tileset, tile and, if any acquisition properties are set,
+    /// the acquisition class. The height properties are only declared if the tileset has heights.
+    pub(crate) fn derive_schema(&self, has_heights: bool) -> Result<Schema, Error> {
+        let mut tileset_properties = BTreeMap::from([(
+            METADATA_PROPERTY_POINT_COUNT.to_string(),
+            ClassProperty::scalar(ComponentType::Uint64),
+        )]);
+        let mut tile_properties = BTreeMap::from([(
+            METADATA_PROPERTY_POINT_COUNT.to_string(),
+            ClassProperty::scalar(ComponentType::Uint32),
+        )]);
+        if has_heights {
+            tileset_properties.extend([
+                (
+                    METADATA_PROPERTY_MINIMUM_HEIGHT.to_string(),
+                    ClassProperty::scalar(ComponentType::Float64),
+                ),
+                (
+                    METADATA_PROPERTY_MAXIMUM_HEIGHT.to_string(),
+                    ClassProperty::scalar(ComponentType::Float64),
+                ),
+            ]);
+            tile_properties.extend([
+                (
+                    METADATA_PROPERTY_MINIMUM_HEIGHT.to_string(),
+                    ClassProperty::scalar(ComponentType::Float64)
+                        .with_semantic("TILE_MINIMUM_HEIGHT"),
+                ),
+                (
+                    METADATA_PROPERTY_MAXIMUM_HEIGHT.to_string(),
+                    ClassProperty::scalar(ComponentType::Float64)
+                        .with_semantic("TILE_MAXIMUM_HEIGHT"),
+                ),
+            ]);
+        }
+        if self.project_id.is_some() {
+            tileset_properties.insert(
+                METADATA_PROPERTY_PROJECT_ID.to_string(),
+                derive_class_property(METADATA_PROPERTY_PROJECT_ID, &Value::from(""))?,
+            );
+        }
+        if self.time_reference.is_some() {
+            tileset_properties.insert(
+                METADATA_PROPERTY_TIME_REFERENCE.to_string(),
+                derive_class_property(METADATA_PROPERTY_TIME_REFERENCE, &Value::from(""))?,
+            );
+        }
+
+        let mut classes = BTreeMap::from([
+            (
+                METADATA_CLASS_TILESET.to_string(),
+                MetadataClass {
+                    description: Some("Statistics and project of the tileset.".to_string()),
+                    properties: tileset_properties,
+                },
+            ),
+            (
+                METADATA_CLASS_TILE.to_string(),
+                MetadataClass {
+                    description: Some(
+                        "Statistics of a tile, where the heights include all descendant tiles."
+                            .to_string(),
+                    ),
+                    properties: tile_properties,
+                },
+            ),
+        ]);
+        if let Some(acquisition_class) = self.derive_acquisition_class()? {
+            classes.insert(METADATA_CLASS_ACQUISITION.to_string(), acquisition_class);
+        }
+
+        Ok(Schema {
+            id: METADATA_SCHEMA_ID.to_string(),
+            classes,
+        })
+    }
+
+    /// Returns the tileset metadata with the total point count and the height range of the root.
+    pub(crate) fn derive_tileset_entity(
+        &self,
+        point_count: u64,
+        root_statistics: Option<&OctantStatistics>,
+    ) -> MetadataEntity {
+        let mut properties = BTreeMap::from([(
+            METADATA_PROPERTY_POINT_COUNT.to_string(),
+            Value::from(point_count),
+        )]);
+        if let Some(minimum_height) = root_statistics.and_then(|s| s.minimum_height) {
+            properties.insert(
+                METADATA_PROPERTY_MINIMUM_HEIGHT.to_string(),
+                Value::from(minimum_height),
+            );
+        }
+        if let Some(maximum_height) = root_statistics.and_then(|s| s.maximum_height) {
+            properties.insert(
+                METADATA_PROPERTY_MAXIMUM_HEIGHT.to_string(),
+                Value::from(maximum_height),
+            );
+        }
+        if let Some(project_id) = &self.project_id {
+            properties.insert(
+                METADATA_PROPERTY_PROJECT_ID.to_string(),
+                Value::from(project_id.as_str()),
+            );
+        }
+        if let Some(time_reference) = &self.time_reference {
+            properties.insert(
+                METADATA_PROPERTY_TIME_REFERENCE.to_string(),
+                Value::from(time_reference.to_rfc3339()),
+            );
+        }
+
+        MetadataEntity {
+            class: METADATA_CLASS_TILESET.to_string(),
+            properties,
+        }
+    }
+
+    /// Returns the acquisition group, if any acquisition properties are set.
+    pub(crate) fn derive_groups(&self) -> Vec<MetadataEntity> {
+        let properties = self.acquisition_properties();
+        if properties.is_empty() {
+            return Vec::new();
+        }
+
+        vec![MetadataEntity {
+            class: METADATA_CLASS_ACQUISITION.to_string(),
+            properties,
+        }]
+    }
+}
+
+fn derive_class_property(name: &str, value: &Value) -> Result<ClassProperty, Error> {
+    let (element_type, component_type, array) = match value {
+        Value::String(_) => (ElementType::String, None, false),
+        Value::Bool(_) => (ElementType::Boolean, None, false),
+        Value::Number(n) if n.is_i64() || n.is_u64() => {
+            (ElementType::Scalar, Some(ComponentType::Int64), false)
+        }
+        Value::Number(_) => (ElementType::Scalar, Some(ComponentType::Float64), false),
+        Value::Array(values) if !values.is_empty() => {
+            let element_property = derive_class_property(name, &values[0])?;
+            if element_property.array
+                || values
+                    .iter()
+                    .any(|v| derive_class_property(name, v).ok() != Some(element_property.clone()))
+            {
+                return Err(UnsupportedMetadataValue(name.to_string()));
+            }
+            (
+                element_property.element_type,
+                element_property.component_type,
+                true,
+            )
+        }
+        _ => return Err(UnsupportedMetadataValue(name.to_string())),
+    };
+
+    Ok(ClassProperty {
+        element_type,
+        component_type,
+        array,
+        semantic: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heights_are_only_declared_with_heights() {
+        let metadata = TilesetMetadata::default();
+
+        for has_heights in [false, true] {
+            let schema = metadata.derive_schema(has_heights).expect("schema");
+
+            for class in [METADATA_CLASS_TILESET, METADATA_CLASS_TILE] {
+                let properties = &schema.classes[class].properties;
+                assert!(properties.contains_key(METADATA_PROPERTY_POINT_COUNT));
+                assert_eq!(
+                    properties.contains_key(METADATA_PROPERTY_MINIMUM_HEIGHT),
+                    has_heights
+                );
+                assert_eq!(
+                    properties.contains_key(METADATA_PROPERTY_MAXIMUM_HEIGHT),
+                    has_heights
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_class_property_of_homogeneous_array() {
+        let class_property =
+            derive_class_property("values", &serde_json::json!([1, 2, 3])).expect("property");
+
+        assert_eq!(class_property.element_type, ElementType::Scalar);
+        assert_eq!(class_property.component_type, Some(ComponentType::Int64));
+        assert!(class_property.array);
+    }
+
+    #[test]
+    fn test_class_property_of_mixed_array_is_rejected() {
+        for value in [
+            serde_json::json!([1, "a"]),
+            serde_json::json!([true, 1.5]),
+            serde_json::json!([[1], [2]]),
+            serde_json::json!([]),
+            serde_json::json!({ "a": 1 }),
+        ] {
+            let result = derive_class_property("values", &value);
+
+            assert!(
+                matches!(&result, Err(UnsupportedMetadataValue(name)) if name == "values"),
+                "{value} was accepted"
+            );
+        }
+    }
+}
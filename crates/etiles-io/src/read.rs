@@ -0,0 +1,52 @@
+use crate::compression::ArchiveCompression;
+use crate::error::Error;
+use crate::read_impl::read::{decode_tileset, read_archive_entries};
+#[cfg(feature = "sqlite")]
+use crate::sqlite::SqliteContainer;
+use etiles_core::Tileset;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// `EtilesReader` reads a tileset from an archive written by `EtilesWriter`.
+///
+/// Besides TAR archives, a `SqliteContainer` can be read with the feature `sqlite`. Compressed
+/// archives and precompressed entries are detected and decompressed transparently.
+#[derive(Debug, Clone)]
+pub struct EtilesReader<R> {
+    reader: R,
+}
+
+impl<R> EtilesReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: Read> EtilesReader<R> {
+    pub fn finish(self) -> Result<Tileset, Error> {
+        let (entries, _) = read_archive_entries(self.reader)?;
+        let (tileset, _) = decode_tileset(&entries)?;
+
+        Ok(tileset)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl EtilesReader<SqliteContainer> {
+    pub fn finish(self) -> Result<Tileset, Error> {
+        let (entries, _) = self.reader.read_entries()?;
+        let (tileset, _) = decode_tileset(&entries)?;
+
+        Ok(tileset)
+    }
+}
+
+impl EtilesReader<BufReader<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        ArchiveCompression::from_path(&path)?;
+
+        let file = File::open(path)?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
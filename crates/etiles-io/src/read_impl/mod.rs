@@ -0,0 +1,4 @@
+pub(crate) mod read;
+pub mod read_gltf_tile;
+pub(crate) mod read_subtree;
+pub(crate) mod verify_archive;
@@ -0,0 +1,270 @@
+use crate::Error::{MissingArchiveEntry, UnsupportedTileset};
+use crate::asset_info::AssetInfo;
+use crate::compression::{decompress_archive, decompress_entry};
+use crate::content_layer::ContentLayer;
+use crate::documents::bounding_volume::BoundingVolume;
+use crate::documents::tileset::TilesetDocument;
+use crate::layout::ArchiveLayout;
+use crate::metadata::TilesetMetadata;
+use crate::read_impl::read_gltf_tile::read_gltf_tile;
+use crate::write_impl::write::parse_content_filename;
+use crate::{
+    Error, FILE_EXTENSION_PRECOMPRESSED_ENTRY, FILE_NAME_MANIFEST_JSON, FILE_NAME_TILESET_JSON,
+};
+use ecoord::AxisAlignedBoundingCube;
+use ecoord::octree::OctantIndex;
+use etiles_core::{Tileset, Vertex};
+use nalgebra::{
+    Isometry3, Matrix3, Matrix4, Point3, Rotation3, Translation3, UnitQuaternion, Vector3,
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// File entries of an archive by their path.
+pub(crate) type ArchiveEntries = HashMap<PathBuf, Vec<u8>>;
+
+/// Reads all file entries of a TAR archive into memory, which may be compressed as a whole.
+///
+/// Precompressed entries are decompressed and stored under their original paths. Additionally
+/// returns whether the archive contained precompressed entries.
+pub(crate) fn read_archive_entries<R: Read>(
+    reader: R,
+) -> Result<(HashMap<PathBuf, Vec<u8>>, bool), Error> {
+    let mut archive = tar::Archive::new(decompress_archive(reader)?);
+    let mut entries = HashMap::new();
+    let mut precompressed_entries = false;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?.into_owned();
+        let mut buffer = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut buffer)?;
+        match decompress_entry(&path, &buffer)? {
+            Some((path, buffer)) => {
+                precompressed_entries = true;
+                entries.insert(path, buffer);
+            }
+            None => {
+                entries.insert(path, buffer);
+            }
+        }
+    }
+
+    Ok((entries, precompressed_entries))
+}
+
+/// Reads all file entries of a TAR archive like [`read_archive_entries`], but continues past
+/// unreadable entries, e.g. for verification.
+///
+/// Precompressed entries that cannot be decompressed are kept as stored under their original
+/// paths. Reading stops at the end of a truncated archive, keeping the incomplete entry, and at
+/// an entry header that cannot be decoded, which is marked by its position in the archive. The
+/// paths of these are additionally returned.
+pub(crate) fn read_archive_entries_leniently<R: Read>(
+    reader: R,
+) -> Result<(ArchiveEntries, Vec<PathBuf>), Error> {
+    let mut archive = tar::Archive::new(decompress_archive(reader)?);
+    let mut entries = HashMap::new();
+    let mut unreadable_entries = Vec::new();
+
+    for (entry_index, entry) in archive.entries()?.enumerate() {
+        let Ok(mut entry) = entry else {
+            unreadable_entries.push(PathBuf::from(format!("<header of entry {entry_index}>")));
+            break;
+        };
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Ok(path) = entry.path().map(|p| p.into_owned()) else {
+            continue;
+        };
+
+        // the size is not trusted for the allocation, as it may be damaged
+        let entry_size = entry.size();
+        let mut buffer = Vec::new();
+        let is_complete = (&mut entry)
+            .take(entry_size)
+            .read_to_end(&mut buffer)
+            .is_ok()
+            && buffer.len() as u64 == entry_size;
+        let decompressed_entry = is_complete
+            .then(|| decompress_entry(&path, &buffer).ok())
+            .flatten();
+        match decompressed_entry {
+            Some(Some((path, buffer))) => {
+                entries.insert(path, buffer);
+            }
+            Some(None) => {
+                entries.insert(path, buffer);
+            }
+            None => {
+                let path = match path.extension().and_then(|e| e.to_str()) {
+                    Some(FILE_EXTENSION_PRECOMPRESSED_ENTRY) => path.with_extension(""),
+                    _ => path,
+                };
+                unreadable_entries.push(path.clone());
+                entries.insert(path, buffer);
+            }
+        }
+        if !is_complete {
+            break;
+        }
+    }
+
+    Ok((entries, unreadable_entries))
+}
+
+/// Parses the tileset document of an archive written by `EtilesWriter` and derives its layout.
+pub(crate) fn decode_archive_layout(
+    entries: &HashMap<PathBuf, Vec<u8>>,
+) -> Result<(TilesetDocument, ArchiveLayout), Error> {
+    let tileset_document_buffer = entries
+        .get(Path::new(FILE_NAME_TILESET_JSON))
+        .ok_or_else(|| MissingArchiveEntry(FILE_NAME_TILESET_JSON.to_string()))?;
+    let tileset_document: TilesetDocument = serde_json::from_slice(tileset_document_buffer)?;
+    let root = &tileset_document.root;
+
+    let implicit_tiling = root
+        .implicit_tiling
+        .as_ref()
+        .ok_or(UnsupportedTileset("root tile without implicit tiling"))?;
+    let (content_directory_path, content_layers) = match &root.content {
+        Some(content) => (parent_directory(&content.uri), Vec::new()),
+        None => derive_content_layers(&tileset_document)?,
+    };
+    let layout = ArchiveLayout {
+        content_directory_path,
+        content_layers,
+        subtrees_directory_path: parent_directory(&implicit_tiling.subtrees.uri),
+        levels_per_subtree: implicit_tiling.subtree_levels as usize,
+        metadata: TilesetMetadata::from_tileset_document(&tileset_document),
+        asset_info: AssetInfo::from_tileset_document(&tileset_document),
+        style: None,
+        manifest: entries.contains_key(Path::new(FILE_NAME_MANIFEST_JSON)),
+        precompressed_entries: false,
+    };
+
+    Ok((tileset_document, layout))
+}
+
+/// Reconstructs the tileset from the entries of an archive written by `EtilesWriter`.
+pub(crate) fn decode_tileset(
+    entries: &HashMap<PathBuf, Vec<u8>>,
+) -> Result<(Tileset, ArchiveLayout), Error> {
+    let (tileset_document, layout) = decode_archive_layout(entries)?;
+    let root = &tileset_document.root;
+
+    let root_transform = root
+        .transform
+        .map(|t| derive_isometry(&t))
+        .unwrap_or_else(Isometry3::identity);
+    let enclosing_cube = match root.bounding_volume {
+        BoundingVolume::Box(b) => {
+            let center = Point3::new(b[0], b[1], b[2]);
+            let half_edge_length = b[3];
+            AxisAlignedBoundingCube::new(
+                center - Vector3::from_element(half_edge_length),
+                2.0 * half_edge_length,
+            )
+            .map_err(ecoord::Error::from)?
+        }
+        BoundingVolume::Region(_) => {
+            return Err(UnsupportedTileset("root bounding region"));
+        }
+    };
+
+    // the contents of all layers of an octant are joined in the order of the layers
+    let mut content_entries: Vec<(usize, OctantIndex, &Vec<u8>)> = entries
+        .iter()
+        .filter_map(|(path, buffer)| {
+            let directory_path = path.parent()?;
+            let layer_index = if layout.content_layers.is_empty() {
+                (directory_path == layout.content_directory_path).then_some(0)?
+            } else {
+                layout
+                    .content_layers
+                    .iter()
+                    .position(|l| directory_path == layout.content_directory_path.join(&l.name))?
+            };
+            let file_name = path.file_name()?.to_str()?;
+            parse_content_filename(file_name).map(|index| (layer_index, index, buffer))
+        })
+        .collect();
+    content_entries.sort_by_key(|(layer_index, _, _)| *layer_index);
+    let decoded_contents: Vec<(OctantIndex, Vec<Vertex>)> = content_entries
+        .into_par_iter()
+        .map(|(_, index, buffer)| Ok((index, read_gltf_tile(buffer)?)))
+        .collect::<Result<Vec<(OctantIndex, Vec<Vertex>)>, Error>>()?;
+    let mut cells: HashMap<OctantIndex, Vec<Vertex>> = HashMap::new();
+    for (index, vertices) in decoded_contents {
+        cells.entry(index).or_default().extend(vertices);
+    }
+
+    let tileset = Tileset::from_raw_parts(
+        &enclosing_cube,
+        cells,
+        root_transform,
+        tileset_document.geometric_error,
+        root.geometric_error,
+    )?;
+
+    Ok((tileset, layout))
+}
+
+/// Recovers the content layers from the contents of the root tile and their metadata groups,
+/// returning them together with the common content directory.
+fn derive_content_layers(
+    tileset_document: &TilesetDocument,
+) -> Result<(PathBuf, Vec<ContentLayer>), Error> {
+    let contents = &tileset_document.root.contents;
+    let first_content = contents
+        .first()
+        .ok_or(UnsupportedTileset("root tile without content"))?;
+    let content_directory_path = parent_directory(parent_directory(&first_content.uri));
+
+    let content_layers = contents
+        .iter()
+        .map(|c| {
+            let layer_directory_path = parent_directory(&c.uri);
+            let name = layer_directory_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .filter(|_| layer_directory_path.parent() == Some(&content_directory_path))
+                .ok_or(UnsupportedTileset("contents in differing directories"))?;
+            let classifications = c
+                .group
+                .and_then(|g| tileset_document.groups.get(g as usize))
+                .and_then(ContentLayer::from_group_entity)
+                .map(|l| l.classifications)
+                .unwrap_or_default();
+
+            Ok(ContentLayer::new(name, classifications))
+        })
+        .collect::<Result<Vec<ContentLayer>, Error>>()?;
+
+    Ok((content_directory_path, content_layers))
+}
+
+fn parent_directory(uri_template: impl AsRef<Path>) -> PathBuf {
+    uri_template
+        .as_ref()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Converts a column-major 4x4 transform of a tile into an isometry.
+fn derive_isometry(transform: &[f64; 16]) -> Isometry3<f64> {
+    let matrix = Matrix4::from_column_slice(transform);
+    let rotation_matrix: Matrix3<f64> = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&rotation_matrix));
+    let translation = Translation3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+
+    Isometry3::from_parts(translation, rotation)
+}
@@ -0,0 +1,90 @@
+use crate::Error;
+use crate::Error::{EmptyContent, GltfDecoding};
+use crate::write_impl::write_gltf_tile::{
+    GLTF_ATTRIBUTE_CLASSIFICATION, GLTF_ATTRIBUTE_TIME, GLTF_EXTRAS_KEY_TIME_REFERENCE,
+    gltf_axis_adjustment_isometry,
+};
+use chrono::{DateTime, Utc};
+use nalgebra::{Point3, Vector3};
+use palette::Srgb;
+
+/// Decodes the vertices of a binary glTF tile as written by `write_gltf_tile`, applying the
+/// translation of the node of each mesh.
+pub fn read_gltf_tile(bytes: &[u8]) -> Result<Vec<etiles_core::Vertex>, Error> {
+    let gltf = gltf::Gltf::from_slice(bytes).map_err(GltfDecoding)?;
+    let blob = gltf.blob.as_deref().ok_or(EmptyContent())?;
+    let inverse_axis_adjustment = gltf_axis_adjustment_isometry().inverse();
+
+    let mut vertices: Vec<etiles_core::Vertex> = Vec::new();
+    for (node, mesh) in gltf.nodes().filter_map(|n| n.mesh().map(|m| (n, m))) {
+        let (translation, _, _) = node.transform().decomposed();
+        let translation = Vector3::from(translation).cast::<f64>();
+
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|_| Some(blob));
+
+            let positions: Vec<[f32; 3]> = reader.read_positions().ok_or(EmptyContent())?.collect();
+            let colors: Vec<[f32; 3]> = match reader.read_colors(0) {
+                Some(colors) => colors.into_rgb_f32().collect(),
+                None => vec![[1.0, 1.0, 1.0]; positions.len()],
+            };
+            let normals: Vec<Option<[f32; 3]>> = match reader.read_normals() {
+                Some(normals) => normals.map(Some).collect(),
+                None => vec![None; positions.len()],
+            };
+            let classifications: Vec<Option<u8>> = match primitive
+                .get(&gltf::Semantic::Extras(
+                    GLTF_ATTRIBUTE_CLASSIFICATION.to_string(),
+                ))
+                .and_then(|a| gltf::accessor::Iter::<u8>::new(a, |_| Some(blob)))
+            {
+                Some(classifications) => classifications.map(Some).collect(),
+                None => vec![None; positions.len()],
+            };
+            let times: Vec<Option<f64>> = match primitive
+                .get(&gltf::Semantic::Extras(GLTF_ATTRIBUTE_TIME.to_string()))
+                .and_then(|a| {
+                    let reference_seconds = derive_time_reference(&a)?.timestamp() as f64;
+                    let times = gltf::accessor::Iter::<f32>::new(a, |_| Some(blob))?;
+                    Some(times.map(move |t| reference_seconds + t as f64))
+                }) {
+                Some(times) => times.map(Some).collect(),
+                None => vec![None; positions.len()],
+            };
+
+            vertices.extend(
+                positions
+                    .into_iter()
+                    .zip(colors)
+                    .zip(normals)
+                    .zip(classifications)
+                    .zip(times)
+                    .map(|((((p, c), n), k), t)| etiles_core::Vertex {
+                        position: inverse_axis_adjustment
+                            * (Point3::new(p[0] as f64, p[1] as f64, p[2] as f64) + translation),
+                        color: Srgb::new(c[0], c[1], c[2]),
+                        normal: n.map(|n| {
+                            inverse_axis_adjustment
+                                .rotation
+                                .cast::<f32>()
+                                .transform_vector(&Vector3::from(n))
+                        }),
+                        classification: k,
+                        time: t,
+                    }),
+            );
+        }
+    }
+
+    Ok(vertices)
+}
+
+/// Parses the time reference from the extras of the time accessor.
+fn derive_time_reference(accessor: &gltf::Accessor) -> Option<DateTime<Utc>> {
+    let extras: serde_json::Value = serde_json::from_str(accessor.extras().as_ref()?.get()).ok()?;
+    let time_reference = extras.get(GLTF_EXTRAS_KEY_TIME_REFERENCE)?.as_str()?;
+
+    DateTime::parse_from_rfc3339(time_reference)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
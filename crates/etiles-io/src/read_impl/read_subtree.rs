@@ -0,0 +1,115 @@
+use crate::Error;
+use crate::Error::InvalidSubtree;
+use crate::write_impl::write_subtree::{Availability, Constant, Subtree, SubtreeBinaryHeader};
+use bincode::config;
+use bitvec::order::Lsb0;
+use bitvec::slice::BitSlice;
+use ecoord::octree::{OctantIndex, VecOctantIndexExt};
+
+/// Octants declared available by a subtree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SubtreeAvailability {
+    /// Octants with content, for each content layer.
+    pub contents: Vec<Vec<OctantIndex>>,
+    /// Root octants of the available child subtrees.
+    pub child_subtrees: Vec<OctantIndex>,
+}
+
+/// Reads the content and child subtree availability of a binary subtree file.
+pub(crate) fn read_subtree_availability(
+    bytes: &[u8],
+    base_octant_index: OctantIndex,
+    levels_per_subtree: usize,
+) -> Result<SubtreeAvailability, Error> {
+    let config = config::standard().with_fixed_int_encoding();
+    let (header, header_length): (SubtreeBinaryHeader, usize) =
+        bincode::decode_from_slice(bytes, config)?;
+    if header.magic != ['s', 'u', 'b', 't'] {
+        return Err(InvalidSubtree("magic is not `subt`"));
+    }
+    let json_end = header_length + header.json_byte_length as usize;
+    let binary_end = json_end + header.binary_byte_length as usize;
+    if bytes.len() < binary_end {
+        return Err(InvalidSubtree(
+            "file is shorter than declared in the header",
+        ));
+    }
+    let subtree: Subtree = serde_json::from_slice(&bytes[header_length..json_end])?;
+    let binary = &bytes[json_end..binary_end];
+
+    let subtree_octant_indices: Vec<OctantIndex> = (0..levels_per_subtree as u32)
+        .map(|l| {
+            base_octant_index
+                .get_descendents(l)
+                .sort_by_morton_indices()
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ecoord::Error::from)?
+        .into_iter()
+        .flatten()
+        .map(|(i, _)| i)
+        .collect();
+    let child_subtree_octant_indices: Vec<OctantIndex> = base_octant_index
+        .get_descendents(levels_per_subtree as u32)
+        .sort_by_morton_indices()
+        .map_err(ecoord::Error::from)?
+        .into_iter()
+        .map(|(i, _)| i)
+        .collect();
+
+    let contents = subtree
+        .content_availability
+        .iter()
+        .map(|a| select_available(&subtree, binary, a, &subtree_octant_indices))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let child_subtrees = select_available(
+        &subtree,
+        binary,
+        &subtree.child_subtree_availability,
+        &child_subtree_octant_indices,
+    )?;
+
+    Ok(SubtreeAvailability {
+        contents,
+        child_subtrees,
+    })
+}
+
+/// Returns the octants whose bit in the availability is set, with the octants ordered as the
+/// bits.
+fn select_available(
+    subtree: &Subtree,
+    binary: &[u8],
+    availability: &Availability,
+    octant_indices: &[OctantIndex],
+) -> Result<Vec<OctantIndex>, Error> {
+    if let Some(constant) = availability.constant {
+        return Ok(match constant {
+            Constant::Available => octant_indices.to_vec(),
+            Constant::Unavailable => Vec::new(),
+        });
+    }
+
+    let bitstream = availability
+        .bitstream
+        .ok_or(InvalidSubtree("availability without bitstream or constant"))?;
+    let buffer_view = subtree
+        .buffer_views
+        .get(bitstream as usize)
+        .ok_or(InvalidSubtree("bitstream refers to a missing buffer view"))?;
+    let bytes = binary
+        .get(buffer_view.byte_offset as usize..)
+        .and_then(|b| b.get(..buffer_view.byte_length as usize))
+        .ok_or(InvalidSubtree("buffer view exceeds the binary"))?;
+    let bits = BitSlice::<u8, Lsb0>::from_slice(bytes);
+    if bits.len() < octant_indices.len() {
+        return Err(InvalidSubtree("bitstream is shorter than the availability"));
+    }
+
+    Ok(octant_indices
+        .iter()
+        .zip(bits.iter())
+        .filter(|(_, b)| **b)
+        .map(|(i, _)| *i)
+        .collect())
+}
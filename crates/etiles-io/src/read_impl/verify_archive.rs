@@ -0,0 +1,146 @@
+use crate::documents::manifest::ManifestDocument;
+use crate::layout::ArchiveLayout;
+use crate::manifest::derive_sha256_hex;
+use crate::read_impl::read::decode_archive_layout;
+use crate::read_impl::read_subtree::read_subtree_availability;
+use crate::verify::VerificationReport;
+use crate::write_impl::write::{derive_content_filename, derive_subtree_filename};
+use crate::{FILE_NAME_MANIFEST_JSON, FILE_NAME_TILESET_JSON};
+use ecoord::octree::OctantIndex;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Verifies the entries of an archive, where `unreadable_entries` could not be decompressed or
+/// were cut off. These are reported as corrupted, as are a tileset document and a manifest that
+/// cannot be decoded.
+pub(crate) fn verify_archive_entries(
+    entries: &HashMap<PathBuf, Vec<u8>>,
+    unreadable_entries: Vec<PathBuf>,
+) -> VerificationReport {
+    let mut report = VerificationReport {
+        entry_count: entries.len(),
+        has_manifest: entries.contains_key(Path::new(FILE_NAME_MANIFEST_JSON)),
+        corrupted_entries: unreadable_entries,
+        ..Default::default()
+    };
+
+    if let Some(manifest_document_buffer) = entries.get(Path::new(FILE_NAME_MANIFEST_JSON)) {
+        match serde_json::from_slice::<ManifestDocument>(manifest_document_buffer) {
+            Ok(manifest_document) => verify_manifest(entries, &manifest_document, &mut report),
+            Err(_) => report
+                .corrupted_entries
+                .push(PathBuf::from(FILE_NAME_MANIFEST_JSON)),
+        }
+    }
+    match decode_archive_layout(entries) {
+        Ok((_, layout)) => verify_availability(entries, &layout, &mut report),
+        Err(_) if entries.contains_key(Path::new(FILE_NAME_TILESET_JSON)) => report
+            .corrupted_entries
+            .push(PathBuf::from(FILE_NAME_TILESET_JSON)),
+        Err(_) => report
+            .missing_available_entries
+            .push(PathBuf::from(FILE_NAME_TILESET_JSON)),
+    }
+    report.corrupted_entries.sort();
+    report.corrupted_entries.dedup();
+
+    report
+}
+
+fn verify_manifest(
+    entries: &HashMap<PathBuf, Vec<u8>>,
+    manifest_document: &ManifestDocument,
+    report: &mut VerificationReport,
+) {
+    let mut listed_paths: BTreeSet<PathBuf> = BTreeSet::new();
+    for manifest_entry in &manifest_document.entries {
+        let path = PathBuf::from(&manifest_entry.path);
+        match entries.get(&path) {
+            Some(buffer)
+                if buffer.len() as u64 == manifest_entry.size
+                    && derive_sha256_hex(buffer) == manifest_entry.sha256 => {}
+            Some(_) => report.corrupted_entries.push(path.clone()),
+            None => report.missing_manifest_entries.push(path.clone()),
+        }
+        listed_paths.insert(path);
+    }
+
+    report.extra_manifest_entries = entries
+        .keys()
+        .filter(|p| !listed_paths.contains(*p) && p.as_path() != Path::new(FILE_NAME_MANIFEST_JSON))
+        .cloned()
+        .collect::<BTreeSet<PathBuf>>()
+        .into_iter()
+        .collect();
+    report.missing_manifest_entries.sort();
+}
+
+/// Walks the subtrees from the root and compares the declared subtree and content entries with
+/// the entries in the subtree and content directories. Subtrees that cannot be decoded are
+/// reported as corrupted.
+fn verify_availability(
+    entries: &HashMap<PathBuf, Vec<u8>>,
+    layout: &ArchiveLayout,
+    report: &mut VerificationReport,
+) {
+    let content_directory_paths: Vec<PathBuf> = if layout.content_layers.is_empty() {
+        vec![layout.content_directory_path.clone()]
+    } else {
+        layout
+            .content_layers
+            .iter()
+            .map(|l| layout.content_directory_path.join(&l.name))
+            .collect()
+    };
+
+    let mut declared_paths: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut subtree_root_indices: Vec<OctantIndex> = vec![OctantIndex::origin()];
+    while let Some(subtree_root_index) = subtree_root_indices.pop() {
+        let subtree_path = layout
+            .subtrees_directory_path
+            .join(derive_subtree_filename(&subtree_root_index));
+        declared_paths.insert(subtree_path.clone());
+        let Some(subtree_buffer) = entries.get(&subtree_path) else {
+            continue;
+        };
+
+        let Ok(availability) = read_subtree_availability(
+            subtree_buffer,
+            subtree_root_index,
+            layout.levels_per_subtree,
+        ) else {
+            report.corrupted_entries.push(subtree_path);
+            continue;
+        };
+        for (content_directory_path, octant_indices) in
+            content_directory_paths.iter().zip(&availability.contents)
+        {
+            declared_paths.extend(
+                octant_indices
+                    .iter()
+                    .map(|i| content_directory_path.join(derive_content_filename(i))),
+            );
+        }
+        subtree_root_indices.extend(availability.child_subtrees);
+    }
+
+    report.missing_available_entries = declared_paths
+        .iter()
+        .filter(|p| !entries.contains_key(*p))
+        .cloned()
+        .collect();
+    report.extra_available_entries = entries
+        .keys()
+        .filter(|p| {
+            let parent = p.parent();
+            (parent == Some(layout.subtrees_directory_path.as_path())
+                || content_directory_paths
+                    .iter()
+                    .any(|c| parent == Some(c.as_path())))
+                && !declared_paths.contains(*p)
+        })
+        .cloned()
+        .collect::<BTreeSet<PathBuf>>()
+        .into_iter()
+        .collect();
+}
@@ -0,0 +1,61 @@
+use crate::archive_header::ArchiveHeaderInfo;
+use crate::compression::ArchiveCompression;
+use crate::error::Error;
+use crate::sink::tar_sink::TarSink;
+use crate::sink::{AsyncOutputSink, ContentEncoding, OutputSink};
+use std::path::Path;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// `AsyncTarSink` streams the entries as TAR archive into an asynchronous writer, e.g. the body
+/// of a response or an upload.
+///
+/// Each entry is archived and compressed in memory like by [`TarSink`], and the resulting bytes
+/// are written before the next entry is put. The writer is shut down on finalizing.
+pub struct AsyncTarSink<W: AsyncWrite + Unpin + Send> {
+    writer: W,
+    tar_sink: TarSink<Vec<u8>>,
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncTarSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            tar_sink: TarSink::new(Vec::new()),
+        }
+    }
+
+    /// Compresses the archive as a whole.
+    pub fn with_archive_compression(mut self, archive_compression: ArchiveCompression) -> Self {
+        self.tar_sink = self.tar_sink.with_archive_compression(archive_compression);
+        self
+    }
+
+    /// Sets the header info of the archive entries, see [`TarSink::with_archive_header_info`].
+    pub fn with_archive_header_info(mut self, archive_header_info: ArchiveHeaderInfo) -> Self {
+        self.tar_sink = self.tar_sink.with_archive_header_info(archive_header_info);
+        self
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncOutputSink for AsyncTarSink<W> {
+    async fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        self.tar_sink.put(path, content, content_encoding)?;
+        let buffer = self.tar_sink.take_written();
+        self.writer.write_all(&buffer).await?;
+
+        Ok(())
+    }
+
+    async fn finalize(mut self) -> Result<(), Error> {
+        let buffer = self.tar_sink.into_inner()?;
+        self.writer.write_all(&buffer).await?;
+        self.writer.shutdown().await?;
+
+        Ok(())
+    }
+}
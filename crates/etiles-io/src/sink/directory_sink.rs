@@ -0,0 +1,51 @@
+use crate::compression::derive_precompressed_path;
+use crate::error::Error;
+use crate::sink::{ContentEncoding, OutputSink};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `DirectorySink` writes each entry as a file below a root directory, which can be served
+/// directly by static hosting.
+///
+/// Precompressed entries are stored with the additional extension `.gz`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectorySink {
+    root_directory_path: PathBuf,
+}
+
+impl DirectorySink {
+    /// Creates the root directory if it does not exist yet. Existing files are overwritten, but
+    /// not removed.
+    pub fn new(root_directory_path: impl AsRef<Path>) -> Result<Self, Error> {
+        fs::create_dir_all(&root_directory_path)?;
+
+        Ok(Self {
+            root_directory_path: root_directory_path.as_ref().to_path_buf(),
+        })
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        let path = match content_encoding {
+            Some(ContentEncoding::Gzip) => derive_precompressed_path(path),
+            None => path.to_path_buf(),
+        };
+        let file_path = self.root_directory_path.join(path);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(file_path, content)?;
+
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
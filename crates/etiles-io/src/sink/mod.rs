@@ -0,0 +1,59 @@
+#[cfg(feature = "async")]
+pub mod async_tar_sink;
+pub mod directory_sink;
+#[cfg(feature = "object-store")]
+pub mod object_store_sink;
+pub mod tar_sink;
+
+use crate::error::Error;
+use std::path::Path;
+
+/// Encoding applied to the content of an entry, as declared by the HTTP `Content-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+    Gzip,
+}
+
+impl ContentEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// `OutputSink` receives the entries of a tileset written by `EtilesWriter`.
+///
+/// The entries are put one after another in their reproducible order, starting with
+/// `tileset.json`, and the sink is finalized once all entries have been put.
+pub trait OutputSink {
+    /// Stores the content under the relative path, e.g. `content/pc_0__0_0_0.glb`.
+    ///
+    /// Precompressed content is put with its encoding under the path of the uncompressed entry,
+    /// leaving it to the sink how to record the encoding.
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error>;
+
+    /// Completes the output. Entries are not guaranteed to be persisted before.
+    fn finalize(self) -> Result<(), Error>;
+}
+
+/// `AsyncOutputSink` receives the entries of a tileset written by `AsyncEtilesWriter`, like
+/// [`OutputSink`] but awaiting each put.
+#[cfg(feature = "async")]
+pub trait AsyncOutputSink: Send {
+    /// Stores the content under the relative path, e.g. `content/pc_0__0_0_0.glb`.
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Completes the output. Entries are not guaranteed to be persisted before.
+    fn finalize(self) -> impl Future<Output = Result<(), Error>> + Send;
+}
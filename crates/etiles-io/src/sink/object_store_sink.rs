@@ -0,0 +1,195 @@
+use crate::Error::{InvalidObjectStoreUrl, NonUtf8Path};
+use crate::error::Error;
+#[cfg(feature = "async")]
+use crate::sink::AsyncOutputSink;
+use crate::sink::{ContentEncoding, OutputSink};
+use object_store::aws::AmazonS3Builder;
+use object_store::path::{Path as ObjectPath, PathPart};
+use object_store::{
+    Attribute, Attributes, BackoffConfig, ObjectStore, PutOptions, PutPayload, RetryConfig,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::task::{JoinError, JoinSet};
+
+const DEFAULT_CONCURRENCY: usize = 8;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// `ObjectStoreSink` uploads each entry as an object below a prefix of an object store, such as
+/// an S3-compatible bucket.
+///
+/// As [`OutputSink`], the uploads run concurrently on an internal runtime, which is created on
+/// the first put, so the sink must not be used from within an asynchronous context. As
+/// `AsyncOutputSink`, the uploads are spawned on the runtime of the caller instead. Failed
+/// uploads are retried by the store as configured on it. Precompressed entries keep their key and
+/// are uploaded with `Content-Encoding: gzip`.
+pub struct ObjectStoreSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    concurrency: usize,
+    runtime: Option<Runtime>,
+    uploads: JoinSet<Result<(), Error>>,
+}
+
+impl ObjectStoreSink {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: ObjectPath) -> Self {
+        Self {
+            store,
+            prefix,
+            concurrency: DEFAULT_CONCURRENCY,
+            runtime: None,
+            uploads: JoinSet::new(),
+        }
+    }
+
+    /// Uploads to an S3-compatible bucket given as `s3://{bucket}/{prefix}`.
+    ///
+    /// Credentials, region and endpoint are read from the `AWS_*` environment variables, e.g.
+    /// `AWS_ENDPOINT` and `AWS_ALLOW_HTTP` for a local MinIO instance. A failed request is retried
+    /// up to `retries` times, with a delay that starts at 200 ms and doubles with every retry.
+    pub fn from_s3_url(url: &str, retries: usize) -> Result<Self, Error> {
+        let location = url
+            .strip_prefix("s3://")
+            .filter(|l| !l.is_empty())
+            .ok_or_else(|| InvalidObjectStoreUrl(url.to_string()))?;
+        let (bucket_name, prefix) = location.split_once('/').unwrap_or((location, ""));
+        let retry_config = RetryConfig {
+            max_retries: retries,
+            backoff: BackoffConfig {
+                init_backoff: INITIAL_RETRY_DELAY,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket_name)
+            .with_retry(retry_config)
+            .build()?;
+
+        Ok(Self::new(Arc::new(store), ObjectPath::parse(prefix)?))
+    }
+
+    /// Sets the maximum number of concurrent uploads, which is at least one.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Derives the upload of an entry, which fails if the path is not valid UTF-8.
+    fn derive_upload(
+        &self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<impl Future<Output = Result<(), Error>> + Send + 'static, Error> {
+        let path_parts = path
+            .iter()
+            .map(|p| p.to_str().map(PathPart::from))
+            .collect::<Option<Vec<PathPart>>>()
+            .ok_or_else(|| NonUtf8Path(path.to_path_buf()))?;
+        let location = ObjectPath::from_iter(self.prefix.parts().chain(path_parts));
+        let mut attributes = Attributes::new();
+        if let Some(content_type) = derive_content_type(path) {
+            attributes.insert(Attribute::ContentType, content_type.into());
+        }
+        if let Some(content_encoding) = content_encoding {
+            attributes.insert(Attribute::ContentEncoding, content_encoding.as_str().into());
+        }
+        let options = PutOptions {
+            attributes,
+            ..Default::default()
+        };
+
+        let store = self.store.clone();
+        let payload = PutPayload::from(content);
+        Ok(async move {
+            store
+                .put_opts(&location, payload, options)
+                .await
+                .map(|_| ())
+                .map_err(Error::from)
+        })
+    }
+}
+
+/// Fails if the upload failed or its task panicked.
+fn check_upload(result: Result<Result<(), Error>, JoinError>) -> Result<(), Error> {
+    result.map_err(std::io::Error::other)?
+}
+
+impl OutputSink for ObjectStoreSink {
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        let upload = self.derive_upload(path, content, content_encoding)?;
+        let runtime = match &mut self.runtime {
+            Some(runtime) => runtime,
+            runtime => runtime.insert(
+                tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .build()?,
+            ),
+        };
+        while self.uploads.len() >= self.concurrency
+            && let Some(result) = runtime.block_on(self.uploads.join_next())
+        {
+            check_upload(result)?;
+        }
+
+        self.uploads.spawn_on(upload, runtime.handle());
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<(), Error> {
+        let Some(runtime) = &self.runtime else {
+            return Ok(());
+        };
+        while let Some(result) = runtime.block_on(self.uploads.join_next()) {
+            check_upload(result)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncOutputSink for ObjectStoreSink {
+    async fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        while self.uploads.len() >= self.concurrency
+            && let Some(result) = self.uploads.join_next().await
+        {
+            check_upload(result)?;
+        }
+
+        let upload = self.derive_upload(path, content, content_encoding)?;
+        self.uploads.spawn(upload);
+        Ok(())
+    }
+
+    async fn finalize(mut self) -> Result<(), Error> {
+        while let Some(result) = self.uploads.join_next().await {
+            check_upload(result)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn derive_content_type(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "json" => Some("application/json"),
+        "glb" => Some("model/gltf-binary"),
+        "subtree" => Some("application/octet-stream"),
+        _ => None,
+    }
+}
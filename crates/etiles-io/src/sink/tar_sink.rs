@@ -0,0 +1,114 @@
+use crate::archive_header::ArchiveHeaderInfo;
+use crate::compression::{ArchiveCompression, ArchiveEncoder, derive_precompressed_path};
+use crate::error::Error;
+use crate::error::Error::UnusableArchive;
+use crate::sink::{ContentEncoding, OutputSink};
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+/// `TarSink` writes the entries as TAR archive into a single byte stream, which can be
+/// compressed as a whole.
+///
+/// Precompressed entries are stored with the additional extension `.gz`.
+pub struct TarSink<W: Write> {
+    archive_state: ArchiveState<W>,
+    archive_compression: ArchiveCompression,
+    archive_header_info: ArchiveHeaderInfo,
+}
+
+/// The archive builder is created with the first entry so that the compression can still be
+/// configured until then.
+enum ArchiveState<W: Write> {
+    Pending(W),
+    Started(Box<tar::Builder<ArchiveEncoder<W>>>),
+    /// Creating the archive encoder failed, which consumed the writer.
+    Failed,
+}
+
+impl<W: Write> TarSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            archive_state: ArchiveState::Pending(writer),
+            archive_compression: ArchiveCompression::default(),
+            archive_header_info: ArchiveHeaderInfo::default(),
+        }
+    }
+
+    /// Compresses the archive as a whole.
+    pub fn with_archive_compression(mut self, archive_compression: ArchiveCompression) -> Self {
+        self.archive_compression = archive_compression;
+        self
+    }
+
+    /// Sets the modification time and ownership of the archive entries, which default to fixed
+    /// values for reproducible archives.
+    pub fn with_archive_header_info(mut self, archive_header_info: ArchiveHeaderInfo) -> Self {
+        self.archive_header_info = archive_header_info;
+        self
+    }
+
+    /// Returns the archive builder and creates it on the first call.
+    fn archive_builder(&mut self) -> Result<&mut tar::Builder<ArchiveEncoder<W>>, Error> {
+        self.archive_state = match std::mem::replace(&mut self.archive_state, ArchiveState::Failed)
+        {
+            ArchiveState::Pending(writer) => ArchiveState::Started(Box::new(tar::Builder::new(
+                ArchiveEncoder::new(writer, self.archive_compression)?,
+            ))),
+            archive_state => archive_state,
+        };
+
+        match &mut self.archive_state {
+            ArchiveState::Started(archive_builder) => Ok(archive_builder),
+            ArchiveState::Pending(_) | ArchiveState::Failed => Err(UnusableArchive()),
+        }
+    }
+
+    /// Completes the archive and returns the writer.
+    pub(crate) fn into_inner(mut self) -> Result<W, Error> {
+        self.archive_builder()?;
+
+        match self.archive_state {
+            ArchiveState::Started(archive_builder) => (*archive_builder).into_inner()?.finish(),
+            ArchiveState::Pending(_) | ArchiveState::Failed => Err(UnusableArchive()),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl TarSink<Vec<u8>> {
+    /// Takes the bytes of the archive written so far, which allows streaming the archive while
+    /// entries are still put.
+    pub(crate) fn take_written(&mut self) -> Vec<u8> {
+        match &mut self.archive_state {
+            ArchiveState::Started(archive_builder) => {
+                std::mem::take(archive_builder.get_mut().get_mut())
+            }
+            ArchiveState::Pending(_) | ArchiveState::Failed => Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> OutputSink for TarSink<W> {
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        let path = match content_encoding {
+            Some(ContentEncoding::Gzip) => derive_precompressed_path(path),
+            None => path.to_path_buf(),
+        };
+        let mut header = self.archive_header_info.create_header(content.len())?;
+        self.archive_builder()?
+            .append_data(&mut header, path, Cursor::new(content))?;
+
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<(), Error> {
+        self.into_inner()?;
+
+        Ok(())
+    }
+}
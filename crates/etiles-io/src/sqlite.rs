@@ -0,0 +1,271 @@
+use crate::asset_info::{AssetInfo, GENERATOR};
+use crate::compression::gunzip_entry_content;
+use crate::documents::tileset::TilesetDocument;
+use crate::error::Error;
+use crate::error::Error::UnsupportedContentEncoding;
+use crate::read_impl::read::ArchiveEntries;
+use crate::sink::{ContentEncoding, OutputSink};
+use crate::{FILE_NAME_TILESET_JSON, TilesetMetadata};
+use rayon::prelude::*;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SQLITE_METADATA_NAME_GENERATOR: &str = "generator";
+const SQLITE_METADATA_NAME_TILESET: &str = "tileset";
+const SQLITE_METADATA_NAME_VERSION: &str = "version";
+const SQLITE_METADATA_NAME_TILESET_VERSION: &str = "tilesetVersion";
+const SQLITE_METADATA_NAME_COPYRIGHT: &str = "copyright";
+const SQLITE_METADATA_NAME_METADATA: &str = "metadata";
+
+/// Key, content and content encoding of an entry as stored in the table `media`.
+type StoredEntry = (String, Vec<u8>, Option<String>);
+
+/// `SqliteContainer` stores all entries of a tileset in a single SQLite database, similar to
+/// MBTiles for 2D tiles.
+///
+/// Entries are stored in the table `media` with their path as `key` and their bytes as
+/// `content`, as in the `.3dtiles` format of the 3D Tiles tools. The additional column
+/// `content_encoding` is `gzip` for precompressed entries. The table `metadata` holds name and
+/// value pairs describing the tileset, such as the path of its `tileset.json`.
+#[derive(Debug)]
+pub struct SqliteContainer {
+    connection: Connection,
+}
+
+/// Entry of a container as stored, which can be served as is with its content encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteContainerEntry {
+    pub content: Vec<u8>,
+    pub content_encoding: Option<String>,
+}
+
+impl SqliteContainerEntry {
+    /// Returns the content decompressed according to its content encoding, e.g. for clients
+    /// that do not accept the encoding.
+    pub fn into_decoded_content(self) -> Result<Vec<u8>, Error> {
+        match self.content_encoding.as_deref() {
+            None => Ok(self.content),
+            Some(content_encoding) if content_encoding == ContentEncoding::Gzip.as_str() => {
+                gunzip_entry_content(&self.content)
+            }
+            Some(content_encoding) => Err(UnsupportedContentEncoding(content_encoding.to_string())),
+        }
+    }
+}
+
+impl SqliteContainer {
+    /// Creates an empty container at the path, replacing an existing file.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Error> {
+        if path.as_ref().exists() {
+            fs::remove_file(&path)?;
+        }
+
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE metadata (name TEXT NOT NULL PRIMARY KEY, value TEXT NOT NULL);
+             CREATE TABLE media (
+                 key TEXT NOT NULL PRIMARY KEY,
+                 content BLOB NOT NULL,
+                 content_encoding TEXT
+             );",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Opens an existing container for reading.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Returns the entry stored under the path, e.g. `content/pc_0__0_0_0.glb`, without
+    /// decompressing it.
+    pub fn read_entry(&self, path: &str) -> Result<Option<SqliteContainerEntry>, Error> {
+        let entry = self
+            .connection
+            .prepare_cached("SELECT content, content_encoding FROM media WHERE key = ?1")?
+            .query_row(params![path], |row| {
+                Ok(SqliteContainerEntry {
+                    content: row.get(0)?,
+                    content_encoding: row.get(1)?,
+                })
+            })
+            .optional()?;
+
+        Ok(entry)
+    }
+
+    /// Returns the entry addressed by the path of an HTTP request, e.g.
+    /// `/content/pc_0__0_0_0.glb?v=1`, without decompressing it. The root path `/` addresses the
+    /// tileset document named in the table `metadata`.
+    pub fn read_requested_entry(
+        &self,
+        request_path: &str,
+    ) -> Result<Option<SqliteContainerEntry>, Error> {
+        let path = request_path
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('/');
+        if !path.is_empty() {
+            return self.read_entry(path);
+        }
+
+        let tileset_path = self
+            .read_metadata()?
+            .remove(SQLITE_METADATA_NAME_TILESET)
+            .unwrap_or_else(|| FILE_NAME_TILESET_JSON.to_string());
+        self.read_entry(&tileset_path)
+    }
+
+    /// Returns the name and value pairs of the table `metadata`.
+    pub fn read_metadata(&self) -> Result<BTreeMap<String, String>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, value FROM metadata")?;
+        let metadata = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<BTreeMap<String, String>, rusqlite::Error>>()?;
+
+        Ok(metadata)
+    }
+
+    /// Reads all entries into memory, decompressing precompressed entries. Additionally returns
+    /// whether the container held precompressed entries.
+    pub(crate) fn read_entries(&self) -> Result<(HashMap<PathBuf, Vec<u8>>, bool), Error> {
+        let stored_entries = self.read_stored_entries()?;
+
+        let precompressed_entries = stored_entries
+            .iter()
+            .any(|(_, _, e)| e.as_deref() == Some(ContentEncoding::Gzip.as_str()));
+        let entries = stored_entries
+            .into_par_iter()
+            .map(|(key, content, content_encoding)| {
+                let buffer = if content_encoding.as_deref() == Some(ContentEncoding::Gzip.as_str())
+                {
+                    gunzip_entry_content(&content)?
+                } else {
+                    content
+                };
+                Ok((PathBuf::from(key), buffer))
+            })
+            .collect::<Result<HashMap<PathBuf, Vec<u8>>, Error>>()?;
+
+        Ok((entries, precompressed_entries))
+    }
+
+    /// Reads all entries like [`SqliteContainer::read_entries`], but keeps precompressed entries
+    /// that cannot be decompressed as stored and additionally returns their paths.
+    pub(crate) fn read_entries_leniently(&self) -> Result<(ArchiveEntries, Vec<PathBuf>), Error> {
+        let mut unreadable_entries = Vec::new();
+        let mut entries = HashMap::new();
+        for (key, content, content_encoding) in self.read_stored_entries()? {
+            let path = PathBuf::from(key);
+            let buffer = if content_encoding.as_deref() == Some(ContentEncoding::Gzip.as_str()) {
+                gunzip_entry_content(&content).unwrap_or_else(|_| {
+                    unreadable_entries.push(path.clone());
+                    content
+                })
+            } else {
+                content
+            };
+            entries.insert(path, buffer);
+        }
+
+        Ok((entries, unreadable_entries))
+    }
+
+    /// Returns the key, content and content encoding of all entries as stored.
+    fn read_stored_entries(&self) -> Result<Vec<StoredEntry>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT key, content, content_encoding FROM media")?;
+        let stored_entries = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        Ok(stored_entries)
+    }
+}
+
+impl OutputSink for SqliteContainer {
+    /// Inserts the entry within a transaction, which begins with the first entry. The metadata
+    /// is derived from the `tileset.json`.
+    fn put(
+        &mut self,
+        path: &Path,
+        content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), Error> {
+        if self.connection.is_autocommit() {
+            self.connection.execute_batch("BEGIN")?;
+        }
+
+        if path == Path::new(FILE_NAME_TILESET_JSON) {
+            let tileset_document: TilesetDocument = match content_encoding {
+                Some(ContentEncoding::Gzip) => {
+                    serde_json::from_slice(&gunzip_entry_content(&content)?)?
+                }
+                None => serde_json::from_slice(&content)?,
+            };
+            let mut statement = self
+                .connection
+                .prepare_cached("INSERT OR REPLACE INTO metadata (name, value) VALUES (?1, ?2)")?;
+            for (name, value) in derive_metadata_entries(&tileset_document)? {
+                statement.execute(params![name, value])?;
+            }
+        }
+
+        let key = path.to_string_lossy().replace('\\', "/");
+        self.connection
+            .prepare_cached(
+                "INSERT OR REPLACE INTO media (key, content, content_encoding) VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![key, content, content_encoding.map(|e| e.as_str())])?;
+
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<(), Error> {
+        if !self.connection.is_autocommit() {
+            self.connection.execute_batch("COMMIT")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn derive_metadata_entries(
+    tileset_document: &TilesetDocument,
+) -> Result<Vec<(&'static str, String)>, Error> {
+    let asset_info = AssetInfo::from_tileset_document(tileset_document);
+    let metadata = TilesetMetadata::from_tileset_document(tileset_document);
+
+    let mut metadata_entries = vec![
+        (
+            SQLITE_METADATA_NAME_TILESET,
+            FILE_NAME_TILESET_JSON.to_string(),
+        ),
+        (SQLITE_METADATA_NAME_VERSION, "1.1".to_string()),
+        (SQLITE_METADATA_NAME_GENERATOR, GENERATOR.to_string()),
+    ];
+    if let Some(tileset_version) = asset_info.tileset_version {
+        metadata_entries.push((SQLITE_METADATA_NAME_TILESET_VERSION, tileset_version));
+    }
+    if let Some(copyright) = asset_info.copyright {
+        metadata_entries.push((SQLITE_METADATA_NAME_COPYRIGHT, copyright));
+    }
+    if metadata != TilesetMetadata::default() {
+        metadata_entries.push((
+            SQLITE_METADATA_NAME_METADATA,
+            serde_json::to_string(&metadata)?,
+        ));
+    }
+
+    Ok(metadata_entries)
+}
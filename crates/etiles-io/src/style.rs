@@ -0,0 +1,289 @@
+use crate::Error;
+use crate::Error::{MissingOctantStatistics, MissingStyleAttribute};
+use crate::documents::style::{Conditions, StyleDocument};
+use crate::write_impl::write_gltf_tile::{
+    GLTF_METADATA_PROPERTY_CLASSIFICATION, gltf_axis_adjustment_isometry,
+};
+use ecoord::octree::OctantIndex;
+use etiles_core::{ColorGradient, OctantStatistics, Tileset, get_classification_color};
+use palette::Srgb;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::warn;
+
+const STYLE_DEFINE_HEIGHT: &str = "height";
+
+/// Declarative 3D Tiles style generated for a tileset and written as `style.json`.
+///
+/// The style refers to the per-point properties of the glTF tiles, so it can be applied by
+/// viewers without re-encoding the tiles.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TilesetStyle {
+    /// ASPRS standard classification colors for the classes present in the tileset.
+    Classification,
+    /// Colors sampled from a gradient by the ellipsoidal height, spanning the height range of
+    /// the tileset. Omitted for tilesets without heights, see
+    /// [`Tileset::derive_origin_height`].
+    Height { gradient: ColorGradient },
+}
+
+impl TilesetStyle {
+    /// Derives the style document, or `None` if the style does not apply to the tileset.
+    pub(crate) fn derive_style_document(
+        &self,
+        tileset: &Tileset,
+        octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+    ) -> Result<Option<StyleDocument>, Error> {
+        match self {
+            TilesetStyle::Classification => derive_classification_style(tileset).map(Some),
+            TilesetStyle::Height { gradient } => {
+                derive_height_style(tileset, octant_statistics, gradient)
+            }
+        }
+    }
+
+    pub(crate) fn write_style_json(
+        &self,
+        tileset: &Tileset,
+        octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some(style_document) = self.derive_style_document(tileset, octant_statistics)? else {
+            return Ok(None);
+        };
+
+        let mut style_document_buffer: Vec<u8> = Vec::new();
+        serde_json::to_writer_pretty(&mut style_document_buffer, &style_document)?;
+        Ok(Some(style_document_buffer))
+    }
+}
+
+fn derive_classification_style(tileset: &Tileset) -> Result<StyleDocument, Error> {
+    let classifications: BTreeSet<u8> = tileset
+        .tiled_content
+        .cells()
+        .values()
+        .flatten()
+        .filter_map(|v| v.classification)
+        .collect();
+    if classifications.is_empty() {
+        return Err(MissingStyleAttribute(GLTF_METADATA_PROPERTY_CLASSIFICATION));
+    }
+
+    let mut conditions: Vec<[String; 2]> = classifications
+        .into_iter()
+        .map(|c| {
+            [
+                format!("${{{GLTF_METADATA_PROPERTY_CLASSIFICATION}}} === {c}"),
+                derive_color_expression(get_classification_color(c)),
+            ]
+        })
+        .collect();
+    conditions.push([
+        "true".to_string(),
+        derive_color_expression(get_classification_color(u8::MAX)),
+    ]);
+
+    Ok(StyleDocument {
+        defines: BTreeMap::new(),
+        color: Some(Conditions { conditions }),
+    })
+}
+
+/// The height is derived from the glTF position, whose y axis is the up axis of the local frame
+/// of the root transform, like the height statistics of the tileset. Without heights, the style
+/// is skipped with a warning.
+fn derive_height_style(
+    tileset: &Tileset,
+    octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+    gradient: &ColorGradient,
+) -> Result<Option<StyleDocument>, Error> {
+    let root_statistics = octant_statistics
+        .get(&OctantIndex::origin())
+        .ok_or(MissingOctantStatistics(OctantIndex::origin()))?;
+    let (Some(origin_height), Some(minimum_height), Some(maximum_height)) = (
+        tileset.derive_origin_height(),
+        root_statistics.minimum_height,
+        root_statistics.maximum_height,
+    ) else {
+        warn!("Skip height style, as the local frame of the tileset has no vertical axis");
+        return Ok(None);
+    };
+
+    // rounding removes floating-point noise, adding zero removes negative zeros
+    let up_axis =
+        (gltf_axis_adjustment_isometry() * nalgebra::Vector3::z()).map(|c| c.round() + 0.0);
+    let defines = BTreeMap::from([(
+        STYLE_DEFINE_HEIGHT.to_string(),
+        derive_offset_expression(
+            format!(
+                "dot(${{POSITION}}, vec3({}, {}, {}))",
+                up_axis.x, up_axis.y, up_axis.z
+            ),
+            origin_height,
+        ),
+    )]);
+
+    let stops = gradient.stops();
+    let segment_count = stops.len() - 1;
+    let height_extent = maximum_height - minimum_height;
+    let mut conditions: Vec<[String; 2]> = Vec::new();
+    if segment_count > 0 && height_extent > 0.0 {
+        let segment_height = height_extent / segment_count as f64;
+        for (i, segment_stops) in stops.windows(2).enumerate() {
+            let lower_height = minimum_height + i as f64 * segment_height;
+            let upper_height = lower_height + segment_height;
+            let condition = if i + 1 == segment_count {
+                "true".to_string()
+            } else {
+                format!("${{{STYLE_DEFINE_HEIGHT}}} < {upper_height}")
+            };
+            let expression = format!(
+                "mix({}, {}, clamp(({}) / {segment_height}, 0.0, 1.0))",
+                derive_color_expression(segment_stops[0]),
+                derive_color_expression(segment_stops[1]),
+                derive_offset_expression(format!("${{{STYLE_DEFINE_HEIGHT}}}"), -lower_height),
+            );
+            conditions.push([condition, expression]);
+        }
+    } else {
+        conditions.push([
+            "true".to_string(),
+            derive_color_expression(gradient.sample(0.5)),
+        ]);
+    }
+
+    Ok(Some(StyleDocument {
+        defines,
+        color: Some(Conditions { conditions }),
+    }))
+}
+
+fn derive_offset_expression(expression: String, offset: f64) -> String {
+    if offset < 0.0 {
+        format!("{expression} - {}", -offset)
+    } else {
+        format!("{expression} + {offset}")
+    }
+}
+
+fn derive_color_expression(color: Srgb<f32>) -> String {
+    let color: Srgb<u8> = color.into_format();
+    format!(
+        "color('#{:02x}{:02x}{:02x}')",
+        color.red, color.green, color.blue
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{derive_east_north_up_rotation, derive_tileset, derive_vertices};
+    use etiles_core::Vertex;
+    use nalgebra::UnitQuaternion;
+
+    /// Builds a tileset with heights of the classified vertices.
+    fn derive_classified_tileset(classification: impl Fn(usize) -> Option<u8>) -> Tileset {
+        let vertices = derive_vertices()
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| Vertex {
+                classification: classification(i),
+                ..v
+            })
+            .collect();
+
+        derive_tileset(vertices, derive_east_north_up_rotation())
+    }
+
+    fn derive_conditions(style: &TilesetStyle, tileset: &Tileset) -> Option<Vec<[String; 2]>> {
+        style
+            .derive_style_document(tileset, &tileset.derive_octant_statistics())
+            .expect("style document")
+            .map(|d| d.color.expect("color").conditions)
+    }
+
+    #[test]
+    fn test_classification_conditions_of_present_classes() {
+        let tileset = derive_classified_tileset(|i| [Some(6), Some(2), None][i % 3]);
+
+        let conditions =
+            derive_conditions(&TilesetStyle::Classification, &tileset).expect("conditions");
+
+        let expected_conditions = [
+            [
+                format!("${{{GLTF_METADATA_PROPERTY_CLASSIFICATION}}} === 2"),
+                derive_color_expression(get_classification_color(2)),
+            ],
+            [
+                format!("${{{GLTF_METADATA_PROPERTY_CLASSIFICATION}}} === 6"),
+                derive_color_expression(get_classification_color(6)),
+            ],
+            [
+                "true".to_string(),
+                derive_color_expression(get_classification_color(u8::MAX)),
+            ],
+        ];
+        assert_eq!(conditions, expected_conditions);
+    }
+
+    #[test]
+    fn test_classification_style_requires_classifications() {
+        let tileset = derive_classified_tileset(|_| None);
+
+        let result = TilesetStyle::Classification
+            .derive_style_document(&tileset, &tileset.derive_octant_statistics());
+
+        assert!(matches!(result, Err(MissingStyleAttribute(_))));
+    }
+
+    #[test]
+    fn test_height_segments_end_with_catch_all() {
+        let tileset = derive_classified_tileset(|_| None);
+        let root_statistics = tileset.derive_octant_statistics()[&OctantIndex::origin()];
+        let minimum_height = root_statistics.minimum_height.expect("minimum height");
+        let maximum_height = root_statistics.maximum_height.expect("maximum height");
+        let gradient =
+            ColorGradient::from_hex_codes(&["#0000ff", "#00ff00", "#ff0000"]).expect("gradient");
+
+        let conditions =
+            derive_conditions(&TilesetStyle::Height { gradient }, &tileset).expect("conditions");
+
+        assert_eq!(conditions.len(), 2);
+        let middle_height = minimum_height + (maximum_height - minimum_height) / 2.0;
+        assert_eq!(
+            conditions[0][0],
+            format!("${{{STYLE_DEFINE_HEIGHT}}} < {middle_height}")
+        );
+        assert!(conditions[0][1].starts_with("mix(color('#0000ff'), color('#00ff00'), "));
+        assert_eq!(conditions[1][0], "true");
+        assert!(conditions[1][1].starts_with("mix(color('#00ff00'), color('#ff0000'), "));
+    }
+
+    #[test]
+    fn test_height_style_of_single_color_gradient() {
+        let tileset = derive_classified_tileset(|_| None);
+        let gradient = ColorGradient::from_hex_codes(&["#ff0000"]).expect("gradient");
+
+        let conditions =
+            derive_conditions(&TilesetStyle::Height { gradient }, &tileset).expect("conditions");
+
+        assert_eq!(
+            conditions,
+            vec![["true".to_string(), "color('#ff0000')".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_height_style_is_skipped_without_heights() {
+        let mut tileset = derive_classified_tileset(|_| None);
+        tileset.root_transform.rotation = UnitQuaternion::identity();
+
+        let conditions = derive_conditions(
+            &TilesetStyle::Height {
+                gradient: ColorGradient::default(),
+            },
+            &tileset,
+        );
+
+        assert!(conditions.is_none());
+    }
+}
@@ -0,0 +1,38 @@
+use ecoord::octree::{Octree, StorageMode};
+use etiles_core::{Tileset, Vertex};
+use nalgebra::{Isometry3, Matrix3, Point3, Rotation3, Translation3, UnitQuaternion, Vector3};
+use palette::Srgb;
+
+/// Creates points on a grid of 10 x 10 x 10 meters without optional attributes.
+pub(crate) fn derive_vertices() -> Vec<Vertex> {
+    (0..1_000)
+        .map(|i| Vertex {
+            position: Point3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64),
+            color: Srgb::new(0.2, 0.4, 0.6),
+            normal: None,
+            classification: None,
+            time: None,
+        })
+        .collect()
+}
+
+/// Returns the rotation of the east, north and up axes on the equator at the prime meridian.
+pub(crate) fn derive_east_north_up_rotation() -> UnitQuaternion<f64> {
+    UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(Matrix3::from_columns(
+        &[Vector3::y(), Vector3::z(), Vector3::x()],
+    )))
+}
+
+/// Builds the tileset of the vertices with its origin on the equator at the prime meridian,
+/// which has heights if the z-axis of the rotation points up there.
+pub(crate) fn derive_tileset(vertices: Vec<Vertex>, rotation: UnitQuaternion<f64>) -> Tileset {
+    let tiled_content =
+        Octree::new(vertices, 100, StorageMode::AllOctants, Some(42)).expect("octree");
+
+    Tileset {
+        tiled_content,
+        root_transform: Isometry3::from_parts(Translation3::new(6_378_137.0, 0.0, 0.0), rotation),
+        root_geometric_error: 20.0,
+        geometric_error: 1.0,
+    }
+}
@@ -0,0 +1,208 @@
+use crate::asset_info::AssetInfo;
+use crate::compression::ArchiveCompression;
+use crate::error::Error;
+use crate::layout::ArchiveLayout;
+use crate::read_impl::read::{decode_tileset, read_archive_entries};
+use crate::sink::tar_sink::TarSink;
+use crate::style::TilesetStyle;
+use crate::write_impl::write::write_incremental;
+use ecoord::octree::OctantIndex;
+use eproj::SpatialReferenceIdentifier;
+use etiles_core::{LocalPlacement, ProgressReporter, Tileset, Vertex};
+use nalgebra::Point3;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// `EtilesUpdater` inserts points into or removes points from the tileset of an existing
+/// archive.
+///
+/// The root transform and octree bounds of the tileset stay fixed. On finishing, only the
+/// content and subtrees of changed octants are re-encoded; all other entries are copied. The
+/// compression of the archive and its entries is kept.
+#[derive(Debug, Clone)]
+pub struct EtilesUpdater {
+    path: PathBuf,
+    archive_compression: ArchiveCompression,
+    previous_entries: HashMap<PathBuf, Vec<u8>>,
+    layout: ArchiveLayout,
+    tileset: Tileset,
+    changed_octant_indices: HashSet<OctantIndex>,
+    progress_reporter: ProgressReporter,
+}
+
+impl EtilesUpdater {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let archive_compression = ArchiveCompression::from_path(&path)?;
+
+        let (previous_entries, precompressed_entries) =
+            read_archive_entries(BufReader::new(File::open(&path)?))?;
+        let (tileset, mut layout) = decode_tileset(&previous_entries)?;
+        layout.precompressed_entries = precompressed_entries;
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            archive_compression,
+            previous_entries,
+            layout,
+            tileset,
+            changed_octant_indices: HashSet::new(),
+            progress_reporter: ProgressReporter::default(),
+        })
+    }
+
+    pub fn tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    /// Returns the asset information of the existing tileset, which is kept on finishing.
+    pub fn asset_info(&self) -> &AssetInfo {
+        &self.layout.asset_info
+    }
+
+    /// Replaces the asset information. Only re-encoded tiles receive the new glTF asset, while
+    /// copied tiles keep theirs.
+    pub fn set_asset_info(&mut self, asset_info: AssetInfo) {
+        self.layout.asset_info = asset_info;
+    }
+
+    /// Regenerates the style of the archive from the updated tileset on finishing. Otherwise, an
+    /// existing style is copied unchanged.
+    pub fn set_style(&mut self, style: TilesetStyle) {
+        self.layout.style = Some(style);
+    }
+
+    /// Reports the encode and archive phases on finishing and stops once the cancellation token
+    /// is cancelled.
+    pub fn set_progress_reporter(&mut self, progress_reporter: ProgressReporter) {
+        self.progress_reporter = progress_reporter;
+    }
+
+    /// Inserts the points into the tileset. Fails without changes if any point lies outside the
+    /// octree bounds of the existing tileset.
+    pub fn insert_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<(), Error> {
+        let changed_octant_indices = self.tileset.insert_point_cloud(
+            point_cloud,
+            source_srs,
+            maximum_points_per_octant,
+            seed_number,
+        )?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Inserts the points of a point cloud in local coordinates without a CRS, placed like the
+    /// existing tileset. Fails without changes if any point lies outside the octree bounds.
+    pub fn insert_local_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        local_placement: LocalPlacement,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<(), Error> {
+        let changed_octant_indices = self.tileset.insert_local_point_cloud(
+            point_cloud,
+            local_placement,
+            maximum_points_per_octant,
+            seed_number,
+        )?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Inserts vertices given in the local frame of the tileset's root transform.
+    pub fn insert_vertices(
+        &mut self,
+        vertices: Vec<Vertex>,
+        maximum_points_per_octant: u64,
+        seed_number: Option<u64>,
+    ) -> Result<(), Error> {
+        let changed_octant_indices =
+            self.tileset
+                .insert_vertices(vertices, maximum_points_per_octant, seed_number)?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Removes the points of a previously inserted point cloud from the tileset. Each point
+    /// removes the closest point within `tolerance`. Fails without changes if any point matches
+    /// no point of the tileset.
+    pub fn remove_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        source_srs: SpatialReferenceIdentifier,
+        tolerance: f64,
+    ) -> Result<(), Error> {
+        let changed_octant_indices =
+            self.tileset
+                .remove_point_cloud(point_cloud, source_srs, tolerance)?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Removes the points of a previously inserted point cloud in local coordinates without a
+    /// CRS, placed like the existing tileset, see [`EtilesUpdater::remove_point_cloud`].
+    pub fn remove_local_point_cloud(
+        &mut self,
+        point_cloud: epoint::PointCloud,
+        local_placement: LocalPlacement,
+        tolerance: f64,
+    ) -> Result<(), Error> {
+        let changed_octant_indices =
+            self.tileset
+                .remove_local_point_cloud(point_cloud, local_placement, tolerance)?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Removes points at positions given in the local frame of the tileset's root transform.
+    pub fn remove_positions(
+        &mut self,
+        positions: Vec<Point3<f64>>,
+        tolerance: f64,
+    ) -> Result<(), Error> {
+        let changed_octant_indices = self.tileset.remove_positions(positions, tolerance)?;
+        self.changed_octant_indices.extend(changed_octant_indices);
+
+        Ok(())
+    }
+
+    /// Writes the updated archive to a temporary file next to the original, which is then
+    /// replaced. On failure or cancellation, the temporary file is removed and the original is
+    /// kept.
+    pub fn finish(self) -> Result<(), Error> {
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        let temporary_path = PathBuf::from(temporary_path);
+
+        let sink = TarSink::new(BufWriter::new(File::create(&temporary_path)?))
+            .with_archive_compression(self.archive_compression);
+        if let Err(error) = write_incremental(
+            sink,
+            &self.tileset,
+            &self.layout,
+            &self.previous_entries,
+            &self.changed_octant_indices,
+            &self.progress_reporter,
+        ) {
+            let _ = fs::remove_file(&temporary_path);
+            return Err(error);
+        }
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(())
+    }
+}
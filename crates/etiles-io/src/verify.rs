@@ -0,0 +1,83 @@
+use crate::compression::ArchiveCompression;
+use crate::error::Error;
+use crate::read_impl::read::read_archive_entries_leniently;
+use crate::read_impl::verify_archive::verify_archive_entries;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::SqliteContainer;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// `EtilesVerifier` checks the integrity of an archive written by `EtilesWriter`.
+///
+/// The entries are checked against the sizes and hashes of the manifest, if the archive has
+/// one, and the subtree and content entries against the availability declared in the subtrees.
+/// Entries that cannot be read or decoded, including a truncated last entry, are reported
+/// instead of failing the verification.
+#[derive(Debug, Clone)]
+pub struct EtilesVerifier<R> {
+    reader: R,
+}
+
+impl<R> EtilesVerifier<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: Read> EtilesVerifier<R> {
+    pub fn finish(self) -> Result<VerificationReport, Error> {
+        let (entries, unreadable_entries) = read_archive_entries_leniently(self.reader)?;
+        Ok(verify_archive_entries(&entries, unreadable_entries))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl EtilesVerifier<SqliteContainer> {
+    pub fn finish(self) -> Result<VerificationReport, Error> {
+        let (entries, unreadable_entries) = self.reader.read_entries_leniently()?;
+        Ok(verify_archive_entries(&entries, unreadable_entries))
+    }
+}
+
+impl EtilesVerifier<BufReader<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        ArchiveCompression::from_path(&path)?;
+
+        let file = File::open(path)?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+/// Findings of verifying an archive, with all paths sorted.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VerificationReport {
+    /// Number of entries in the archive.
+    pub entry_count: usize,
+    /// Whether the archive contains a manifest.
+    pub has_manifest: bool,
+    /// Entries whose size or SHA-256 hash differs from the manifest, entries that cannot be
+    /// decompressed or are truncated, and documents and subtrees that cannot be decoded. An entry
+    /// header that cannot be decoded is listed as `<header of entry {index}>`.
+    pub corrupted_entries: Vec<PathBuf>,
+    /// Entries listed in the manifest, but missing in the archive.
+    pub missing_manifest_entries: Vec<PathBuf>,
+    /// Entries in the archive, but not listed in the manifest.
+    pub extra_manifest_entries: Vec<PathBuf>,
+    /// Subtree and content entries declared available by the subtrees, but missing in the
+    /// archive, or the tileset document if it is missing.
+    pub missing_available_entries: Vec<PathBuf>,
+    /// Subtree and content entries in the archive, but not declared available by the subtrees.
+    pub extra_available_entries: Vec<PathBuf>,
+}
+
+impl VerificationReport {
+    /// Returns true if no entry is corrupted, missing or extra.
+    pub fn is_valid(&self) -> bool {
+        self.corrupted_entries.is_empty()
+            && self.missing_manifest_entries.is_empty()
+            && self.extra_manifest_entries.is_empty()
+            && self.missing_available_entries.is_empty()
+            && self.extra_available_entries.is_empty()
+    }
+}
@@ -1,63 +1,138 @@
-use crate::Error::{InvalidFileExtension, NoFileExtension};
+use crate::archive_header::ArchiveHeaderInfo;
+use crate::asset_info::AssetInfo;
+use crate::compression::ArchiveCompression;
+use crate::content_layer::ContentLayer;
 use crate::error::Error;
-use crate::{
-    CONTENT_DIRECTORY_PATH, FILE_EXTENSION_ETILES_UNCOMPRESSED, LEVELS_PER_SUBTREE,
-    SUBTREES_DIRECTORY_PATH,
-};
-use etiles_core::Tileset;
-
+use crate::layout::ArchiveLayout;
+use crate::metadata::TilesetMetadata;
+use crate::sink::OutputSink;
+use crate::sink::tar_sink::TarSink;
+use crate::style::TilesetStyle;
 use crate::write_impl::write::write;
+use etiles_core::{ProgressReporter, Tileset};
 use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 /// `EtilesWriter` sets up a writer for the custom reader data structure.
 ///
+/// The entries of the tileset are put into an [`OutputSink`], which is a TAR archive written to
+/// a single byte stream unless constructed with [`EtilesWriter::from_sink`].
 #[derive(Debug, Clone)]
-pub struct EtilesWriter<W: Write> {
-    writer: W,
-    content_directory_path: PathBuf,
-    subtrees_directory_path: PathBuf,
-    levels_per_subtree: usize,
+pub struct EtilesWriter<S> {
+    sink: S,
+    layout: ArchiveLayout,
+    progress_reporter: ProgressReporter,
 }
 
-impl<W: Write> EtilesWriter<W> {
+impl<W: Write> EtilesWriter<TarSink<W>> {
     pub fn new(writer: W) -> Self {
-        Self {
-            writer,
-            content_directory_path: CONTENT_DIRECTORY_PATH.into(),
-            subtrees_directory_path: SUBTREES_DIRECTORY_PATH.into(),
-            levels_per_subtree: LEVELS_PER_SUBTREE,
-        }
+        Self::from_sink(TarSink::new(writer))
+    }
+
+    /// Sets the header info of the archive entries, see [`TarSink::with_archive_header_info`].
+    pub fn with_archive_header_info(mut self, archive_header_info: ArchiveHeaderInfo) -> Self {
+        self.sink = self.sink.with_archive_header_info(archive_header_info);
+        self
+    }
+
+    /// Compresses the archive as a whole. When writing to a path, the compression is derived from
+    /// its extension.
+    pub fn with_archive_compression(mut self, archive_compression: ArchiveCompression) -> Self {
+        self.sink = self.sink.with_archive_compression(archive_compression);
+        self
     }
+}
+
+/// Implements the constructor from a sink and the setters of the archive layout and progress
+/// reporting shared by [`EtilesWriter`] and `AsyncEtilesWriter` for sinks with the given bound.
+macro_rules! impl_writer_setters {
+    ($writer:ident, $sink:path) => {
+        impl<S: $sink> $writer<S> {
+            /// Puts the entries into the sink, such as a directory, an object store or a SQLite
+            /// container.
+            pub fn from_sink(sink: S) -> Self {
+                Self {
+                    sink,
+                    layout: ArchiveLayout::default(),
+                    progress_reporter: ProgressReporter::default(),
+                }
+            }
+
+            /// Sets the user-supplied metadata written alongside the computed statistics.
+            pub fn with_metadata(mut self, metadata: TilesetMetadata) -> Self {
+                self.layout.metadata = metadata;
+                self
+            }
+
+            /// Sets the version, copyright and extras written into the tileset and every glTF
+            /// tile.
+            pub fn with_asset_info(mut self, asset_info: AssetInfo) -> Self {
+                self.layout.asset_info = asset_info;
+                self
+            }
 
+            /// Splits the content of each tile into the given layers, written as separate glTF
+            /// files.
+            pub fn with_content_layers(mut self, content_layers: Vec<ContentLayer>) -> Self {
+                self.layout.content_layers = content_layers;
+                self
+            }
+
+            /// Generates a declarative style for the tileset, written as `style.json` into the
+            /// archive.
+            pub fn with_style(mut self, style: TilesetStyle) -> Self {
+                self.layout.style = Some(style);
+                self
+            }
+
+            /// Reports the encode and archive phases and stops once the cancellation token is
+            /// cancelled.
+            pub fn with_progress_reporter(mut self, progress_reporter: ProgressReporter) -> Self {
+                self.progress_reporter = progress_reporter;
+                self
+            }
+
+            /// Adds `manifest.json` listing the size and SHA-256 hash of every other entry, which
+            /// allows verifying the archive after transfer.
+            pub fn with_manifest(mut self, manifest: bool) -> Self {
+                self.layout.manifest = manifest;
+                self
+            }
+
+            /// Stores each entry gzip-compressed, while the tileset keeps referring to the
+            /// uncompressed names. Archives and directories append the extension `.gz`, so that
+            /// the entries can be served by static hosting with `Content-Encoding: gzip`.
+            pub fn with_precompressed_entries(mut self, precompressed_entries: bool) -> Self {
+                self.layout.precompressed_entries = precompressed_entries;
+                self
+            }
+        }
+    };
+}
+pub(crate) use impl_writer_setters;
+
+impl_writer_setters!(EtilesWriter, OutputSink);
+
+impl<S: OutputSink> EtilesWriter<S> {
     pub fn finish(self, tileset: &Tileset) -> Result<(), Error> {
-        write(
-            self.writer,
-            tileset,
-            self.content_directory_path,
-            self.subtrees_directory_path,
-            self.levels_per_subtree,
-        )?;
+        write(self.sink, tileset, &self.layout, &self.progress_reporter)?;
 
         Ok(())
     }
 }
 
-impl EtilesWriter<File> {
+impl EtilesWriter<TarSink<File>> {
+    /// Creates the archive at the path, which must have the extension `.tar`, `.tar.gz` or
+    /// `.tar.zst`.
     pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
-        let extension = path.as_ref().extension().ok_or(NoFileExtension())?;
-        if extension != FILE_EXTENSION_ETILES_UNCOMPRESSED {
-            return Err(InvalidFileExtension(
-                extension.to_str().unwrap_or_default().to_string(),
-            ));
-        }
+        let archive_compression = ArchiveCompression::from_path(&path)?;
 
         let file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
             .open(path)?;
-        Ok(Self::new(file))
+        Ok(Self::new(file).with_archive_compression(archive_compression))
     }
 }
@@ -0,0 +1,63 @@
+use crate::archive_header::ArchiveHeaderInfo;
+use crate::asset_info::AssetInfo;
+use crate::compression::ArchiveCompression;
+use crate::content_layer::ContentLayer;
+use crate::error::Error;
+use crate::layout::ArchiveLayout;
+use crate::metadata::TilesetMetadata;
+use crate::sink::AsyncOutputSink;
+use crate::sink::async_tar_sink::AsyncTarSink;
+use crate::style::TilesetStyle;
+use crate::write::impl_writer_setters;
+use crate::write_impl::write_async::write_async;
+use etiles_core::{ProgressReporter, Tileset};
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+/// `AsyncEtilesWriter` writes a tileset like [`EtilesWriter`](crate::EtilesWriter) from within
+/// an asynchronous runtime.
+///
+/// The entries are encoded on the rayon thread pool, while putting them into the
+/// [`AsyncOutputSink`] is awaited. Without a manifest, the content is put as it is encoded, so
+/// only a bounded number of encoded entries is held in memory. The output is identical to the
+/// one of `EtilesWriter` with the same configuration.
+#[derive(Debug, Clone)]
+pub struct AsyncEtilesWriter<S> {
+    sink: S,
+    layout: ArchiveLayout,
+    progress_reporter: ProgressReporter,
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncEtilesWriter<AsyncTarSink<W>> {
+    pub fn new(writer: W) -> Self {
+        Self::from_sink(AsyncTarSink::new(writer))
+    }
+
+    /// Sets the header info of the archive entries, see
+    /// [`TarSink::with_archive_header_info`](crate::TarSink::with_archive_header_info).
+    pub fn with_archive_header_info(mut self, archive_header_info: ArchiveHeaderInfo) -> Self {
+        self.sink = self.sink.with_archive_header_info(archive_header_info);
+        self
+    }
+
+    /// Compresses the archive as a whole.
+    pub fn with_archive_compression(mut self, archive_compression: ArchiveCompression) -> Self {
+        self.sink = self.sink.with_archive_compression(archive_compression);
+        self
+    }
+}
+
+impl_writer_setters!(AsyncEtilesWriter, AsyncOutputSink);
+
+impl<S: AsyncOutputSink> AsyncEtilesWriter<S> {
+    /// Writes the tileset, which is shared with the encoding on the rayon thread pool.
+    pub async fn finish(self, tileset: impl Into<Arc<Tileset>>) -> Result<(), Error> {
+        write_async(
+            self.sink,
+            tileset.into(),
+            self.layout,
+            self.progress_reporter,
+        )
+        .await
+    }
+}
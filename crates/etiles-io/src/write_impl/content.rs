@@ -1,3 +1,5 @@
+use crate::Error;
+use crate::asset_info::AssetInfo;
 use crate::write_impl::write_gltf_tile::write_gltf_tile;
 
 #[derive(Copy, Clone, Debug, bytemuck::NoUninit)]
@@ -7,14 +9,34 @@ pub struct EncodablePosition {
     pub color: [f32; 3],
 }
 
+#[derive(Copy, Clone, Debug, bytemuck::NoUninit)]
+#[repr(C)]
+pub struct EncodableNormal {
+    pub normal: [f32; 3],
+}
+
+/// Classification code padded to the four-byte alignment required for vertex attributes.
+#[derive(Copy, Clone, Debug, bytemuck::NoUninit)]
+#[repr(C)]
+pub struct EncodableClassification {
+    pub classification: u8,
+    pub padding: [u8; 3],
+}
+
+#[derive(Copy, Clone, Debug, bytemuck::NoUninit)]
+#[repr(C)]
+pub struct EncodableTime {
+    pub time: f32,
+}
+
 pub trait EncodableContent {
-    fn encode(&self) -> Result<Vec<u8>, etiles_core::Error>;
+    fn encode(&self, asset_info: &AssetInfo, center_positions: bool) -> Result<Vec<u8>, Error>;
 }
 
 impl EncodableContent for &Vec<etiles_core::Vertex> {
-    fn encode(&self) -> Result<Vec<u8>, etiles_core::Error> {
+    fn encode(&self, asset_info: &AssetInfo, center_positions: bool) -> Result<Vec<u8>, Error> {
         let mut point_data_buffer: Vec<u8> = Vec::new();
-        write_gltf_tile(&mut point_data_buffer, self).expect("TODO: panic message");
+        write_gltf_tile(&mut point_data_buffer, self, asset_info, center_positions)?;
 
         Ok(point_data_buffer)
     }
@@ -1,5 +1,7 @@
 pub(crate) mod content;
 pub(crate) mod write;
+#[cfg(feature = "async")]
+pub(crate) mod write_async;
 pub mod write_gltf_tile;
 pub mod write_subtree;
 
@@ -1,75 +1,311 @@
+use crate::Error::NonUtf8Path;
+use crate::asset_info::AssetInfo;
+use crate::compression::gzip_entry_content;
+use crate::content_layer::{
+    ContentLayer, METADATA_CLASS_CONTENT_LAYER, derive_content_layer_class,
+    derive_content_layer_index, validate_content_layers,
+};
 use crate::documents::bounding_volume::BoundingVolume;
 use crate::documents::content::Content;
 use crate::documents::implicit_tiling::{ImplicitTiling, SubdivisionScheme, Subtrees};
 use crate::documents::tile::{Refinement, Tile};
 use crate::documents::tileset::TilesetDocument;
+use crate::layout::ArchiveLayout;
+use crate::manifest::write_manifest_json;
+use crate::metadata::TilesetMetadata;
+use crate::sink::{ContentEncoding, OutputSink};
 use crate::write_impl::write_subtree::write_subtree;
-use crate::{EncodableContent, Error, FILE_NAME_TILESET_JSON};
-use chrono::Utc;
+use crate::{
+    EncodableContent, Error, FILE_NAME_MANIFEST_JSON, FILE_NAME_STYLE_JSON, FILE_NAME_TILESET_JSON,
+};
 use ecoord::octree::{OctantIndex, Octree};
-use etiles_core::{BoundingCube, Tileset, Vertex};
-use std::collections::HashMap;
-use std::io::{Cursor, Write};
-use std::path::PathBuf;
+use etiles_core::{
+    BoundingCube, OctantStatistics, ProgressPhase, ProgressReporter, Tileset, Vertex,
+};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-pub fn write<W: Write>(
-    writer: W,
+pub fn write<S: OutputSink>(
+    sink: S,
     tileset: &Tileset,
-    content_directory_path: PathBuf,
-    subtrees_directory_path: PathBuf,
-    levels_per_subtree: usize,
+    layout: &ArchiveLayout,
+    progress_reporter: &ProgressReporter,
 ) -> Result<(), Error> {
-    let mut archive_builder = tar::Builder::new(writer);
+    let archive_entries = derive_archive_entries(tileset, layout, progress_reporter)?;
+    put_archive_entries(sink, archive_entries, layout, progress_reporter)
+}
+
+/// Encodes all entries of the archive in their reproducible order, including the manifest.
+pub(crate) fn derive_archive_entries(
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    progress_reporter: &ProgressReporter,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    let metadata = derive_metadata(tileset, layout);
+    let mut archive_entries = derive_leading_archive_entries(tileset, layout, &metadata)?;
+
+    let encoded_content_tiles = encode_cells(
+        tileset.tiled_content.cells().iter(),
+        tileset.tiled_content.cells().len() as u64,
+        layout,
+        derive_center_positions(tileset),
+        progress_reporter,
+    )?;
+    archive_entries.extend(
+        encoded_content_tiles
+            .into_iter()
+            .map(|(path, buffer)| (layout.content_directory_path.join(path), buffer)),
+    );
+
+    order_archive_entries(archive_entries, layout)
+}
 
-    //info!("Writing write_tileset_json");
-    let tileset_document_buffer = write_tileset_json(
+/// Returns the user-supplied metadata with the time reference of the tileset.
+pub(crate) fn derive_metadata(tileset: &Tileset, layout: &ArchiveLayout) -> TilesetMetadata {
+    let mut metadata = layout.metadata.clone();
+    metadata.derive_time_reference(tileset);
+    metadata
+}
+
+/// Encodes the entries preceding the content, i.e. the tileset document, the style and the
+/// subtrees, in no particular order.
+pub(crate) fn derive_leading_archive_entries(
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    metadata: &TilesetMetadata,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    validate_content_layers(
+        &layout.content_layers,
+        tileset.tiled_content.cells().values().flatten(),
+    )?;
+    let octant_statistics = tileset.derive_octant_statistics();
+
+    let mut archive_entries: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let tileset_document_buffer =
+        derive_tileset_document_buffer(tileset, layout, metadata, &octant_statistics)?;
+    archive_entries.push((FILE_NAME_TILESET_JSON.into(), tileset_document_buffer));
+    if let Some(style) = &layout.style
+        && let Some(style_document_buffer) = style.write_style_json(tileset, &octant_statistics)?
+    {
+        archive_entries.push((FILE_NAME_STYLE_JSON.into(), style_document_buffer));
+    }
+
+    let subtree_binaries = write_subtree_info(
+        layout.levels_per_subtree,
+        &tileset.tiled_content,
+        &octant_statistics,
+        &layout.content_layers,
+    )?;
+    archive_entries.extend(
+        subtree_binaries
+            .into_iter()
+            .map(|(name, buffer)| (layout.subtrees_directory_path.join(name), buffer)),
+    );
+
+    Ok(archive_entries)
+}
+
+/// Writes the archive like [`write`], but copies the content and subtrees that are not
+/// affected by the changed octants from the previous archive entries instead of re-encoding them.
+///
+/// Further entries of the previous archive, such as a style that is not regenerated, are copied
+/// unchanged.
+pub fn write_incremental<S: OutputSink>(
+    sink: S,
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    previous_entries: &HashMap<PathBuf, Vec<u8>>,
+    changed_octant_indices: &HashSet<OctantIndex>,
+    progress_reporter: &ProgressReporter,
+) -> Result<(), Error> {
+    let archive_entries = derive_incremental_archive_entries(
         tileset,
-        &content_directory_path,
-        &subtrees_directory_path,
-        levels_per_subtree,
+        layout,
+        previous_entries,
+        changed_octant_indices,
+        progress_reporter,
     )?;
-    //info!("Writing append_data");
-    archive_builder.append_data(
-        &mut create_archive_header(tileset_document_buffer.len(), None),
-        FILE_NAME_TILESET_JSON,
-        Cursor::new(tileset_document_buffer),
+    put_archive_entries(sink, archive_entries, layout, progress_reporter)
+}
+
+/// Derives the entries like [`derive_archive_entries`], but copies unaffected content and
+/// subtrees from the previous archive entries.
+pub(crate) fn derive_incremental_archive_entries(
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    previous_entries: &HashMap<PathBuf, Vec<u8>>,
+    changed_octant_indices: &HashSet<OctantIndex>,
+    progress_reporter: &ProgressReporter,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    validate_content_layers(
+        &layout.content_layers,
+        tileset.tiled_content.cells().values().flatten(),
     )?;
+    let octant_statistics = tileset.derive_octant_statistics();
+    let metadata = derive_metadata(tileset, layout);
+    let content_directory_path = &layout.content_directory_path;
+    let subtrees_directory_path = &layout.subtrees_directory_path;
 
-    //info!("Writing write_subtree_info");
-    let subtree_binaries = write_subtree_info(levels_per_subtree, &tileset.tiled_content)?;
-    for (current_subtree_binary_name, current_data_buffer) in subtree_binaries {
-        archive_builder.append_data(
-            &mut create_archive_header(current_data_buffer.len(), None),
-            subtrees_directory_path.join(current_subtree_binary_name),
-            Cursor::new(current_data_buffer),
-        )?;
+    let mut archive_entries: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let tileset_document_buffer =
+        derive_tileset_document_buffer(tileset, layout, &metadata, &octant_statistics)?;
+    archive_entries.push((FILE_NAME_TILESET_JSON.into(), tileset_document_buffer));
+    let style_document_buffer = match &layout.style {
+        Some(style) => style.write_style_json(tileset, &octant_statistics)?,
+        None => previous_entries
+            .get(Path::new(FILE_NAME_STYLE_JSON))
+            .cloned(),
+    };
+    if let Some(style_document_buffer) = style_document_buffer {
+        archive_entries.push((FILE_NAME_STYLE_JSON.into(), style_document_buffer));
     }
 
-    //info!("Writing tileset.tiled_content");
-    let encoded_content_tiles: HashMap<String, Vec<u8>> = tileset
-        .tiled_content
-        .cell_indices()
-        .into_iter()
-        .map(|x| {
-            //info!("Get cell content: {}", x);
-            let cell_content = tileset.tiled_content.cell(x).expect("must be contained");
-
-            //info!("Encode cell content: {}", x);
-            (derive_content_filename(&x), cell_content.encode().unwrap())
-        })
+    // subtrees and content of changed octants are not copied, as they may have become empty
+    let affected_subtree_root_indices =
+        derive_affected_subtree_root_indices(changed_octant_indices, layout.levels_per_subtree);
+    let mut subtree_binaries = write_subtree_info_of_octants(
+        layout.levels_per_subtree,
+        &tileset.tiled_content,
+        &octant_statistics,
+        &layout.content_layers,
+        affected_subtree_root_indices
+            .iter()
+            .copied()
+            .filter(|i| tileset.tiled_content.occupancy_graph().is_cell_occupied(*i))
+            .collect(),
+    )?;
+    for (path, buffer) in previous_entries {
+        let file_name = path.file_name().and_then(|f| f.to_str());
+        if path.parent() == Some(subtrees_directory_path.as_path())
+            && let Some(file_name) = file_name
+            && parse_subtree_filename(file_name)
+                .is_none_or(|i| !affected_subtree_root_indices.contains(&i))
+        {
+            subtree_binaries
+                .entry(file_name.to_string())
+                .or_insert_with(|| buffer.clone());
+        }
+    }
+    archive_entries.extend(
+        subtree_binaries
+            .into_iter()
+            .map(|(name, buffer)| (subtrees_directory_path.join(name), buffer)),
+    );
+
+    let changed_cells: Vec<(&OctantIndex, &Vec<Vertex>)> = changed_octant_indices
+        .iter()
+        .filter_map(|x| tileset.tiled_content.cell(*x).map(|c| (x, c)))
         .collect();
-    //info!(
-    //    "Writing encoded_content_tiles: {}",
-    //    tileset.tiled_content.cell_count()
-    //);
-    for (current_encoded_content_tile_name, current_data_buffer) in encoded_content_tiles {
-        archive_builder.append_data(
-            &mut create_archive_header(current_data_buffer.len(), None),
-            content_directory_path.join(current_encoded_content_tile_name),
-            Cursor::new(current_data_buffer),
-        )?;
+    let mut encoded_content_tiles = encode_cells(
+        changed_cells.iter().copied(),
+        changed_cells.len() as u64,
+        layout,
+        derive_center_positions(tileset),
+        progress_reporter,
+    )?;
+    for (path, buffer) in previous_entries {
+        if let Ok(relative_path) = path.strip_prefix(content_directory_path)
+            && relative_path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .and_then(parse_content_filename)
+                .is_none_or(|i| !changed_octant_indices.contains(&i))
+        {
+            encoded_content_tiles
+                .entry(relative_path.to_path_buf())
+                .or_insert_with(|| buffer.clone());
+        }
     }
-    //info!("Finished encoded_content_tiles");
+    archive_entries.extend(
+        encoded_content_tiles
+            .into_iter()
+            .map(|(path, buffer)| (content_directory_path.join(path), buffer)),
+    );
+
+    order_archive_entries(archive_entries, layout)
+}
+
+/// Encodes the content of the cells, reported as encode phase.
+fn encode_cells<'a>(
+    cells: impl Iterator<Item = (&'a OctantIndex, &'a Vec<Vertex>)>,
+    cell_count: u64,
+    layout: &ArchiveLayout,
+    center_positions: bool,
+    progress_reporter: &ProgressReporter,
+) -> Result<HashMap<PathBuf, Vec<u8>>, Error> {
+    let encode_progress = progress_reporter.begin_phase(ProgressPhase::Encode, cell_count)?;
+    let mut encoded_content_tiles: HashMap<PathBuf, Vec<u8>> = HashMap::new();
+    for (octant_index, cell_content) in cells {
+        encoded_content_tiles.extend(encode_cell_contents(
+            octant_index,
+            cell_content,
+            &layout.content_layers,
+            &layout.asset_info,
+            center_positions,
+        )?);
+        encode_progress.advance(1)?;
+    }
+    encode_progress.finish()?;
+
+    Ok(encoded_content_tiles)
+}
+
+/// Sorts the entries into a reproducible order and inserts the manifest, which lists the entries
+/// before they are precompressed.
+fn order_archive_entries(
+    mut archive_entries: Vec<(PathBuf, Vec<u8>)>,
+    layout: &ArchiveLayout,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    archive_entries.sort_by_cached_key(|(path, _)| derive_archive_entry_order(path, layout));
+    if layout.manifest {
+        let manifest_document_buffer = write_manifest_json(&archive_entries)?;
+        let manifest_order = derive_archive_entry_order(Path::new(FILE_NAME_MANIFEST_JSON), layout);
+        let manifest_position = archive_entries
+            .partition_point(|(p, _)| derive_archive_entry_order(p, layout) < manifest_order);
+        archive_entries.insert(
+            manifest_position,
+            (FILE_NAME_MANIFEST_JSON.into(), manifest_document_buffer),
+        );
+    }
+
+    Ok(archive_entries)
+}
+
+/// Compresses the content of the entries in parallel with the content encoding, if any.
+pub(crate) fn precompress_archive_entries(
+    archive_entries: Vec<(PathBuf, Vec<u8>)>,
+    content_encoding: Option<ContentEncoding>,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    match content_encoding {
+        Some(ContentEncoding::Gzip) => archive_entries
+            .into_par_iter()
+            .map(|(path, buffer)| Ok((path, gzip_entry_content(&buffer)?)))
+            .collect(),
+        None => Ok(archive_entries),
+    }
+}
+
+/// Puts the ordered entries into the sink and finalizes it, reported as archive phase.
+fn put_archive_entries<S: OutputSink>(
+    mut sink: S,
+    archive_entries: Vec<(PathBuf, Vec<u8>)>,
+    layout: &ArchiveLayout,
+    progress_reporter: &ProgressReporter,
+) -> Result<(), Error> {
+    let content_encoding = layout
+        .precompressed_entries
+        .then_some(ContentEncoding::Gzip);
+    let archive_entries = precompress_archive_entries(archive_entries, content_encoding)?;
+
+    let archive_progress =
+        progress_reporter.begin_phase(ProgressPhase::Archive, archive_entries.len() as u64)?;
+    for (path, buffer) in archive_entries {
+        sink.put(&path, buffer, content_encoding)?;
+        archive_progress.advance(1)?;
+    }
+    sink.finalize()?;
+    archive_progress.finish()?;
 
     Ok(())
 }
@@ -77,12 +313,13 @@ pub fn write<W: Write>(
 pub fn write_subtree_info(
     levels_per_subtree: usize,
     content_octree: &Octree<Vertex>,
+    octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+    content_layers: &[ContentLayer],
 ) -> Result<HashMap<String, Vec<u8>>, Error> {
-    let mut subtree_infos = HashMap::new();
     let max_occupied_level = if let Some(v) = content_octree.get_max_occupied_level() {
         v
     } else {
-        return Ok(subtree_infos);
+        return Ok(HashMap::new());
     };
 
     let occupied_octant_indices: Vec<OctantIndex> = (0..=max_occupied_level)
@@ -94,50 +331,165 @@ pub fn write_subtree_info(
         })
         .collect();
 
-    for current_occupied_octant_index in occupied_octant_indices {
+    write_subtree_info_of_octants(
+        levels_per_subtree,
+        content_octree,
+        octant_statistics,
+        content_layers,
+        occupied_octant_indices,
+    )
+}
+
+fn write_subtree_info_of_octants(
+    levels_per_subtree: usize,
+    content_octree: &Octree<Vertex>,
+    octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+    content_layers: &[ContentLayer],
+    subtree_root_indices: Vec<OctantIndex>,
+) -> Result<HashMap<String, Vec<u8>>, Error> {
+    let mut subtree_infos = HashMap::new();
+
+    for current_occupied_octant_index in subtree_root_indices {
         let mut subtree_info_buffer: Vec<u8> = Vec::new();
         write_subtree(
             &mut subtree_info_buffer,
             current_occupied_octant_index,
             levels_per_subtree,
             content_octree,
-        )
-        .expect("should work");
-
-        let file_name = format!(
-            "{}__{}_{}_{}.subtree",
-            current_occupied_octant_index.level,
-            current_occupied_octant_index.x,
-            current_occupied_octant_index.y,
-            current_occupied_octant_index.z
+            Some(octant_statistics),
+            content_layers,
+        )?;
+
+        subtree_infos.insert(
+            derive_subtree_filename(&current_occupied_octant_index),
+            subtree_info_buffer,
         );
-        subtree_infos.insert(file_name, subtree_info_buffer);
     }
 
     Ok(subtree_infos)
 }
 
+/// Returns the roots of all subtrees whose availability may change by adding or removing content
+/// of the octants, including the subtrees of newly occupied siblings along the paths to the root.
+fn derive_affected_subtree_root_indices(
+    changed_octant_indices: &HashSet<OctantIndex>,
+    levels_per_subtree: usize,
+) -> HashSet<OctantIndex> {
+    let possibly_occupied_octant_indices: HashSet<OctantIndex> = changed_octant_indices
+        .iter()
+        .flat_map(|i| i.get_ancestors())
+        .flat_map(|i| std::iter::once(i).chain(i.get_children()))
+        .collect();
+
+    let mut subtree_root_indices = HashSet::new();
+    for octant_index in possibly_occupied_octant_indices {
+        subtree_root_indices.insert(get_subtree_root_index(octant_index, levels_per_subtree));
+
+        // child subtree availability of the parent subtree references subtree roots
+        if (octant_index.level as usize).is_multiple_of(levels_per_subtree)
+            && let Some(parent_index) = octant_index.get_parent()
+        {
+            subtree_root_indices.insert(get_subtree_root_index(parent_index, levels_per_subtree));
+        }
+    }
+
+    subtree_root_indices
+}
+
+fn get_subtree_root_index(octant_index: OctantIndex, levels_per_subtree: usize) -> OctantIndex {
+    let mut subtree_root_index = octant_index;
+    while !(subtree_root_index.level as usize).is_multiple_of(levels_per_subtree)
+        && let Some(parent_index) = subtree_root_index.get_parent()
+    {
+        subtree_root_index = parent_index;
+    }
+
+    subtree_root_index
+}
+
+/// Writes the tileset document without content layers, style or user-supplied metadata.
 pub fn write_tileset_json(
     tileset: &Tileset,
-    content_directory_path: &PathBuf,
-    subtrees_directory_path: &PathBuf,
+    content_directory_path: &Path,
+    subtrees_directory_path: &Path,
     levels_per_subtree: usize,
 ) -> Result<Vec<u8>, Error> {
-    let tile = derive_implicit_tile_from_content_octree(
+    let layout = ArchiveLayout {
+        content_directory_path: content_directory_path.to_path_buf(),
+        subtrees_directory_path: subtrees_directory_path.to_path_buf(),
+        levels_per_subtree,
+        ..ArchiveLayout::default()
+    };
+    let metadata = derive_metadata(tileset, &layout);
+    let octant_statistics = tileset.derive_octant_statistics();
+    derive_tileset_document_buffer(tileset, &layout, &metadata, &octant_statistics)
+}
+
+/// Encodes the tileset document of the layout. With content layers, the root tile references one
+/// content per layer, each belonging to the metadata group of its layer.
+fn derive_tileset_document_buffer(
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    metadata: &TilesetMetadata,
+    octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+) -> Result<Vec<u8>, Error> {
+    let content_directory_path = &layout.content_directory_path;
+    let content_layers = &layout.content_layers;
+    let mut tile = derive_implicit_tile_from_content_octree(
         OctantIndex::origin(),
         content_directory_path,
-        subtrees_directory_path,
-        levels_per_subtree,
+        &layout.subtrees_directory_path,
+        layout.levels_per_subtree,
         tileset.geometric_error,
         &tileset.tiled_content,
     )?;
 
-    // info!("diagonal: {root_geometric_error}");
-    let tileset_document = TilesetDocument::new(
+    let mut groups = metadata.derive_groups();
+    if !content_layers.is_empty() {
+        tile.content = None;
+        tile.contents = content_layers
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                Ok(Content {
+                    uri: derive_content_uri_template(&content_directory_path.join(&l.name))?,
+                    group: Some((groups.len() + i) as u32),
+                })
+            })
+            .collect::<Result<Vec<Content>, Error>>()?;
+        groups.extend(content_layers.iter().map(|l| l.derive_group_entity()));
+    } else if let Some(content) = tile.content.as_mut()
+        && !groups.is_empty()
+    {
+        content.group = Some(0);
+    }
+
+    let point_count = tileset
+        .tiled_content
+        .cells()
+        .values()
+        .map(|c| c.len() as u64)
+        .sum();
+    let mut tileset_document = TilesetDocument::new(
         tile,
         Some(tileset.root_transform),
         tileset.root_geometric_error,
     );
+    let mut schema = metadata.derive_schema(tileset.derive_origin_height().is_some())?;
+    if !content_layers.is_empty() {
+        schema.classes.insert(
+            METADATA_CLASS_CONTENT_LAYER.to_string(),
+            derive_content_layer_class(),
+        );
+    }
+    tileset_document.schema = Some(schema);
+    tileset_document.metadata = Some(
+        metadata.derive_tileset_entity(point_count, octant_statistics.get(&OctantIndex::origin())),
+    );
+    tileset_document.groups = groups;
+    layout
+        .asset_info
+        .apply_to_tileset_document(&mut tileset_document);
     let mut tileset_document_buffer: Vec<u8> = Vec::new();
     serde_json::to_writer_pretty(&mut tileset_document_buffer, &tileset_document)?;
     Ok(tileset_document_buffer)
@@ -145,8 +497,8 @@ pub fn write_tileset_json(
 
 pub fn derive_implicit_tile_from_content_octree(
     index: OctantIndex,
-    content_directory_path: &PathBuf,
-    subtree_directory_path: &PathBuf,
+    content_directory_path: &Path,
+    subtree_directory_path: &Path,
     levels_per_subtree: usize,
     geometric_error: f64,
     content_octree: &Octree<Vertex>,
@@ -156,14 +508,11 @@ pub fn derive_implicit_tile_from_content_octree(
 
     let tile = Tile {
         geometric_error,
-        content: Content {
-            uri: content_directory_path
-                .clone()
-                .join("pc_{level}__{x}_{y}_{z}.glb")
-                .to_str()
-                .unwrap()
-                .to_string(),
-        },
+        content: Some(Content {
+            uri: derive_content_uri_template(content_directory_path)?,
+            group: None,
+        }),
+        contents: vec![],
         bounding_volume: BoundingVolume::Box(bounding_cube.bounding_array()),
         children: vec![],
         transform: None,
@@ -173,12 +522,7 @@ pub fn derive_implicit_tile_from_content_octree(
             subtree_levels: levels_per_subtree as u16,
             available_levels: content_octree.get_max_occupied_level().unwrap_or_default() as u16,
             subtrees: Subtrees {
-                uri: subtree_directory_path
-                    .clone()
-                    .join("{level}__{x}_{y}_{z}.subtree")
-                    .to_str()
-                    .unwrap()
-                    .to_string(),
+                uri: derive_uri(subtree_directory_path.join("{level}__{x}_{y}_{z}.subtree"))?,
             },
         }),
     };
@@ -186,6 +530,111 @@ pub fn derive_implicit_tile_from_content_octree(
     Ok(tile)
 }
 
+fn derive_content_uri_template(content_directory_path: &Path) -> Result<String, Error> {
+    derive_uri(content_directory_path.join("pc_{level}__{x}_{y}_{z}.glb"))
+}
+
+/// Converts a path relative to the tileset document into a URI, which must be valid UTF-8.
+fn derive_uri(path: PathBuf) -> Result<String, Error> {
+    path.into_os_string()
+        .into_string()
+        .map_err(|p| NonUtf8Path(p.into()))
+}
+
+/// Returns whether the tile positions are stored relative to the tile centers, which is the
+/// case for tilesets without heights, whose local coordinates may be far from the origin. With
+/// heights, the positions stay in the local frame, from which the height style derives them.
+pub(crate) fn derive_center_positions(tileset: &Tileset) -> bool {
+    tileset.derive_origin_height().is_none()
+}
+
+/// Encodes the content of an octant, split into one file per non-empty content layer if any
+/// are given. Returns the encoded files with their paths relative to the content directory.
+pub(crate) fn encode_cell_contents(
+    index: &OctantIndex,
+    cell_content: &Vec<Vertex>,
+    content_layers: &[ContentLayer],
+    asset_info: &AssetInfo,
+    center_positions: bool,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    let file_name = derive_content_filename(index);
+    if content_layers.is_empty() {
+        return Ok(vec![(
+            file_name.into(),
+            cell_content.encode(asset_info, center_positions)?,
+        )]);
+    }
+
+    let mut layer_contents: Vec<Vec<Vertex>> = vec![Vec::new(); content_layers.len()];
+    for vertex in cell_content {
+        if let Some(layer_index) = derive_content_layer_index(content_layers, vertex) {
+            layer_contents[layer_index].push(*vertex);
+        }
+    }
+
+    content_layers
+        .iter()
+        .zip(layer_contents)
+        .filter(|(_, c)| !c.is_empty())
+        .map(|(l, c)| {
+            Ok((
+                Path::new(&l.name).join(&file_name),
+                (&c).encode(asset_info, center_positions)?,
+            ))
+        })
+        .collect()
+}
+
+/// Sort key of an archive entry: `tileset.json`, `style.json`, `manifest.json`, the subtrees and
+/// the content, with subtrees and content ordered by the level and Morton index of their octant
+/// and content of the same octant by layer. Other entries follow by path.
+pub(crate) fn derive_archive_entry_order(
+    path: &Path,
+    layout: &ArchiveLayout,
+) -> (u8, u32, u64, usize, PathBuf) {
+    let file_name = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_default();
+
+    if path == Path::new(FILE_NAME_TILESET_JSON) {
+        return (0, 0, 0, 0, PathBuf::new());
+    }
+    if path == Path::new(FILE_NAME_STYLE_JSON) {
+        return (1, 0, 0, 0, PathBuf::new());
+    }
+    if path == Path::new(FILE_NAME_MANIFEST_JSON) {
+        return (2, 0, 0, 0, PathBuf::new());
+    }
+    if path.parent() == Some(layout.subtrees_directory_path.as_path())
+        && let Some(octant_index) = parse_subtree_filename(file_name)
+    {
+        let (level, morton_index) = octant_order(octant_index);
+        return (3, level, morton_index, 0, path.to_path_buf());
+    }
+    if let Ok(relative_path) = path.strip_prefix(&layout.content_directory_path)
+        && let Some(octant_index) = parse_content_filename(file_name)
+    {
+        let layer_index = relative_path
+            .parent()
+            .and_then(|p| p.to_str())
+            .and_then(|p| layout.content_layers.iter().position(|l| l.name == p))
+            .unwrap_or_default();
+        let (level, morton_index) = octant_order(octant_index);
+        return (4, level, morton_index, layer_index, path.to_path_buf());
+    }
+
+    (5, 0, 0, 0, path.to_path_buf())
+}
+
+/// Sort key of the subtree or content of an octant by its level and Morton index.
+pub(crate) fn octant_order(octant_index: OctantIndex) -> (u32, u64) {
+    (
+        octant_index.level,
+        octant_index.morton_index().unwrap_or(u64::MAX),
+    )
+}
+
 pub fn derive_content_filename(index: &OctantIndex) -> String {
     format!(
         "pc_{}__{}_{}_{}.glb",
@@ -193,14 +642,32 @@ pub fn derive_content_filename(index: &OctantIndex) -> String {
     )
 }
 
-fn create_archive_header(size: usize, time: Option<chrono::DateTime<Utc>>) -> tar::Header {
-    let mut header = tar::Header::new_gnu();
-    header.set_size(size as u64);
-    header.set_mode(0o664);
-    if let Some(time) = time {
-        header.set_mtime(time.timestamp() as u64);
-    }
-    header.set_cksum();
+/// Parses the octant index from a content file name derived by [`derive_content_filename`].
+pub fn parse_content_filename(file_name: &str) -> Option<OctantIndex> {
+    parse_octant_filename(file_name.strip_prefix("pc_")?.strip_suffix(".glb")?)
+}
+
+/// Parses the octant index from a subtree file name derived by [`derive_subtree_filename`].
+pub(crate) fn parse_subtree_filename(file_name: &str) -> Option<OctantIndex> {
+    parse_octant_filename(file_name.strip_suffix(".subtree")?)
+}
+
+fn parse_octant_filename(file_stem: &str) -> Option<OctantIndex> {
+    let (level, coordinates) = file_stem.split_once("__")?;
+    let mut coordinates = coordinates.splitn(3, '_').map(|c| c.parse::<u64>());
+
+    OctantIndex::new(
+        level.parse().ok()?,
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+    )
+    .ok()
+}
 
-    header
+pub fn derive_subtree_filename(index: &OctantIndex) -> String {
+    format!(
+        "{}__{}_{}_{}.subtree",
+        index.level, index.x, index.y, index.z
+    )
 }
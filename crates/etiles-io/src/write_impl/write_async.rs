@@ -0,0 +1,162 @@
+use crate::content_layer::derive_content_layer_index;
+use crate::error::Error;
+use crate::layout::ArchiveLayout;
+use crate::sink::{AsyncOutputSink, ContentEncoding};
+use crate::write_impl::write::{
+    derive_archive_entries, derive_archive_entry_order, derive_center_positions,
+    derive_leading_archive_entries, derive_metadata, encode_cell_contents, octant_order,
+    precompress_archive_entries,
+};
+use ecoord::octree::OctantIndex;
+use etiles_core::{ProgressPhase, ProgressReporter, Tileset, Vertex};
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Number of entries encoded ahead of the sink.
+const ENTRY_CHANNEL_CAPACITY: usize = 64;
+
+/// Leading entries with the total number of entries, and the remaining entries as they are
+/// encoded.
+type LeadingArchiveEntries = (u64, Vec<(PathBuf, Vec<u8>)>);
+type ArchiveEntryReceiver = mpsc::Receiver<Result<(PathBuf, Vec<u8>), Error>>;
+
+/// Encodes the entries on the rayon thread pool and puts them into the sink, without blocking
+/// the asynchronous runtime.
+///
+/// Without a manifest, the content is streamed to the sink as it is encoded, so that the first
+/// entries are put before the last content is encoded. With a manifest, all entries are encoded
+/// first, as the manifest precedes the content and lists its hashes.
+pub(crate) async fn write_async<S: AsyncOutputSink>(
+    mut sink: S,
+    tileset: Arc<Tileset>,
+    layout: ArchiveLayout,
+    progress_reporter: ProgressReporter,
+) -> Result<(), Error> {
+    let content_encoding = layout
+        .precompressed_entries
+        .then_some(ContentEncoding::Gzip);
+    let (leading_sender, leading_receiver) = oneshot::channel();
+    let (entry_sender, mut entry_receiver): (_, ArchiveEntryReceiver) =
+        mpsc::channel(ENTRY_CHANNEL_CAPACITY);
+    let encode_progress_reporter = progress_reporter.clone();
+    rayon::spawn(move || {
+        stream_archive_entries(
+            &tileset,
+            &layout,
+            content_encoding,
+            &encode_progress_reporter,
+            leading_sender,
+            entry_sender,
+        )
+    });
+    let (entry_count, leading_entries) =
+        leading_receiver.await.map_err(std::io::Error::other)??;
+
+    let archive_progress = progress_reporter.begin_phase(ProgressPhase::Archive, entry_count)?;
+    for (path, buffer) in leading_entries {
+        sink.put(&path, buffer, content_encoding).await?;
+        archive_progress.advance(1)?;
+    }
+    while let Some(entry) = entry_receiver.recv().await {
+        let (path, buffer) = entry?;
+        sink.put(&path, buffer, content_encoding).await?;
+        archive_progress.advance(1)?;
+    }
+    sink.finalize().await?;
+    archive_progress.finish()?;
+
+    Ok(())
+}
+
+/// Sends the leading entries and then the content in the reproducible order of
+/// [`derive_archive_entries`], reporting the encode phase. Encoding stops once the writing
+/// future was dropped, which drops the receivers.
+fn stream_archive_entries(
+    tileset: &Tileset,
+    layout: &ArchiveLayout,
+    content_encoding: Option<ContentEncoding>,
+    progress_reporter: &ProgressReporter,
+    leading_sender: oneshot::Sender<Result<LeadingArchiveEntries, Error>>,
+    entry_sender: mpsc::Sender<Result<(PathBuf, Vec<u8>), Error>>,
+) {
+    if layout.manifest {
+        let archive_entries = derive_archive_entries(tileset, layout, progress_reporter)
+            .and_then(|e| precompress_archive_entries(e, content_encoding))
+            .map(|e| (e.len() as u64, e));
+        let _ = leading_sender.send(archive_entries);
+        return;
+    }
+
+    let metadata = derive_metadata(tileset, layout);
+    let center_positions = derive_center_positions(tileset);
+    let mut cells: Vec<(&OctantIndex, &Vec<Vertex>)> =
+        tileset.tiled_content.cells().iter().collect();
+    cells.sort_by_key(|(i, _)| octant_order(**i));
+    let leading_entries = derive_leading_archive_entries(tileset, layout, &metadata)
+        .and_then(|mut e| {
+            e.sort_by_cached_key(|(path, _)| derive_archive_entry_order(path, layout));
+            precompress_archive_entries(e, content_encoding)
+        })
+        .map(|e| {
+            let content_entry_count: usize = cells
+                .par_iter()
+                .map(|(_, c)| count_cell_contents(c, layout))
+                .sum();
+            ((e.len() + content_entry_count) as u64, e)
+        });
+    let is_failed = leading_entries.is_err();
+    if leading_sender.send(leading_entries).is_err() || is_failed {
+        return;
+    }
+
+    let encode_cells = || -> Result<(), Error> {
+        let encode_progress =
+            progress_reporter.begin_phase(ProgressPhase::Encode, cells.len() as u64)?;
+        for chunk in cells.chunks(rayon::current_num_threads() * 4) {
+            let content_entries = chunk
+                .par_iter()
+                .map(|(i, c)| {
+                    encode_cell_contents(
+                        i,
+                        c,
+                        &layout.content_layers,
+                        &layout.asset_info,
+                        center_positions,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+                .into_iter()
+                .flatten()
+                .map(|(path, buffer)| (layout.content_directory_path.join(path), buffer))
+                .collect();
+            for entry in precompress_archive_entries(content_entries, content_encoding)? {
+                if entry_sender.blocking_send(Ok(entry)).is_err() {
+                    return Ok(());
+                }
+            }
+            encode_progress.advance(chunk.len() as u64)?;
+        }
+        encode_progress.finish()?;
+        Ok(())
+    };
+    if let Err(error) = encode_cells() {
+        let _ = entry_sender.blocking_send(Err(error));
+    }
+}
+
+/// Number of files the content of an octant is encoded into, see [`encode_cell_contents`].
+fn count_cell_contents(cell_content: &[Vertex], layout: &ArchiveLayout) -> usize {
+    if layout.content_layers.is_empty() {
+        return 1;
+    }
+
+    let mut is_layer_occupied = vec![false; layout.content_layers.len()];
+    for vertex in cell_content {
+        if let Some(layer_index) = derive_content_layer_index(&layout.content_layers, vertex) {
+            is_layer_occupied[layer_index] = true;
+        }
+    }
+    is_layer_occupied.into_iter().filter(|o| *o).count()
+}
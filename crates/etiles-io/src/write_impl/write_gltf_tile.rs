@@ -1,13 +1,33 @@
 use crate::Error;
-use crate::write_impl::content::EncodablePosition;
+use crate::Error::{EmptyContent, SizeLimitExceeded};
+use crate::asset_info::AssetInfo;
+use crate::write_impl::content::{
+    EncodableClassification, EncodableNormal, EncodablePosition, EncodableTime,
+};
+use chrono::{DateTime, Utc};
 use gltf::json;
 use gltf_json::validation::Checked::Valid;
 use gltf_json::validation::USize64;
-use nalgebra::{Isometry3, Translation, UnitQuaternion, Vector3};
+use nalgebra::{Isometry3, Point3, Translation, UnitQuaternion, Vector3};
 use std::borrow::Cow;
 use std::io::Write;
 use std::mem;
 
+/// Name of the custom vertex attribute holding the classification codes, without the leading
+/// underscore required for application-specific attributes.
+pub(crate) const GLTF_ATTRIBUTE_CLASSIFICATION: &str = "CLASSIFICATION";
+
+/// Name of the custom vertex attribute holding the point times in seconds since the time
+/// reference of the tile, which is stored in the extras of its accessor.
+pub(crate) const GLTF_ATTRIBUTE_TIME: &str = "TIME";
+pub(crate) const GLTF_EXTRAS_KEY_TIME_REFERENCE: &str = "timeReference";
+
+/// Extension exposing the custom vertex attributes as per-point properties, e.g. for styling.
+pub(crate) const GLTF_EXTENSION_STRUCTURAL_METADATA: &str = "EXT_structural_metadata";
+pub(crate) const GLTF_METADATA_CLASS_POINT: &str = "point";
+pub(crate) const GLTF_METADATA_PROPERTY_CLASSIFICATION: &str = "classification";
+pub(crate) const GLTF_METADATA_PROPERTY_TIME: &str = "time";
+
 fn align_to_multiple_of_four(n: &mut usize) {
     *n = (*n + 3) & !3;
 }
@@ -23,21 +43,76 @@ fn to_padded_byte_vector<T: bytemuck::NoUninit>(data: &[T]) -> Vec<u8> {
     new_vec
 }
 
+fn bounding_coords(vertices: &[EncodablePosition]) -> Result<([f32; 3], [f32; 3]), Error> {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    if vertices.is_empty() {
+        return Err(EmptyContent());
+    }
+
+    for current_vertex in vertices {
+        for i in 0..3 {
+            min[i] = f32::min(min[i], current_vertex.position[i]);
+            max[i] = f32::max(max[i], current_vertex.position[i]);
+        }
+    }
+
+    Ok((min, max))
+}
+
+/// Rotates the z-up tile frame into the y-up frame of glTF.
+pub(crate) fn gltf_axis_adjustment_isometry() -> Isometry3<f64> {
+    Isometry3::from_parts(
+        Translation::identity(),
+        UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -std::f64::consts::FRAC_PI_2),
+    )
+}
+
+/// Derives the translation of the glTF node to the center of the bounding box of the vertices,
+/// rounded to single precision, so that the node represents it exactly.
+fn derive_node_translation(
+    vertex_list: &[etiles_core::Vertex],
+    gltf_axis_adjustment_isometry: &Isometry3<f64>,
+) -> Vector3<f32> {
+    let (min, max) = vertex_list.iter().fold(
+        (Vector3::repeat(f64::MAX), Vector3::repeat(f64::MIN)),
+        |(min, max), v| (min.inf(&v.position.coords), max.sup(&v.position.coords)),
+    );
+    let center = Point3::from((min + max) / 2.0);
+
+    (gltf_axis_adjustment_isometry * center)
+        .coords
+        .cast::<f32>()
+}
+
 /// Point cloud is in Epsg4979
+///
+/// Point times are only written if every vertex has one. They are stored relative to the start
+/// of the second of the earliest time of the tile, rather than to the time reference of the
+/// tileset, so that their single precision only depends on the time span of the tile. If
+/// `center_positions` is set, the positions are stored relative to the center of the tile, which
+/// becomes the translation of the glTF node, so that coordinates far from the origin, e.g. in
+/// ECEF, keep their precision.
 pub fn write_gltf_tile<W: Write>(
     writer: &mut W,
-    vertex_list: &Vec<etiles_core::Vertex>,
+    vertex_list: &[etiles_core::Vertex],
+    asset_info: &AssetInfo,
+    center_positions: bool,
 ) -> Result<(), Error> {
-    let gltf_axis_adjustment_isometry = Isometry3::from_parts(
-        Translation::identity(),
-        UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -std::f64::consts::FRAC_PI_2),
-    );
+    if vertex_list.is_empty() {
+        return Err(EmptyContent());
+    }
+
+    let gltf_axis_adjustment_isometry = gltf_axis_adjustment_isometry();
+    let node_translation: Option<Vector3<f32>> = center_positions
+        .then(|| derive_node_translation(vertex_list, &gltf_axis_adjustment_isometry));
+    let position_offset: Vector3<f64> =
+        node_translation.map_or(Vector3::zeros(), |t| t.cast::<f64>());
 
-    // info!("Writing tiangle_vertices");
     let encodable_vertices: Vec<EncodablePosition> = vertex_list
         .iter()
         .map(|v| {
-            let transformed_point = gltf_axis_adjustment_isometry * v.position;
+            let transformed_point = gltf_axis_adjustment_isometry * v.position - position_offset;
 
             EncodablePosition {
                 position: [
@@ -50,45 +125,74 @@ pub fn write_gltf_tile<W: Write>(
         })
         .collect();
 
-    let min = [
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[0])
-            .reduce(f32::min)
-            .unwrap(),
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[1])
-            .reduce(f32::min)
-            .unwrap(),
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[2])
-            .reduce(f32::min)
-            .unwrap(),
-    ];
-    let max = [
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[0])
-            .reduce(f32::max)
-            .unwrap(),
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[1])
-            .reduce(f32::max)
-            .unwrap(),
-        encodable_vertices
-            .iter()
-            .map(|i| i.position[2])
-            .reduce(f32::max)
-            .unwrap(),
-    ];
-    // let (min, max) = bounding_coords(&triangle_vertices);
-
-    //info!("Writing buffer");
-    let mut root = gltf_json::Root::default();
-    let buffer_length = encodable_vertices.len() * mem::size_of::<EncodablePosition>();
+    // normals are only written if every vertex of the tile has one
+    let encodable_normals: Option<Vec<EncodableNormal>> = vertex_list
+        .iter()
+        .map(|v| {
+            v.normal.map(|n| {
+                let transformed_normal = gltf_axis_adjustment_isometry
+                    .rotation
+                    .cast::<f32>()
+                    .transform_vector(&n);
+                EncodableNormal {
+                    normal: [
+                        transformed_normal.x,
+                        transformed_normal.y,
+                        transformed_normal.z,
+                    ],
+                }
+            })
+        })
+        .collect();
+
+    // classifications are only written if every vertex of the tile has one
+    let encodable_classifications: Option<Vec<EncodableClassification>> = vertex_list
+        .iter()
+        .map(|v| {
+            v.classification.map(|c| EncodableClassification {
+                classification: c,
+                padding: [0; 3],
+            })
+        })
+        .collect();
+
+    // times are only written if every vertex of the tile has one
+    let times: Option<Vec<f64>> = vertex_list.iter().map(|v| v.time).collect();
+    let tile_time_reference: Option<DateTime<Utc>> = times.as_ref().and_then(|t| {
+        let earliest_time = t.iter().copied().min_by(f64::total_cmp)?;
+        DateTime::from_timestamp(earliest_time.floor() as i64, 0)
+    });
+    let encodable_times: Option<Vec<EncodableTime>> =
+        times.zip(tile_time_reference).map(|(times, reference)| {
+            let reference_seconds = reference.timestamp() as f64;
+            times
+                .into_iter()
+                .map(|t| EncodableTime {
+                    time: (t - reference_seconds) as f32,
+                })
+                .collect()
+        });
+
+    let (min, max) = bounding_coords(&encodable_vertices)?;
+
+    let mut root = gltf_json::Root {
+        asset: asset_info.derive_gltf_asset()?,
+        ..Default::default()
+    };
+    let vertex_buffer_length = encodable_vertices.len() * mem::size_of::<EncodablePosition>();
+    let normal_buffer_length = encodable_normals
+        .as_ref()
+        .map_or(0, |n| n.len() * mem::size_of::<EncodableNormal>());
+    let classification_buffer_length = encodable_classifications
+        .as_ref()
+        .map_or(0, |c| c.len() * mem::size_of::<EncodableClassification>());
+    let time_buffer_length = encodable_times
+        .as_ref()
+        .map_or(0, |t| t.len() * mem::size_of::<EncodableTime>());
+    let buffer_length = vertex_buffer_length
+        + normal_buffer_length
+        + classification_buffer_length
+        + time_buffer_length;
     let buffer = root.push(json::Buffer {
         byte_length: USize64::from(buffer_length),
         extensions: Default::default(),
@@ -98,7 +202,7 @@ pub fn write_gltf_tile<W: Write>(
     });
     let buffer_view = root.push(json::buffer::View {
         buffer,
-        byte_length: USize64::from(buffer_length),
+        byte_length: USize64::from(vertex_buffer_length),
         byte_offset: None,
         byte_stride: Some(json::buffer::Stride(mem::size_of::<EncodablePosition>())),
         extensions: Default::default(),
@@ -139,14 +243,184 @@ pub fn write_gltf_tile<W: Write>(
         sparse: None,
     });
 
+    let normals = encodable_normals.as_ref().map(|encodable_normals| {
+        let normal_buffer_view = root.push(json::buffer::View {
+            buffer,
+            byte_length: USize64::from(normal_buffer_length),
+            byte_offset: Some(USize64::from(vertex_buffer_length)),
+            byte_stride: Some(json::buffer::Stride(mem::size_of::<EncodableNormal>())),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            target: Some(Valid(json::buffer::Target::ArrayBuffer)),
+        });
+        root.push(json::Accessor {
+            buffer_view: Some(normal_buffer_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(encodable_normals.len()),
+            component_type: Valid(json::accessor::GenericComponentType(
+                json::accessor::ComponentType::F32,
+            )),
+            extensions: Default::default(),
+            extras: Default::default(),
+            type_: Valid(json::accessor::Type::Vec3),
+            min: None,
+            max: None,
+            name: None,
+            normalized: false,
+            sparse: None,
+        })
+    });
+
+    let classifications = encodable_classifications
+        .as_ref()
+        .map(|encodable_classifications| {
+            let classification_buffer_view = root.push(json::buffer::View {
+                buffer,
+                byte_length: USize64::from(classification_buffer_length),
+                byte_offset: Some(USize64::from(vertex_buffer_length + normal_buffer_length)),
+                byte_stride: Some(json::buffer::Stride(
+                    mem::size_of::<EncodableClassification>(),
+                )),
+                extensions: Default::default(),
+                extras: Default::default(),
+                name: None,
+                target: Some(Valid(json::buffer::Target::ArrayBuffer)),
+            });
+            root.push(json::Accessor {
+                buffer_view: Some(classification_buffer_view),
+                byte_offset: Some(USize64(0)),
+                count: USize64::from(encodable_classifications.len()),
+                component_type: Valid(json::accessor::GenericComponentType(
+                    json::accessor::ComponentType::U8,
+                )),
+                extensions: Default::default(),
+                extras: Default::default(),
+                type_: Valid(json::accessor::Type::Scalar),
+                min: None,
+                max: None,
+                name: None,
+                normalized: false,
+                sparse: None,
+            })
+        });
+
+    let times = encodable_times.as_ref().map(|encodable_times| {
+        let time_buffer_view = root.push(json::buffer::View {
+            buffer,
+            byte_length: USize64::from(time_buffer_length),
+            byte_offset: Some(USize64::from(
+                vertex_buffer_length + normal_buffer_length + classification_buffer_length,
+            )),
+            byte_stride: Some(json::buffer::Stride(mem::size_of::<EncodableTime>())),
+            extensions: Default::default(),
+            extras: Default::default(),
+            name: None,
+            target: Some(Valid(json::buffer::Target::ArrayBuffer)),
+        });
+        let extras = tile_time_reference.and_then(|r| {
+            serde_json::value::to_raw_value(&serde_json::json!({
+                GLTF_EXTRAS_KEY_TIME_REFERENCE: r.to_rfc3339()
+            }))
+            .ok()
+        });
+        root.push(json::Accessor {
+            buffer_view: Some(time_buffer_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(encodable_times.len()),
+            component_type: Valid(json::accessor::GenericComponentType(
+                json::accessor::ComponentType::F32,
+            )),
+            extensions: Default::default(),
+            extras,
+            type_: Valid(json::accessor::Type::Scalar),
+            min: None,
+            max: None,
+            name: None,
+            normalized: false,
+            sparse: None,
+        })
+    });
+
+    // the custom attributes are declared as property attributes of a single point class
+    let mut point_class_properties = serde_json::Map::new();
+    let mut property_attribute_properties = serde_json::Map::new();
+    if classifications.is_some() {
+        point_class_properties.insert(
+            GLTF_METADATA_PROPERTY_CLASSIFICATION.to_string(),
+            serde_json::json!({"type": "SCALAR", "componentType": "UINT8"}),
+        );
+        property_attribute_properties.insert(
+            GLTF_METADATA_PROPERTY_CLASSIFICATION.to_string(),
+            serde_json::json!({"attribute": format!("_{GLTF_ATTRIBUTE_CLASSIFICATION}")}),
+        );
+    }
+    if times.is_some() {
+        point_class_properties.insert(
+            GLTF_METADATA_PROPERTY_TIME.to_string(),
+            serde_json::json!({"type": "SCALAR", "componentType": "FLOAT32"}),
+        );
+        property_attribute_properties.insert(
+            GLTF_METADATA_PROPERTY_TIME.to_string(),
+            serde_json::json!({"attribute": format!("_{GLTF_ATTRIBUTE_TIME}")}),
+        );
+    }
+    let primitive_extensions = if point_class_properties.is_empty() {
+        None
+    } else {
+        let mut root_extensions = json::extensions::Root::default();
+        root_extensions.others.insert(
+            GLTF_EXTENSION_STRUCTURAL_METADATA.to_string(),
+            serde_json::json!({
+                "schema": {
+                    "id": "etiles",
+                    "classes": {GLTF_METADATA_CLASS_POINT: {"properties": point_class_properties}}
+                },
+                "propertyAttributes": [{
+                    "class": GLTF_METADATA_CLASS_POINT,
+                    "properties": property_attribute_properties
+                }]
+            }),
+        );
+        root.extensions = Some(root_extensions);
+        root.extensions_used
+            .push(GLTF_EXTENSION_STRUCTURAL_METADATA.to_string());
+
+        let mut primitive_extensions = json::extensions::mesh::Primitive::default();
+        primitive_extensions.others.insert(
+            GLTF_EXTENSION_STRUCTURAL_METADATA.to_string(),
+            serde_json::json!({"propertyAttributes": [0]}),
+        );
+        Some(primitive_extensions)
+    };
+
     let primitive = json::mesh::Primitive {
         attributes: {
             let mut map = std::collections::BTreeMap::new();
             map.insert(Valid(json::mesh::Semantic::Positions), positions);
             map.insert(Valid(json::mesh::Semantic::Colors(0)), colors);
+            if let Some(normals) = normals {
+                map.insert(Valid(json::mesh::Semantic::Normals), normals);
+            }
+            if let Some(classifications) = classifications {
+                map.insert(
+                    Valid(json::mesh::Semantic::Extras(
+                        GLTF_ATTRIBUTE_CLASSIFICATION.to_string(),
+                    )),
+                    classifications,
+                );
+            }
+            if let Some(times) = times {
+                map.insert(
+                    Valid(json::mesh::Semantic::Extras(
+                        GLTF_ATTRIBUTE_TIME.to_string(),
+                    )),
+                    times,
+                );
+            }
             map
         },
-        extensions: Default::default(),
+        extensions: primitive_extensions,
         extras: Default::default(),
         indices: None,
         material: None,
@@ -164,6 +438,7 @@ pub fn write_gltf_tile<W: Write>(
 
     let node = root.push(json::Node {
         mesh: Some(mesh),
+        translation: node_translation.map(|t| [t.x, t.y, t.z]),
         ..Default::default()
     });
 
@@ -174,27 +449,36 @@ pub fn write_gltf_tile<W: Write>(
         nodes: vec![node],
     });
 
-    // info!("Writing padded_byte_vector");
-    let padded_byte_vector = to_padded_byte_vector(&encodable_vertices);
+    let mut padded_byte_vector = to_padded_byte_vector(&encodable_vertices);
+    if let Some(encodable_normals) = &encodable_normals {
+        padded_byte_vector.extend(to_padded_byte_vector(encodable_normals));
+    }
+    if let Some(encodable_classifications) = &encodable_classifications {
+        padded_byte_vector.extend(to_padded_byte_vector(encodable_classifications));
+    }
+    if let Some(encodable_times) = &encodable_times {
+        padded_byte_vector.extend(to_padded_byte_vector(encodable_times));
+    }
 
-    //info!("Writing padded_byte_vector");
-    let json_string = json::serialize::to_string(&root).expect("Serialization error");
+    let json_string = json::serialize::to_string(&root)?;
     let mut json_offset = json_string.len();
     align_to_multiple_of_four(&mut json_offset);
+    let glb_length = json_offset + buffer_length;
     let glb = gltf::binary::Glb {
         header: gltf::binary::Header {
             magic: *b"glTF",
             version: 2,
-            length: (json_offset + buffer_length)
-                .try_into()
-                .expect("file size exceeds binary glTF limit"),
+            length: glb_length.try_into().map_err(|_| SizeLimitExceeded {
+                size: glb_length,
+                limit: u32::MAX as usize,
+            })?,
         },
         bin: Some(Cow::Owned(padded_byte_vector)),
         json: Cow::Owned(json_string.into_bytes()),
     };
     // let writer = std::fs::File::create("triangle.glb").expect("I/O error");
     let buf_writer = std::io::BufWriter::new(writer);
-    glb.to_writer(buf_writer).expect("glTF binary output error");
+    glb.to_writer(buf_writer)?;
 
     Ok(())
 }
@@ -1,13 +1,21 @@
 use crate::Error;
+use crate::Error::{MissingOctantStatistics, UnoccupiedSubtreeRoot};
+use crate::content_layer::{ContentLayer, derive_content_layer_index};
+use crate::documents::metadata::{PropertyTable, PropertyTableProperty};
+use crate::metadata::{
+    METADATA_CLASS_TILE, METADATA_PROPERTY_MAXIMUM_HEIGHT, METADATA_PROPERTY_MINIMUM_HEIGHT,
+    METADATA_PROPERTY_POINT_COUNT,
+};
 use crate::write_impl::STRING_PADDING_CHARACTER;
 use bincode::{Decode, Encode, config};
 use bitvec::order::Lsb0;
 use bitvec::vec::BitVec;
 use ecoord::octree::VecOctantIndexExt;
 use ecoord::octree::{OctantIndex, Octree};
-use etiles_core::Vertex;
+use etiles_core::{OctantStatistics, Vertex};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
 
 #[derive(Encode, Decode, PartialEq, Debug)]
@@ -59,112 +67,200 @@ pub enum Constant {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Subtree {
-    buffers: Vec<Buffer>,
-    buffer_views: Vec<BufferView>,
-    tile_availability: Availability,
-    content_availability: Vec<Availability>,
-    child_subtree_availability: Availability,
+    pub(crate) buffers: Vec<Buffer>,
+    pub(crate) buffer_views: Vec<BufferView>,
+    pub(crate) tile_availability: Availability,
+    pub(crate) content_availability: Vec<Availability>,
+    pub(crate) child_subtree_availability: Availability,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) property_tables: Vec<PropertyTable>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tile_metadata: Option<u32>,
 }
 
 impl Subtree {
-    pub fn encode_as_bytes(&self) -> Vec<u8> {
-        let mut encoded_data = serde_json::to_vec(&self).unwrap();
+    pub fn encode_as_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut encoded_data = serde_json::to_vec(&self)?;
         let current_length = encoded_data.len();
         let padding_length = 8 - (current_length % 8);
         // let padding = " ".as_bytes();
 
         encoded_data.append(&mut vec![STRING_PADDING_CHARACTER; padding_length]);
-        encoded_data
+        Ok(encoded_data)
     }
 }
 
+/// Writes the availability of the subtree and, if statistics are given, the statistics of its
+/// available tiles as tile metadata.
+///
+/// With content layers, the content availability is written for each layer in the given order.
 pub fn write_subtree<W: Write>(
     writer: &mut W,
     base_octant_index: OctantIndex,
     levels_per_subtree: usize,
     content_octree: &Octree<Vertex>,
+    octant_statistics: Option<&HashMap<OctantIndex, OctantStatistics>>,
+    content_layers: &[ContentLayer],
 ) -> Result<(), Error> {
-    let availability_info =
-        get_availability_buffer(base_octant_index, levels_per_subtree, content_octree);
+    let availability_info = get_availability_buffer(
+        base_octant_index,
+        levels_per_subtree,
+        content_octree,
+        content_layers,
+    )?;
+    let mut combined_buffer = availability_info.get_combined_buffer();
+    let mut buffer_views = availability_info.get_buffer_views();
+
+    let mut property_tables = Vec::new();
+    if let Some(octant_statistics) = octant_statistics {
+        let property_table = append_tile_property_table(
+            &mut combined_buffer,
+            &mut buffer_views,
+            base_octant_index,
+            levels_per_subtree,
+            content_octree,
+            octant_statistics,
+        )?;
+        property_tables.push(property_table);
+    }
 
     let subtree_document = Subtree {
         buffers: vec![Buffer {
-            byte_length: availability_info.get_combined_buffer().len() as u32,
+            byte_length: combined_buffer.len() as u32,
             ..Default::default()
         }],
-        buffer_views: availability_info.get_buffer_views(),
+        buffer_views,
         tile_availability: availability_info.get_tile_availability(),
         content_availability: availability_info.get_content_availability(),
         child_subtree_availability: availability_info.get_child_subtree_availability(),
+        tile_metadata: (!property_tables.is_empty()).then_some(0),
+        property_tables,
     };
-    let encoded_subtree_json = subtree_document.encode_as_bytes();
+    let encoded_subtree_json = subtree_document.encode_as_bytes()?;
 
     let subtree_binary_header = SubtreeBinaryHeader {
         magic: ['s', 'u', 'b', 't'],
         version: 1,
         json_byte_length: encoded_subtree_json.len() as u64,
-        binary_byte_length: availability_info.get_combined_buffer().len() as u64,
+        binary_byte_length: combined_buffer.len() as u64,
     };
     let config = config::standard().with_fixed_int_encoding();
     let encoded_subtree_binary_header: Vec<u8> =
-        bincode::encode_to_vec(subtree_binary_header, config).unwrap();
-
-    writer
-        .write_all(&encoded_subtree_binary_header)
-        .expect("should work");
-    writer
-        .write_all(&encoded_subtree_json)
-        .expect("should work");
-    writer
-        .write_all(&availability_info.get_combined_buffer())
-        .expect("should work");
+        bincode::encode_to_vec(subtree_binary_header, config)?;
+
+    writer.write_all(&encoded_subtree_binary_header)?;
+    writer.write_all(&encoded_subtree_json)?;
+    writer.write_all(&combined_buffer)?;
 
     Ok(())
 }
 
+/// Appends the point count and, if the tileset has heights, the height range of each available
+/// tile, ordered as in the tile availability, to the buffer and returns the property table
+/// referencing them.
+fn append_tile_property_table(
+    combined_buffer: &mut Vec<u8>,
+    buffer_views: &mut Vec<BufferView>,
+    base_octant_index: OctantIndex,
+    levels_per_subtree: usize,
+    content_octree: &Octree<Vertex>,
+    octant_statistics: &HashMap<OctantIndex, OctantStatistics>,
+) -> Result<PropertyTable, Error> {
+    let tile_statistics = get_subtree_morton_indices(base_octant_index, levels_per_subtree)?
+        .into_iter()
+        .map(|(octant_index, _)| octant_index)
+        .filter(|i| content_octree.occupancy_graph().is_cell_occupied(*i))
+        .map(|i| octant_statistics.get(&i).ok_or(MissingOctantStatistics(i)))
+        .collect::<Result<Vec<&OctantStatistics>, Error>>()?;
+
+    let point_counts: Vec<u8> = tile_statistics
+        .iter()
+        .flat_map(|s| (s.point_count as u32).to_le_bytes())
+        .collect();
+    // heights are omitted if the tileset has none
+    let minimum_heights: Option<Vec<[u8; 8]>> = tile_statistics
+        .iter()
+        .map(|s| s.minimum_height.map(f64::to_le_bytes))
+        .collect();
+    let maximum_heights: Option<Vec<[u8; 8]>> = tile_statistics
+        .iter()
+        .map(|s| s.maximum_height.map(f64::to_le_bytes))
+        .collect();
+    let mut property_values = vec![(METADATA_PROPERTY_POINT_COUNT, point_counts)];
+    if let (Some(minimum_heights), Some(maximum_heights)) = (minimum_heights, maximum_heights) {
+        property_values.push((METADATA_PROPERTY_MINIMUM_HEIGHT, minimum_heights.concat()));
+        property_values.push((METADATA_PROPERTY_MAXIMUM_HEIGHT, maximum_heights.concat()));
+    }
+
+    let mut properties = BTreeMap::new();
+    for (name, values) in property_values {
+        properties.insert(
+            name.to_string(),
+            PropertyTableProperty {
+                values: buffer_views.len() as u32,
+            },
+        );
+        buffer_views.push(BufferView {
+            buffer: 0,
+            byte_offset: combined_buffer.len() as u32,
+            byte_length: values.len() as u32,
+            name: None,
+        });
+        let padding_length = (8 - values.len() % 8) % 8;
+        combined_buffer.extend(values);
+        combined_buffer.extend(vec![0u8; padding_length]);
+    }
+
+    Ok(PropertyTable {
+        class: METADATA_CLASS_TILE.to_string(),
+        count: tile_statistics.len() as u32,
+        properties,
+    })
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct AvailabilityInfo {
     tile: AvailabilityRecord,
-    content: AvailabilityRecord,
+    contents: Vec<AvailabilityRecord>,
     child_subtree: AvailabilityRecord,
 }
 
 impl AvailabilityInfo {
+    /// Returns the records in the order of their bitstreams, omitting an empty child subtree
+    /// availability.
+    fn records(&self) -> Vec<&AvailabilityRecord> {
+        let mut records: Vec<&AvailabilityRecord> = std::iter::once(&self.tile)
+            .chain(self.contents.iter())
+            .collect();
+        if !self.child_subtree.buffer().is_empty() {
+            records.push(&self.child_subtree);
+        }
+
+        records
+    }
+
     pub fn get_combined_buffer(&self) -> Vec<u8> {
-        [
-            self.tile.get_padded_buffer(),
-            self.content.get_padded_buffer(),
-            self.child_subtree.get_padded_buffer(),
-        ]
-        .concat()
+        self.records()
+            .into_iter()
+            .flat_map(|r| r.get_padded_buffer())
+            .collect()
     }
 
     pub fn get_buffer_views(&self) -> Vec<BufferView> {
-        let mut buffer_views = vec![
-            BufferView {
-                buffer: 0,
-                byte_offset: 0,
-                byte_length: self.tile.buffer().len() as u32,
-                name: None,
-            },
-            BufferView {
-                buffer: 0,
-                byte_offset: self.tile.get_padded_buffer().len() as u32,
-                byte_length: self.content.buffer().len() as u32,
-                name: None,
-            },
-        ];
-        if !self.child_subtree.buffer().is_empty() {
-            buffer_views.push(BufferView {
-                buffer: 0,
-                byte_offset: (self.tile.get_padded_buffer().len()
-                    + self.content.get_padded_buffer().len()) as u32,
-                byte_length: self.child_subtree.buffer().len() as u32,
-                name: None,
+        let mut byte_offset = 0;
+        self.records()
+            .into_iter()
+            .map(|r| {
+                let buffer_view = BufferView {
+                    buffer: 0,
+                    byte_offset: byte_offset as u32,
+                    byte_length: r.buffer().len() as u32,
+                    name: None,
+                };
+                byte_offset += r.get_padded_buffer().len();
+                buffer_view
             })
-        }
-
-        buffer_views
+            .collect()
     }
 
     pub fn get_tile_availability(&self) -> Availability {
@@ -176,11 +272,15 @@ impl AvailabilityInfo {
     }
 
     pub fn get_content_availability(&self) -> Vec<Availability> {
-        vec![Availability {
-            bitstream: Some(1),
-            available_count: Some(self.content.count),
-            constant: None,
-        }]
+        self.contents
+            .iter()
+            .enumerate()
+            .map(|(i, c)| Availability {
+                bitstream: Some(1 + i as u32),
+                available_count: Some(c.count),
+                constant: None,
+            })
+            .collect()
     }
 
     pub fn get_child_subtree_availability(&self) -> Availability {
@@ -192,7 +292,7 @@ impl AvailabilityInfo {
             }
         } else {
             Availability {
-                bitstream: Some(2),
+                bitstream: Some(1 + self.contents.len() as u32),
                 available_count: Some(self.child_subtree.count),
                 constant: None,
             }
@@ -211,10 +311,6 @@ impl AvailabilityRecord {
         self.bit_buffer.clone().into_vec()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.count == 0
-    }
-
     pub fn get_padded_buffer(&self) -> Vec<u8> {
         if self.buffer().len().is_multiple_of(8) {
             return self.buffer();
@@ -229,36 +325,47 @@ fn get_availability_buffer(
     base_octant_index: OctantIndex,
     levels_per_subtree: usize,
     content_octree: &Octree<Vertex>,
-) -> AvailabilityInfo {
-    let tile = get_tile_availability_buffer(base_octant_index, levels_per_subtree, content_octree);
-    let content =
-        get_content_availability_buffer(base_octant_index, levels_per_subtree, content_octree);
+    content_layers: &[ContentLayer],
+) -> Result<AvailabilityInfo, Error> {
+    let tile = get_tile_availability_buffer(base_octant_index, levels_per_subtree, content_octree)?;
+    let contents = if content_layers.is_empty() {
+        vec![get_content_availability_buffer(
+            base_octant_index,
+            levels_per_subtree,
+            |i| content_octree.contains_content_cells(i),
+        )?]
+    } else {
+        (0..content_layers.len())
+            .map(|layer_index| {
+                get_content_availability_buffer(base_octant_index, levels_per_subtree, |i| {
+                    content_octree.cell(i).is_some_and(|c| {
+                        c.iter().any(|v| {
+                            derive_content_layer_index(content_layers, v) == Some(layer_index)
+                        })
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+    };
     let child_subtree = get_child_subtree_availability_buffer(
         base_octant_index,
         levels_per_subtree,
         content_octree,
-    );
+    )?;
 
-    AvailabilityInfo {
+    Ok(AvailabilityInfo {
         tile,
-        content,
+        contents,
         child_subtree,
-    }
+    })
 }
 
 fn get_tile_availability_buffer(
     base_octant_index: OctantIndex,
     levels_per_subtree: usize,
     content_octree: &Octree<Vertex>,
-) -> AvailabilityRecord {
-    let morton_indices: Vec<(OctantIndex, u64)> = (0..levels_per_subtree)
-        .flat_map(|l| {
-            base_octant_index
-                .get_descendents(l as u32)
-                .sort_by_morton_indices()
-                .expect("should work")
-        })
-        .collect();
+) -> Result<AvailabilityRecord, Error> {
+    let morton_indices = get_subtree_morton_indices(base_octant_index, levels_per_subtree)?;
 
     let mut available_cell_count: u32 = 0;
     let mut bit_buffer: BitVec<u8, Lsb0> = BitVec::new();
@@ -276,30 +383,23 @@ fn get_tile_availability_buffer(
         (8_u32.pow(levels_per_subtree as u32) - 1) / 7,
         "Wrong tile length"
     );
-    AvailabilityRecord {
+    Ok(AvailabilityRecord {
         bit_buffer,
         count: available_cell_count,
-    }
+    })
 }
 
 fn get_content_availability_buffer(
     base_octant_index: OctantIndex,
     levels_per_subtree: usize,
-    content_octree: &Octree<Vertex>,
-) -> AvailabilityRecord {
-    let morton_indices: Vec<(OctantIndex, u64)> = (0..levels_per_subtree)
-        .flat_map(|l| {
-            base_octant_index
-                .get_descendents(l as u32)
-                .sort_by_morton_indices()
-                .expect("should work")
-        })
-        .collect();
+    has_content: impl Fn(OctantIndex) -> bool,
+) -> Result<AvailabilityRecord, Error> {
+    let morton_indices = get_subtree_morton_indices(base_octant_index, levels_per_subtree)?;
 
     let mut content_availability_cell_count: u32 = 0;
     let mut bit_buffer: BitVec<u8, Lsb0> = BitVec::new();
     for current_morton_index in morton_indices {
-        let current_availability = content_octree.contains_content_cells(current_morton_index.0);
+        let current_availability = has_content(current_morton_index.0);
         content_availability_cell_count += current_availability as u32;
         bit_buffer.push(current_availability);
     }
@@ -310,27 +410,27 @@ fn get_content_availability_buffer(
         (8_u32.pow(levels_per_subtree as u32) - 1) / 7,
         "Wrong content length"
     );
-    AvailabilityRecord {
+    Ok(AvailabilityRecord {
         bit_buffer,
         count: content_availability_cell_count,
-    }
+    })
 }
 
 fn get_child_subtree_availability_buffer(
     base_octant_index: OctantIndex,
     levels_per_subtree: usize,
     content_octree: &Octree<Vertex>,
-) -> AvailabilityRecord {
+) -> Result<AvailabilityRecord, Error> {
     if !content_octree
         .occupancy_graph()
         .is_cell_occupied(base_octant_index)
     {
-        panic!("must be occupied in the content cells");
+        return Err(UnoccupiedSubtreeRoot(base_octant_index));
     }
     let morton_indices: Vec<(OctantIndex, u64)> = base_octant_index
         .get_descendents(levels_per_subtree as u32)
         .sort_by_morton_indices()
-        .expect("should work");
+        .map_err(ecoord::Error::from)?;
 
     let mut available_cell_count: u32 = 0;
     let mut bit_buffer: BitVec<u8, Lsb0> = BitVec::new();
@@ -348,8 +448,115 @@ fn get_child_subtree_availability_buffer(
         8_u32.pow(levels_per_subtree as u32),
         "Wrong tile length"
     );
-    AvailabilityRecord {
+    Ok(AvailabilityRecord {
         bit_buffer,
         count: available_cell_count,
+    })
+}
+
+fn get_subtree_morton_indices(
+    base_octant_index: OctantIndex,
+    levels_per_subtree: usize,
+) -> Result<Vec<(OctantIndex, u64)>, Error> {
+    let mut morton_indices: Vec<(OctantIndex, u64)> = Vec::new();
+    for current_level in 0..levels_per_subtree {
+        let current_morton_indices = base_octant_index
+            .get_descendents(current_level as u32)
+            .sort_by_morton_indices()
+            .map_err(ecoord::Error::from)?;
+        morton_indices.extend(current_morton_indices);
+    }
+
+    Ok(morton_indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{derive_east_north_up_rotation, derive_tileset, derive_vertices};
+    use etiles_core::Tileset;
+    use nalgebra::UnitQuaternion;
+
+    fn derive_property_table(
+        tileset: &Tileset,
+        base_octant_index: OctantIndex,
+        levels_per_subtree: usize,
+    ) -> (PropertyTable, Vec<BufferView>) {
+        let mut combined_buffer = Vec::new();
+        let mut buffer_views = Vec::new();
+        let property_table = append_tile_property_table(
+            &mut combined_buffer,
+            &mut buffer_views,
+            base_octant_index,
+            levels_per_subtree,
+            &tileset.tiled_content,
+            &tileset.derive_octant_statistics(),
+        )
+        .expect("property table");
+
+        (property_table, buffer_views)
+    }
+
+    #[test]
+    fn test_property_table_count_equals_tile_availability_count() {
+        let tileset = derive_tileset(derive_vertices(), derive_east_north_up_rotation());
+        let levels_per_subtree = 2;
+        let subtree_root_indices: Vec<OctantIndex> = std::iter::once(OctantIndex::origin())
+            .chain(
+                tileset
+                    .tiled_content
+                    .cells()
+                    .keys()
+                    .filter(|i| i.level == levels_per_subtree as u32)
+                    .copied(),
+            )
+            .collect();
+        assert!(subtree_root_indices.len() > 1);
+
+        for base_octant_index in subtree_root_indices {
+            let availability_info = get_availability_buffer(
+                base_octant_index,
+                levels_per_subtree,
+                &tileset.tiled_content,
+                &[],
+            )
+            .expect("availability");
+            let (property_table, buffer_views) =
+                derive_property_table(&tileset, base_octant_index, levels_per_subtree);
+
+            assert_eq!(
+                Some(property_table.count),
+                availability_info.get_tile_availability().available_count
+            );
+            let point_count_view = &buffer_views
+                [property_table.properties[METADATA_PROPERTY_POINT_COUNT].values as usize];
+            assert_eq!(point_count_view.byte_length, property_table.count * 4);
+        }
+    }
+
+    #[test]
+    fn test_heights_are_omitted_without_up_axis() {
+        let tileset = derive_tileset(derive_vertices(), derive_east_north_up_rotation());
+        let (property_table, _) = derive_property_table(&tileset, OctantIndex::origin(), 2);
+        assert!(
+            property_table
+                .properties
+                .contains_key(METADATA_PROPERTY_MINIMUM_HEIGHT)
+        );
+        assert!(
+            property_table
+                .properties
+                .contains_key(METADATA_PROPERTY_MAXIMUM_HEIGHT)
+        );
+
+        // the z-axis of ECEF points north on the equator
+        let tileset = derive_tileset(derive_vertices(), UnitQuaternion::identity());
+        let (property_table, buffer_views) =
+            derive_property_table(&tileset, OctantIndex::origin(), 2);
+        assert_eq!(
+            property_table.properties.keys().collect::<Vec<_>>(),
+            vec![METADATA_PROPERTY_POINT_COUNT]
+        );
+        assert_eq!(buffer_views.len(), 1);
     }
 }
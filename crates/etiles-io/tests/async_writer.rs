@@ -0,0 +1,144 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::derive_tileset;
+use etiles_io::{
+    ArchiveCompression, AsyncEtilesWriter, AsyncOutputSink, ContentEncoding, ContentLayer,
+    EtilesWriter,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+struct PutEntry {
+    path: PathBuf,
+    content_encoding: Option<ContentEncoding>,
+}
+
+struct CollectingSink {
+    entries: Vec<PutEntry>,
+    finalized: Arc<Mutex<Vec<PutEntry>>>,
+}
+
+impl AsyncOutputSink for CollectingSink {
+    async fn put(
+        &mut self,
+        path: &Path,
+        _content: Vec<u8>,
+        content_encoding: Option<ContentEncoding>,
+    ) -> Result<(), etiles_io::Error> {
+        tokio::task::yield_now().await;
+        self.entries.push(PutEntry {
+            path: path.to_path_buf(),
+            content_encoding,
+        });
+        Ok(())
+    }
+
+    async fn finalize(self) -> Result<(), etiles_io::Error> {
+        *self.finalized.lock().expect("lock") = self.entries;
+        Ok(())
+    }
+}
+
+fn create_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime")
+}
+
+#[test]
+fn test_async_writer_matches_writer() {
+    let tileset = Arc::new(derive_tileset());
+    let runtime = create_runtime();
+
+    for archive_compression in [
+        ArchiveCompression::None,
+        ArchiveCompression::Gzip,
+        ArchiveCompression::Zstd,
+    ] {
+        let mut buffer: Vec<u8> = Vec::new();
+        EtilesWriter::new(&mut buffer)
+            .with_archive_compression(archive_compression)
+            .with_precompressed_entries(true)
+            .with_manifest(true)
+            .finish(&tileset)
+            .expect("archive");
+
+        let mut async_buffer: Vec<u8> = Vec::new();
+        runtime
+            .block_on(
+                AsyncEtilesWriter::new(&mut async_buffer)
+                    .with_archive_compression(archive_compression)
+                    .with_precompressed_entries(true)
+                    .with_manifest(true)
+                    .finish(tileset.clone()),
+            )
+            .expect("async archive");
+
+        assert_eq!(buffer, async_buffer, "{archive_compression:?}");
+    }
+}
+
+#[test]
+fn test_async_writer_streaming_matches_writer() {
+    let tileset = Arc::new(derive_tileset());
+    let runtime = create_runtime();
+
+    for content_layers in [
+        Vec::new(),
+        vec![
+            ContentLayer::new("ground", vec![2]),
+            ContentLayer::new("other", Vec::new()),
+        ],
+    ] {
+        let mut buffer: Vec<u8> = Vec::new();
+        EtilesWriter::new(&mut buffer)
+            .with_content_layers(content_layers.clone())
+            .with_precompressed_entries(true)
+            .finish(&tileset)
+            .expect("archive");
+
+        let mut async_buffer: Vec<u8> = Vec::new();
+        runtime
+            .block_on(
+                AsyncEtilesWriter::new(&mut async_buffer)
+                    .with_content_layers(content_layers.clone())
+                    .with_precompressed_entries(true)
+                    .finish(tileset.clone()),
+            )
+            .expect("async archive");
+
+        assert_eq!(buffer, async_buffer, "{content_layers:?}");
+    }
+}
+
+#[test]
+fn test_async_writer_puts_entries_into_sink() {
+    let finalized = Arc::new(Mutex::new(Vec::new()));
+    let sink = CollectingSink {
+        entries: Vec::new(),
+        finalized: finalized.clone(),
+    };
+
+    create_runtime()
+        .block_on(
+            AsyncEtilesWriter::from_sink(sink)
+                .with_precompressed_entries(true)
+                .finish(derive_tileset()),
+        )
+        .expect("entries");
+
+    let entries = finalized.lock().expect("lock");
+    assert_eq!(entries[0].path, Path::new("tileset.json"));
+    assert!(
+        entries
+            .iter()
+            .all(|e| e.content_encoding == Some(ContentEncoding::Gzip))
+    );
+    assert!(
+        entries
+            .iter()
+            .any(|e| e.path == Path::new("content/pc_0__0_0_0.glb"))
+    );
+}
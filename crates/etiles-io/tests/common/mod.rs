@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+
+use ecoord::octree::{Octree, StorageMode};
+use etiles_core::{Tileset, Vertex};
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion};
+use palette::Srgb;
+use std::path::PathBuf;
+
+pub const SEED_NUMBER: u64 = 42;
+pub const VERTEX_COUNT: usize = 5_000;
+
+/// Attributes of the fixture vertices in addition to position and color.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VertexAttributes {
+    /// Cycles through the classifications 2, 3 and 6 instead of only ground (2).
+    pub mixed_classifications: bool,
+    /// Adds GPS times at intervals of 10 ms.
+    pub times: bool,
+}
+
+/// Points on a regular grid with classification, located in the local frame of a root
+/// transform on the earth's surface.
+pub fn derive_vertices() -> Vec<Vertex> {
+    derive_vertices_with(VERTEX_COUNT, VertexAttributes::default())
+}
+
+/// Points on the regular grid of [`derive_vertices`] with the number of points and attributes.
+pub fn derive_vertices_with(count: usize, attributes: VertexAttributes) -> Vec<Vertex> {
+    (0..count)
+        .map(|i| {
+            let (x, y, z) = ((i % 50) as f64, ((i / 50) % 20) as f64, (i / 1000) as f64);
+            let classification = if attributes.mixed_classifications {
+                [2, 3, 6][i % 3]
+            } else {
+                2
+            };
+            Vertex {
+                position: Point3::new(x * 2.0, y * 4.0, z),
+                color: Srgb::new(0.2, 0.4, 0.6),
+                normal: None,
+                classification: Some(classification),
+                time: attributes.times.then(|| 1.7e9 + i as f64 * 0.01),
+            }
+        })
+        .collect()
+}
+
+pub fn derive_tileset() -> Tileset {
+    derive_tileset_from(derive_vertices())
+}
+
+/// Builds the tileset of the vertices with the octree parameters and root transform of the
+/// fixture.
+pub fn derive_tileset_from(vertices: Vec<Vertex>) -> Tileset {
+    let tiled_content =
+        Octree::new(vertices, 500, StorageMode::AllOctants, Some(SEED_NUMBER)).expect("octree");
+
+    Tileset {
+        tiled_content,
+        root_transform: Isometry3::from_parts(
+            Translation3::new(4_160_000.0, 850_000.0, 4_750_000.0),
+            UnitQuaternion::identity(),
+        ),
+        root_geometric_error: 100.0,
+        geometric_error: 4.0,
+    }
+}
+
+/// Returns a path in the temporary directory that is unique to the test process.
+pub fn derive_temporary_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("etiles-{}-{name}", std::process::id()))
+}
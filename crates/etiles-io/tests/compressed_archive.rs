@@ -0,0 +1,78 @@
+mod common;
+
+use common::{VERTEX_COUNT, derive_tileset};
+use etiles_io::{ArchiveCompression, EtilesReader, EtilesVerifier, EtilesWriter};
+use std::io::Cursor;
+
+fn write_archive(archive_compression: ArchiveCompression, precompressed_entries: bool) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::new();
+    EtilesWriter::new(&mut buffer)
+        .with_archive_compression(archive_compression)
+        .with_precompressed_entries(precompressed_entries)
+        .with_manifest(true)
+        .finish(&derive_tileset())
+        .expect("archive");
+    buffer
+}
+
+#[test]
+fn test_archive_compression_is_derived_from_path() {
+    let derive = |p: &str| ArchiveCompression::from_path(p).ok();
+
+    assert_eq!(derive("out/tileset.tar"), Some(ArchiveCompression::None));
+    assert_eq!(derive("out/tileset.tar.gz"), Some(ArchiveCompression::Gzip));
+    assert_eq!(
+        derive("out/tileset.tar.zst"),
+        Some(ArchiveCompression::Zstd)
+    );
+    assert_eq!(derive("out/tileset.gz"), None);
+    assert_eq!(derive("out/tileset"), None);
+}
+
+#[test]
+fn test_compressed_archives_are_read_transparently() {
+    for archive_compression in [
+        ArchiveCompression::None,
+        ArchiveCompression::Gzip,
+        ArchiveCompression::Zstd,
+    ] {
+        for precompressed_entries in [false, true] {
+            let archive = write_archive(archive_compression, precompressed_entries);
+
+            let tileset = EtilesReader::new(Cursor::new(&archive))
+                .finish()
+                .expect("tileset");
+            assert_eq!(
+                tileset.tiled_content.cells().values().flatten().count(),
+                VERTEX_COUNT
+            );
+            let report = EtilesVerifier::new(Cursor::new(&archive))
+                .finish()
+                .expect("report");
+            assert!(report.is_valid());
+        }
+    }
+}
+
+#[test]
+fn test_compressed_archive_is_bit_identical_across_runs() {
+    for archive_compression in [ArchiveCompression::Gzip, ArchiveCompression::Zstd] {
+        assert!(
+            write_archive(archive_compression, true) == write_archive(archive_compression, true)
+        );
+    }
+}
+
+#[test]
+fn test_precompressed_entries_are_gzip_files() {
+    let archive = write_archive(ArchiveCompression::None, true);
+
+    let mut archive = tar::Archive::new(archive.as_slice());
+    for entry in archive.entries().expect("entries") {
+        let entry = entry.expect("entry");
+        let path = entry.path().expect("path").display().to_string();
+        assert!(path.ends_with(".gz"), "entry `{path}` is not precompressed");
+        let mut decoder = flate2::read::GzDecoder::new(entry);
+        std::io::copy(&mut decoder, &mut std::io::sink()).expect("gzip entry");
+    }
+}
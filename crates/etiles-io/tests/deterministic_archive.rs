@@ -0,0 +1,93 @@
+mod common;
+
+use common::{VertexAttributes, derive_tileset_from, derive_vertices_with};
+use etiles_io::{ContentLayer, EtilesWriter, TilesetStyle};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+
+fn write_archive(thread_count: usize) -> Vec<u8> {
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(thread_count)
+        .build()
+        .expect("thread pool");
+
+    thread_pool.install(|| {
+        let vertices = derive_vertices_with(
+            20_000,
+            VertexAttributes {
+                mixed_classifications: true,
+                times: true,
+            },
+        );
+        let mut tileset = derive_tileset_from(vertices);
+        tileset.root_transform = Isometry3::from_parts(
+            Translation3::new(4_160_000.0, 850_000.0, 4_750_000.0),
+            UnitQuaternion::from_euler_angles(0.1, 0.7, 0.2),
+        );
+
+        let mut buffer: Vec<u8> = Vec::new();
+        EtilesWriter::new(&mut buffer)
+            .with_content_layers(vec![
+                ContentLayer::new("ground", vec![2]),
+                ContentLayer::new("other", Vec::new()),
+            ])
+            .with_style(TilesetStyle::Classification)
+            .finish(&tileset)
+            .expect("archive");
+        buffer
+    })
+}
+
+fn read_entry_paths(archive: &[u8]) -> Vec<String> {
+    tar::Archive::new(archive)
+        .entries()
+        .expect("entries")
+        .map(|e| {
+            e.expect("entry")
+                .path()
+                .expect("path")
+                .display()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_archive_is_bit_identical_across_runs() {
+    let first_archive = write_archive(4);
+    let second_archive = write_archive(4);
+
+    assert!(first_archive == second_archive);
+}
+
+#[test]
+fn test_archive_is_bit_identical_across_thread_counts() {
+    let single_threaded_archive = write_archive(1);
+    let multi_threaded_archive = write_archive(8);
+
+    assert!(single_threaded_archive == multi_threaded_archive);
+}
+
+#[test]
+fn test_archive_entries_are_ordered() {
+    let entry_paths = read_entry_paths(&write_archive(2));
+
+    assert_eq!(entry_paths[0], "tileset.json");
+    assert_eq!(entry_paths[1], "style.json");
+    assert_eq!(entry_paths[2], "subtrees/0__0_0_0.subtree");
+    let first_content_index = entry_paths
+        .iter()
+        .position(|p| p.starts_with("content/"))
+        .expect("content");
+    assert!(
+        entry_paths[2..first_content_index]
+            .iter()
+            .all(|p| p.starts_with("subtrees/"))
+    );
+    assert_eq!(
+        entry_paths[first_content_index..first_content_index + 2],
+        [
+            "content/ground/pc_0__0_0_0.glb",
+            "content/other/pc_0__0_0_0.glb"
+        ]
+    );
+}
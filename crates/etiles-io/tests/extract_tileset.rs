@@ -0,0 +1,42 @@
+mod common;
+
+use common::{VERTEX_COUNT, derive_tileset};
+use eproj::SpatialReferenceIdentifier;
+use etiles_core::{LevelSelection, extract_point_cloud};
+use etiles_io::{EtilesReader, EtilesWriter};
+use nalgebra::Point3;
+
+#[test]
+fn test_extract_written_tileset() {
+    let tileset = derive_tileset();
+    let mut archive = Vec::new();
+    EtilesWriter::new(&mut archive)
+        .finish(&tileset)
+        .expect("archive");
+
+    let read_tileset = EtilesReader::new(archive.as_slice())
+        .finish()
+        .expect("tileset");
+    let point_cloud = extract_point_cloud(
+        &read_tileset,
+        SpatialReferenceIdentifier::Epsg4978,
+        LevelSelection::All,
+    )
+    .expect("point cloud");
+
+    assert_eq!(point_cloud.size(), VERTEX_COUNT);
+    let ecef_points: Vec<Point3<f64>> = tileset
+        .tiled_content
+        .cells()
+        .values()
+        .flatten()
+        .map(|v| tileset.root_transform * v.position)
+        .collect();
+    let (min, max) = ecef_points
+        .iter()
+        .fold((ecef_points[0], ecef_points[0]), |(min, max), p| {
+            (min.inf(p), max.sup(p))
+        });
+    assert!((point_cloud.point_data.get_local_min() - min).norm() < 1e-2);
+    assert!((point_cloud.point_data.get_local_max() - max).norm() < 1e-2);
+}
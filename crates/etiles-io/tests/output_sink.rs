@@ -0,0 +1,135 @@
+#![cfg(feature = "object-store")]
+
+mod common;
+
+use common::{derive_temporary_path, derive_tileset};
+use etiles_io::{DirectorySink, EtilesWriter, ObjectStoreSink, OutputSink};
+use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::path::Path as ObjectPath;
+use object_store::{Attribute, ObjectStore};
+use std::sync::Arc;
+
+#[test]
+fn test_directory_sink_writes_entries_as_files() {
+    let directory_path = derive_temporary_path("directory");
+    EtilesWriter::from_sink(DirectorySink::new(&directory_path).expect("directory"))
+        .with_manifest(true)
+        .finish(&derive_tileset())
+        .expect("directory");
+
+    let tileset_json: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(directory_path.join("tileset.json")).expect("tileset.json"),
+    )
+    .expect("json");
+    assert!(tileset_json["root"]["implicitTiling"].is_object());
+    assert!(directory_path.join("manifest.json").is_file());
+    assert!(directory_path.join("content/pc_0__0_0_0.glb").is_file());
+    assert!(directory_path.join("subtrees/0__0_0_0.subtree").is_file());
+
+    std::fs::remove_dir_all(directory_path).expect("removed directory");
+}
+
+#[test]
+fn test_object_store_sink_uploads_entries_below_prefix() {
+    let store = Arc::new(InMemory::new());
+    let sink =
+        ObjectStoreSink::new(store.clone(), ObjectPath::from("tilesets/city")).with_concurrency(2);
+    EtilesWriter::from_sink(sink)
+        .with_precompressed_entries(true)
+        .finish(&derive_tileset())
+        .expect("upload");
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime");
+    let tileset_object = runtime
+        .block_on(store.get(&ObjectPath::from("tilesets/city/tileset.json")))
+        .expect("tileset object");
+    assert_eq!(
+        tileset_object
+            .attributes
+            .get(&Attribute::ContentType)
+            .map(|v| v.as_ref()),
+        Some("application/json")
+    );
+    assert_eq!(
+        tileset_object
+            .attributes
+            .get(&Attribute::ContentEncoding)
+            .map(|v| v.as_ref()),
+        Some("gzip")
+    );
+    let content = runtime
+        .block_on(tileset_object.bytes())
+        .expect("tileset content");
+    assert_eq!(content[..2], [0x1f, 0x8b]);
+
+    let content_object = runtime
+        .block_on(store.head(&ObjectPath::from("tilesets/city/content/pc_0__0_0_0.glb")))
+        .expect("content object");
+    assert!(content_object.size > 0);
+    assert!(
+        runtime
+            .block_on(store.head(&ObjectPath::from(
+                "tilesets/city/content/pc_0__0_0_0.glb.gz"
+            )))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_object_store_sink_fails_on_failed_upload() {
+    let root_path = derive_temporary_path("object-store");
+    std::fs::create_dir_all(&root_path).expect("root directory");
+    // A file in place of the content directory lets every upload of a tile fail.
+    std::fs::write(root_path.join("content"), []).expect("blocking file");
+    let store = Arc::new(LocalFileSystem::new_with_prefix(&root_path).expect("store"));
+
+    let sink = ObjectStoreSink::new(store, ObjectPath::default());
+    let result = EtilesWriter::from_sink(sink).finish(&derive_tileset());
+    assert!(matches!(result, Err(etiles_io::Error::ObjectStore(_))));
+
+    std::fs::remove_dir_all(root_path).expect("removed root directory");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_object_store_sink_rejects_non_utf8_path() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::path::Path::new(std::ffi::OsStr::from_bytes(b"content/pc_\xff.glb"));
+    let mut sink = ObjectStoreSink::new(Arc::new(InMemory::new()), ObjectPath::default());
+
+    let result = sink.put(path, vec![0], None);
+    assert!(matches!(result, Err(etiles_io::Error::NonUtf8Path(p)) if p == path));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_object_store_sink_uploads_on_caller_runtime() {
+    let store = Arc::new(InMemory::new());
+    let sink =
+        ObjectStoreSink::new(store.clone(), ObjectPath::from("tilesets/city")).with_concurrency(2);
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+    runtime
+        .block_on(
+            etiles_io::AsyncEtilesWriter::from_sink(sink)
+                .with_precompressed_entries(true)
+                .finish(derive_tileset()),
+        )
+        .expect("upload");
+
+    let content_object = runtime
+        .block_on(store.head(&ObjectPath::from("tilesets/city/content/pc_0__0_0_0.glb")))
+        .expect("content object");
+    assert!(content_object.size > 0);
+    assert!(
+        runtime
+            .block_on(store.head(&ObjectPath::from("tilesets/city/tileset.json")))
+            .is_ok()
+    );
+}
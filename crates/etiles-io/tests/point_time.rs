@@ -0,0 +1,56 @@
+mod common;
+
+use common::{VertexAttributes, derive_vertices_with};
+use etiles_core::Vertex;
+use etiles_io::{AssetInfo, EncodableContent, read_gltf_tile};
+
+/// Seconds between the time reference of a multi-year tileset and the times of a tile.
+const EPOCH_OFFSET: f64 = 1e8;
+
+/// Vertices whose times lie an epoch offset after the time reference of the tileset.
+fn derive_late_vertices() -> Vec<Vertex> {
+    derive_vertices_with(
+        1_000,
+        VertexAttributes {
+            times: true,
+            ..Default::default()
+        },
+    )
+    .into_iter()
+    .map(|v| Vertex {
+        time: v.time.map(|t| t + EPOCH_OFFSET + 0.123_456),
+        ..v
+    })
+    .collect()
+}
+
+fn encode_and_decode(vertices: Vec<Vertex>) -> Vec<Vertex> {
+    let buffer = (&vertices)
+        .encode(&AssetInfo::default(), false)
+        .expect("tile");
+    read_gltf_tile(&buffer).expect("decoded tile")
+}
+
+#[test]
+fn test_times_keep_precision_far_from_tileset_time_reference() {
+    let vertices = derive_late_vertices();
+
+    let decoded_vertices = encode_and_decode(vertices.clone());
+
+    assert_eq!(decoded_vertices.len(), vertices.len());
+    for (vertex, decoded_vertex) in vertices.iter().zip(&decoded_vertices) {
+        let time_error = (vertex.time.unwrap() - decoded_vertex.time.expect("time")).abs();
+        assert!(time_error < 1e-5, "time error of {time_error}s");
+    }
+}
+
+#[test]
+fn test_repeated_roundtrips_do_not_change_times() {
+    let decoded_vertices = encode_and_decode(derive_late_vertices());
+
+    let redecoded_vertices = encode_and_decode(decoded_vertices.clone());
+
+    for (decoded_vertex, redecoded_vertex) in decoded_vertices.iter().zip(&redecoded_vertices) {
+        assert_eq!(decoded_vertex.time, redecoded_vertex.time);
+    }
+}
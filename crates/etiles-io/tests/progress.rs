@@ -0,0 +1,47 @@
+mod common;
+
+use common::derive_tileset;
+use etiles_core::{CancellationToken, Progress, ProgressPhase, ProgressReporter};
+use etiles_io::EtilesWriter;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_cancelled_token_stops_writing() {
+    let cancellation_token = CancellationToken::new();
+    cancellation_token.cancel();
+    let progress_reporter = ProgressReporter::new().with_cancellation_token(cancellation_token);
+
+    let result = EtilesWriter::new(Vec::new())
+        .with_progress_reporter(progress_reporter)
+        .finish(&derive_tileset());
+
+    assert!(matches!(
+        result,
+        Err(etiles_io::Error::EtilesError(
+            etiles_core::Error::Cancelled()
+        ))
+    ));
+}
+
+#[test]
+fn test_every_phase_of_writing_is_completed() {
+    let reported_progress: Arc<Mutex<Vec<Progress>>> = Arc::default();
+    let observed_progress = reported_progress.clone();
+    let progress_reporter = ProgressReporter::new()
+        .with_observer(move |p: &Progress| observed_progress.lock().expect("progress").push(*p));
+
+    EtilesWriter::new(Vec::new())
+        .with_progress_reporter(progress_reporter)
+        .finish(&derive_tileset())
+        .expect("archive");
+
+    let reported_progress = reported_progress.lock().expect("progress");
+    for phase in [ProgressPhase::Encode, ProgressPhase::Archive] {
+        let last_progress = reported_progress
+            .iter()
+            .rfind(|p| p.phase == phase)
+            .unwrap_or_else(|| panic!("no progress of phase {phase}"));
+        assert!(last_progress.total > 0);
+        assert_eq!(last_progress.completed, last_progress.total);
+    }
+}
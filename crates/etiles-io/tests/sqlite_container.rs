@@ -0,0 +1,117 @@
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use common::{VERTEX_COUNT, derive_temporary_path, derive_tileset};
+use etiles_io::{AssetInfo, EtilesReader, EtilesVerifier, EtilesWriter, SqliteContainer};
+use std::path::PathBuf;
+
+fn write_container(name: &str, precompressed_entries: bool) -> PathBuf {
+    let path = derive_temporary_path(&format!("{name}.3dtiles"));
+    EtilesWriter::from_sink(SqliteContainer::create(&path).expect("container"))
+        .with_asset_info(AssetInfo {
+            tileset_version: Some("2024-09".to_string()),
+            ..Default::default()
+        })
+        .with_precompressed_entries(precompressed_entries)
+        .with_manifest(true)
+        .finish(&derive_tileset())
+        .expect("container");
+    path
+}
+
+#[test]
+fn test_container_is_read_transparently() {
+    for precompressed_entries in [false, true] {
+        let path = write_container(
+            &format!("read-{precompressed_entries}"),
+            precompressed_entries,
+        );
+
+        let tileset = EtilesReader::new(SqliteContainer::open(&path).expect("container"))
+            .finish()
+            .expect("tileset");
+        assert_eq!(
+            tileset.tiled_content.cells().values().flatten().count(),
+            VERTEX_COUNT
+        );
+        let report = EtilesVerifier::new(SqliteContainer::open(&path).expect("container"))
+            .finish()
+            .expect("report");
+        assert!(report.is_valid());
+
+        std::fs::remove_file(path).expect("removed container");
+    }
+}
+
+#[test]
+fn test_container_entries_are_served_as_stored() {
+    let path = write_container("serve", true);
+    let container = SqliteContainer::open(&path).expect("container");
+
+    let tileset_entry = container
+        .read_entry("tileset.json")
+        .expect("query")
+        .expect("tileset entry");
+    assert_eq!(tileset_entry.content_encoding.as_deref(), Some("gzip"));
+    assert_eq!(tileset_entry.content[..2], [0x1f, 0x8b]);
+    assert!(
+        container
+            .read_entry("content/pc_0__0_0_0.glb")
+            .expect("query")
+            .is_some()
+    );
+    assert!(
+        container
+            .read_entry("missing.glb")
+            .expect("query")
+            .is_none()
+    );
+
+    let metadata = container.read_metadata().expect("metadata");
+    assert_eq!(metadata["tileset"], "tileset.json");
+    assert_eq!(metadata["tilesetVersion"], "2024-09");
+
+    drop(container);
+    std::fs::remove_file(path).expect("removed container");
+}
+
+#[test]
+fn test_request_paths_resolve_to_entries() {
+    let path = write_container("request", true);
+    let container = SqliteContainer::open(&path).expect("container");
+
+    let root_entry = container
+        .read_requested_entry("/")
+        .expect("query")
+        .expect("root entry");
+    assert_eq!(
+        Some(&root_entry),
+        container
+            .read_entry("tileset.json")
+            .expect("query")
+            .as_ref()
+    );
+    let content_entry = container
+        .read_requested_entry("/content/pc_0__0_0_0.glb?v=1")
+        .expect("query")
+        .expect("content entry");
+    assert_eq!(
+        content_entry.into_decoded_content().expect("decoded")[..4],
+        *b"glTF"
+    );
+    assert!(
+        container
+            .read_requested_entry("/missing.glb")
+            .expect("query")
+            .is_none()
+    );
+
+    let tileset_document: serde_json::Value =
+        serde_json::from_slice(&root_entry.into_decoded_content().expect("decoded"))
+            .expect("tileset document");
+    assert!(tileset_document.get("root").is_some());
+
+    drop(container);
+    std::fs::remove_file(path).expect("removed container");
+}
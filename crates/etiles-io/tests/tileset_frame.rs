@@ -0,0 +1,111 @@
+mod common;
+
+use common::{derive_tileset, derive_vertices};
+use etiles_core::{ColorGradient, Tileset};
+use etiles_io::{AssetInfo, EncodableContent, EtilesWriter, TilesetStyle, read_gltf_tile};
+use nalgebra::{Isometry3, Matrix3, Rotation3, Translation3, UnitQuaternion, Vector3};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Offset of the vertices of an ECEF tileset, which has the identity as root transform.
+const ECEF_OFFSET: Vector3<f64> = Vector3::new(4_160_000.123, 850_000.456, 4_750_000.789);
+
+fn read_entries(archive: &[u8]) -> HashMap<String, Vec<u8>> {
+    tar::Archive::new(archive)
+        .entries()
+        .expect("entries")
+        .map(|e| {
+            let mut entry = e.expect("entry");
+            let path = entry.path().expect("path").display().to_string();
+            let mut buffer = Vec::new();
+            entry.read_to_end(&mut buffer).expect("entry bytes");
+            (path, buffer)
+        })
+        .collect()
+}
+
+fn write_entries(tileset: &Tileset) -> HashMap<String, Vec<u8>> {
+    let mut buffer: Vec<u8> = Vec::new();
+    EtilesWriter::new(&mut buffer)
+        .with_style(TilesetStyle::Height {
+            gradient: ColorGradient::default(),
+        })
+        .finish(tileset)
+        .expect("archive");
+    read_entries(&buffer)
+}
+
+/// Returns the fixture tileset with its local frame along east, north and up at the equator and
+/// the prime meridian.
+fn derive_east_north_up_tileset() -> Tileset {
+    let mut tileset = derive_tileset();
+    let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[
+        Vector3::y(),
+        Vector3::z(),
+        Vector3::x(),
+    ]));
+    tileset.root_transform = Isometry3::from_parts(
+        Translation3::new(6_378_137.0, 0.0, 0.0),
+        UnitQuaternion::from_rotation_matrix(&rotation),
+    );
+    tileset
+}
+
+#[test]
+fn test_centered_tile_keeps_precision_far_from_origin() {
+    let vertices: Vec<etiles_core::Vertex> = derive_vertices()
+        .into_iter()
+        .map(|mut v| {
+            v.position += ECEF_OFFSET;
+            v
+        })
+        .collect();
+
+    let buffer = (&vertices)
+        .encode(&AssetInfo::default(), true)
+        .expect("tile");
+    let decoded_vertices = read_gltf_tile(&buffer).expect("decoded tile");
+
+    assert_eq!(decoded_vertices.len(), vertices.len());
+    for (vertex, decoded_vertex) in vertices.iter().zip(&decoded_vertices) {
+        assert!((vertex.position - decoded_vertex.position).norm() < 1e-4);
+    }
+}
+
+#[test]
+fn test_tileset_without_heights_omits_height_metadata_and_style() {
+    let mut tileset = derive_tileset();
+    tileset.root_transform = Isometry3::identity();
+    let entries = write_entries(&tileset);
+
+    assert!(!entries.contains_key("style.json"));
+    let tileset_json: serde_json::Value =
+        serde_json::from_slice(&entries["tileset.json"]).expect("json");
+    assert!(tileset_json["metadata"]["properties"]["minimumHeight"].is_null());
+    assert!(tileset_json["schema"]["classes"]["tile"]["properties"]["minimumHeight"].is_null());
+
+    let gltf = gltf::Gltf::from_slice(&entries["content/pc_0__0_0_0.glb"]).expect("glb");
+    let node = gltf.nodes().next().expect("node");
+    assert_ne!(node.transform().decomposed().0, [0.0; 3]);
+}
+
+#[test]
+fn test_tileset_with_up_axis_has_heights_and_style() {
+    let entries = write_entries(&derive_east_north_up_tileset());
+
+    assert!(entries.contains_key("style.json"));
+    let tileset_json: serde_json::Value =
+        serde_json::from_slice(&entries["tileset.json"]).expect("json");
+    let minimum_height = tileset_json["metadata"]["properties"]["minimumHeight"]
+        .as_f64()
+        .expect("minimum height");
+    let maximum_height = tileset_json["metadata"]["properties"]["maximumHeight"]
+        .as_f64()
+        .expect("maximum height");
+    assert!(minimum_height.abs() < 1e-3);
+    assert!((maximum_height - 4.0).abs() < 1e-3);
+
+    let gltf = gltf::Gltf::from_slice(&entries["content/pc_0__0_0_0.glb"]).expect("glb");
+    let node = gltf.nodes().next().expect("node");
+    assert_eq!(node.transform().decomposed().0, [0.0; 3]);
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::{SEED_NUMBER, VERTEX_COUNT, derive_temporary_path, derive_tileset};
+use etiles_core::Vertex;
+use etiles_io::{
+    EtilesReader, EtilesUpdater, EtilesVerifier, EtilesWriter, parse_content_filename,
+};
+use nalgebra::Point3;
+use palette::Srgb;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const MAXIMUM_POINTS_PER_OCTANT: u64 = 500;
+
+fn write_archive(name: &str) -> PathBuf {
+    let path = derive_temporary_path(&format!("{name}.tar"));
+    EtilesWriter::new(File::create(&path).expect("archive file"))
+        .finish(&derive_tileset())
+        .expect("archive");
+    path
+}
+
+fn read_entries(path: &Path) -> BTreeMap<String, Vec<u8>> {
+    let mut archive = tar::Archive::new(File::open(path).expect("archive file"));
+    archive
+        .entries()
+        .expect("entries")
+        .map(|entry| {
+            let mut entry = entry.expect("entry");
+            let path = entry.path().expect("path").display().to_string();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).expect("entry bytes");
+            (path, bytes)
+        })
+        .collect()
+}
+
+fn derive_inserted_vertices() -> Vec<Vertex> {
+    (0..10)
+        .map(|i| Vertex {
+            position: Point3::new(1.0 + 0.1 * i as f64, 1.0, 0.5),
+            color: Srgb::new(1.0, 0.0, 0.0),
+            normal: None,
+            classification: Some(2),
+            time: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_update_re_encodes_only_changed_octants() {
+    let path = write_archive("update-changed-octants");
+    let previous_entries = read_entries(&path);
+
+    let changed_octant_indices = derive_tileset()
+        .insert_vertices(
+            derive_inserted_vertices(),
+            MAXIMUM_POINTS_PER_OCTANT,
+            Some(SEED_NUMBER),
+        )
+        .expect("changed octants");
+    assert!(!changed_octant_indices.is_empty());
+
+    let mut updater = EtilesUpdater::from_path(&path).expect("updater");
+    updater
+        .insert_vertices(
+            derive_inserted_vertices(),
+            MAXIMUM_POINTS_PER_OCTANT,
+            Some(SEED_NUMBER),
+        )
+        .expect("insert");
+    updater.finish().expect("update");
+    let updated_entries = read_entries(&path);
+
+    let mut changed_content_count = 0;
+    for (entry_path, bytes) in &updated_entries {
+        let file_name = Path::new(entry_path)
+            .file_name()
+            .and_then(|f| f.to_str())
+            .expect("file name");
+        let Some(octant_index) = parse_content_filename(file_name) else {
+            continue;
+        };
+        if changed_octant_indices.contains(&octant_index) {
+            changed_content_count += 1;
+        } else {
+            assert_eq!(
+                previous_entries.get(entry_path),
+                Some(bytes),
+                "unchanged content `{entry_path}` was re-encoded"
+            );
+        }
+    }
+    assert!(changed_content_count > 0);
+
+    let tileset = EtilesReader::from_path(&path)
+        .expect("reader")
+        .finish()
+        .expect("tileset");
+    assert_eq!(
+        tileset.tiled_content.cells().values().flatten().count(),
+        VERTEX_COUNT + derive_inserted_vertices().len()
+    );
+    std::fs::remove_file(&path).expect("remove archive");
+}
+
+#[test]
+fn test_archive_survives_read_update_roundtrip() {
+    let path = write_archive("update-roundtrip");
+    let previous_entries = read_entries(&path);
+
+    EtilesUpdater::from_path(&path)
+        .expect("updater")
+        .finish()
+        .expect("update");
+    assert!(read_entries(&path) == previous_entries);
+
+    let mut updater = EtilesUpdater::from_path(&path).expect("updater");
+    updater
+        .insert_vertices(
+            derive_inserted_vertices(),
+            MAXIMUM_POINTS_PER_OCTANT,
+            Some(SEED_NUMBER),
+        )
+        .expect("insert");
+    updater.finish().expect("update");
+
+    let mut updater = EtilesUpdater::from_path(&path).expect("updater");
+    updater
+        .remove_positions(
+            derive_inserted_vertices()
+                .into_iter()
+                .map(|v| v.position)
+                .collect(),
+            0.001,
+        )
+        .expect("remove");
+    updater.finish().expect("update");
+
+    let tileset = EtilesReader::from_path(&path)
+        .expect("reader")
+        .finish()
+        .expect("tileset");
+    assert_eq!(
+        tileset.tiled_content.cells().values().flatten().count(),
+        VERTEX_COUNT
+    );
+    let report = EtilesVerifier::new(File::open(&path).expect("archive file"))
+        .finish()
+        .expect("report");
+    assert!(report.is_valid());
+    std::fs::remove_file(&path).expect("remove archive");
+}
+
+#[test]
+fn test_removing_unknown_positions_fails() {
+    let path = write_archive("update-unknown-positions");
+
+    let mut updater = EtilesUpdater::from_path(&path).expect("updater");
+    assert!(
+        updater
+            .remove_positions(vec![Point3::new(1.05, 1.0, 0.5)], 0.001)
+            .is_err()
+    );
+    assert_eq!(
+        updater
+            .tileset()
+            .tiled_content
+            .cells()
+            .values()
+            .flatten()
+            .count(),
+        VERTEX_COUNT
+    );
+    std::fs::remove_file(&path).expect("remove archive");
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::derive_tileset;
+use etiles_io::{EtilesVerifier, EtilesWriter};
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+fn write_archive(precompressed_entries: bool) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::new();
+    EtilesWriter::new(&mut buffer)
+        .with_precompressed_entries(precompressed_entries)
+        .with_manifest(true)
+        .finish(&derive_tileset())
+        .expect("archive");
+    buffer
+}
+
+/// Rewrites the archive with the content of the entry at the path replaced.
+fn replace_entry(archive: &[u8], path: &str, content: &[u8]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for entry in tar::Archive::new(archive).entries().expect("entries") {
+        let mut entry = entry.expect("entry");
+        let entry_path = entry.path().expect("path").display().to_string();
+        let mut buffer = Vec::new();
+        entry.read_to_end(&mut buffer).expect("entry bytes");
+        if entry_path == path {
+            buffer = content.to_vec();
+        }
+
+        let mut header = entry.header().clone();
+        header.set_size(buffer.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, entry_path, buffer.as_slice())
+            .expect("append");
+    }
+    builder.into_inner().expect("archive")
+}
+
+/// Rewrites the size field in the header of the entry at the path, with a checksum that matches
+/// the damaged header if `update_checksum` is set.
+fn damage_entry_size(archive: &[u8], path: &str, size: u64, update_checksum: bool) -> Vec<u8> {
+    let header_position = tar::Archive::new(archive)
+        .entries()
+        .expect("entries")
+        .map(|e| e.expect("entry"))
+        .find(|e| e.path().expect("path").display().to_string() == path)
+        .expect("entry")
+        .raw_header_position() as usize;
+    let header_range = header_position..header_position + 512;
+
+    let mut header = tar::Header::from_byte_slice(&archive[header_range.clone()]).clone();
+    header.set_size(size);
+    if update_checksum {
+        header.set_cksum();
+    }
+    let mut archive = archive.to_vec();
+    archive[header_range].copy_from_slice(header.as_bytes());
+    archive
+}
+
+fn entry_index(archive: &[u8], path: &str) -> usize {
+    tar::Archive::new(archive)
+        .entries()
+        .expect("entries")
+        .position(|e| {
+            e.expect("entry")
+                .path()
+                .expect("path")
+                .display()
+                .to_string()
+                == path
+        })
+        .expect("entry index")
+}
+
+fn first_entry_path(archive: &[u8], prefix: &str) -> String {
+    tar::Archive::new(archive)
+        .entries()
+        .expect("entries")
+        .map(|e| {
+            e.expect("entry")
+                .path()
+                .expect("path")
+                .display()
+                .to_string()
+        })
+        .find(|p| p.starts_with(prefix))
+        .expect("entry path")
+}
+
+#[test]
+fn test_corrupt_tileset_document_is_reported() {
+    let archive = replace_entry(&write_archive(false), "tileset.json", b"{");
+
+    let report = EtilesVerifier::new(Cursor::new(archive))
+        .finish()
+        .expect("report");
+    assert!(!report.is_valid());
+    assert!(
+        report
+            .corrupted_entries
+            .contains(&PathBuf::from("tileset.json"))
+    );
+}
+
+#[test]
+fn test_undecompressable_entry_is_reported() {
+    let archive = write_archive(true);
+    let content_path = first_entry_path(&archive, "content/");
+    let archive = replace_entry(&archive, &content_path, b"not gzip");
+
+    let report = EtilesVerifier::new(Cursor::new(archive))
+        .finish()
+        .expect("report");
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.corrupted_entries,
+        vec![PathBuf::from(content_path.strip_suffix(".gz").expect("gz"))]
+    );
+    assert!(report.missing_available_entries.is_empty());
+}
+
+#[test]
+fn test_truncated_archive_is_reported() {
+    let mut archive = write_archive(false);
+    archive.truncate(archive.len() / 2 + 100);
+
+    let report = EtilesVerifier::new(Cursor::new(archive))
+        .finish()
+        .expect("report");
+    assert!(!report.is_valid());
+    assert_eq!(report.corrupted_entries.len(), 1);
+}
+
+#[test]
+fn test_entry_with_damaged_size_is_reported() {
+    let archive = write_archive(false);
+    let content_path = first_entry_path(&archive, "content/");
+    let archive = damage_entry_size(&archive, &content_path, 1 << 40, true);
+
+    let report = EtilesVerifier::new(Cursor::new(archive))
+        .finish()
+        .expect("report");
+    assert!(!report.is_valid());
+    assert_eq!(report.corrupted_entries, vec![PathBuf::from(content_path)]);
+}
+
+#[test]
+fn test_undecodable_entry_header_is_reported() {
+    let archive = write_archive(false);
+    let content_path = first_entry_path(&archive, "content/");
+    let content_index = entry_index(&archive, &content_path);
+    let archive = damage_entry_size(&archive, &content_path, 1 << 40, false);
+
+    let report = EtilesVerifier::new(Cursor::new(archive))
+        .finish()
+        .expect("report");
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.corrupted_entries,
+        vec![PathBuf::from(format!("<header of entry {content_index}>"))]
+    );
+}
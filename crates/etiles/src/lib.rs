@@ -3,6 +3,14 @@
 //! Only writing of 3D Tiles 1.1 datasets is currently supported.
 //!
 
-pub use etiles_core::{Error, Tileset};
+pub use etiles_core::{
+    CancellationToken, ClipPolygon, ColorGradient, ColorMode, Error, GeoRaster, LevelSelection,
+    LocalPlacement, NormalEstimation, NormalOrientation, PhaseProgress, PointFilter, Progress,
+    ProgressObserver, ProgressPhase, ProgressReporter, RadiusOutlierRemoval, RasterSampling,
+    StatisticalOutlierRemoval, Tileset, TilesetAlignment, TilesetBuilder, TilesetFrame,
+    TilesetOrigin, Vertex, VertexSchema, colorize_point_cloud, colorize_point_cloud_from_rasters,
+    downsample_voxel_grid, estimate_normals, extract_local_point_cloud, extract_point_cloud,
+    filter_point_cloud, has_normals, remove_radius_outliers, remove_statistical_outliers,
+};
 
 pub use etiles_io as io;